@@ -241,6 +241,7 @@ pub struct Use {
     pub span: Span,
     pub common_path: Vec<UsePathComponent>,
     pub target: UseTargetDescriptor,
+    pub allow: Vec<Name>,
 }
 
 #[derive(Clone, Debug)]
@@ -634,8 +635,12 @@ pub struct Function {
     pub visibility: Visibility,
     pub is_static: bool,
     pub is_test: bool,
+    pub is_serial: bool,
+    pub allow: Vec<Name>,
     pub internal: bool,
     pub is_constructor: bool,
+    // Statements that failed to parse were left out of the body.
+    pub has_errors: bool,
 
     pub params: Vec<Param>,
 
@@ -664,14 +669,24 @@ impl Modifiers {
         self.0.iter().find(|el| el.value == modifier).is_some()
     }
 
-    pub fn add(&mut self, modifier: Modifier, pos: Position, span: Span) {
+    pub fn add(&mut self, modifier: Modifier, pos: Position, span: Span, args: Vec<Name>) {
         self.0.push(ModifierElement {
             value: modifier,
             pos,
             span,
+            args,
         });
     }
 
+    // names of all lints listed in @allow(...)
+    pub fn allowed(&self) -> Vec<Name> {
+        self.0
+            .iter()
+            .filter(|el| el.value == Modifier::Allow)
+            .flat_map(|el| el.args.iter().cloned())
+            .collect()
+    }
+
     pub fn iter(&self) -> Iter<ModifierElement> {
         self.0.iter()
     }
@@ -683,6 +698,7 @@ pub struct ModifierElement {
     pub value: Modifier,
     pub pos: Position,
     pub span: Span,
+    pub args: Vec<Name>,
 }
 
 #[derive(Clone, Debug)]
@@ -722,6 +738,8 @@ pub enum Modifier {
     Pub,
     Static,
     Test,
+    Serial,
+    Allow,
     OptimizeImmediately,
 }
 
@@ -732,6 +750,8 @@ impl Modifier {
             "pub" => Some(Modifier::Pub),
             "static" => Some(Modifier::Static),
             "test" => Some(Modifier::Test),
+            "serial" => Some(Modifier::Serial),
+            "allow" => Some(Modifier::Allow),
             "optimizeImmediately" => Some(Modifier::OptimizeImmediately),
             _ => None,
         }
@@ -743,6 +763,8 @@ impl Modifier {
             Modifier::Pub => "pub",
             Modifier::Static => "static",
             Modifier::Test => "test",
+            Modifier::Serial => "serial",
+            Modifier::Allow => "allow",
             Modifier::OptimizeImmediately => "optimizeImmediately",
         }
     }
@@ -814,6 +836,7 @@ impl Stmt {
         id: NodeId,
         pos: Position,
         span: Span,
+        pattern: Option<Box<MatchPattern>>,
         cond: Box<Expr>,
         block: Box<Stmt>,
     ) -> Stmt {
@@ -822,6 +845,7 @@ impl Stmt {
             pos,
             span,
 
+            pattern,
             cond,
             block,
         })
@@ -1094,6 +1118,8 @@ pub struct StmtWhileType {
     pub pos: Position,
     pub span: Span,
 
+    // Set for `while let`, `cond` is then the value matched against it.
+    pub pattern: Option<Box<MatchPattern>>,
     pub cond: Box<Expr>,
     pub block: Box<Stmt>,
 }
@@ -1252,6 +1278,7 @@ pub enum Expr {
     Tuple(ExprTupleType),
     Paren(ExprParenType),
     Match(ExprMatchType),
+    Try(ExprTryType),
 }
 
 impl Expr {
@@ -1276,6 +1303,7 @@ impl Expr {
         id: NodeId,
         pos: Position,
         span: Span,
+        pattern: Option<Box<MatchPattern>>,
         cond: Box<Expr>,
         then_block: Box<Expr>,
         else_block: Option<Box<Expr>>,
@@ -1285,6 +1313,7 @@ impl Expr {
             pos,
             span,
 
+            pattern,
             cond,
             then_block,
             else_block,
@@ -1307,6 +1336,16 @@ impl Expr {
         })
     }
 
+    pub fn create_try(id: NodeId, pos: Position, span: Span, expr: Box<Expr>) -> Expr {
+        Expr::Try(ExprTryType {
+            id,
+            pos,
+            span,
+
+            expr,
+        })
+    }
+
     pub fn create_un(id: NodeId, pos: Position, span: Span, op: UnOp, opnd: Box<Expr>) -> Expr {
         Expr::Un(ExprUnType {
             id,
@@ -1410,13 +1449,20 @@ impl Expr {
         })
     }
 
-    pub fn create_template(id: NodeId, pos: Position, span: Span, parts: Vec<Box<Expr>>) -> Expr {
+    pub fn create_template(
+        id: NodeId,
+        pos: Position,
+        span: Span,
+        parts: Vec<Box<Expr>>,
+        specs: Vec<Option<FormatSpec>>,
+    ) -> Expr {
         Expr::Template(ExprTemplateType {
             id,
             pos,
             span,
 
             parts,
+            specs,
         })
     }
 
@@ -1584,6 +1630,27 @@ impl Expr {
         }
     }
 
+    pub fn to_match(&self) -> Option<&ExprMatchType> {
+        match *self {
+            Expr::Match(ref val) => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn to_try(&self) -> Option<&ExprTryType> {
+        match *self {
+            Expr::Try(ref val) => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn is_try(&self) -> bool {
+        match *self {
+            Expr::Try(_) => true,
+            _ => false,
+        }
+    }
+
     pub fn to_ident(&self) -> Option<&ExprIdentType> {
         match *self {
             Expr::Ident(ref val) => Some(val),
@@ -1854,6 +1921,7 @@ impl Expr {
             Expr::Tuple(ref val) => val.pos,
             Expr::Paren(ref val) => val.pos,
             Expr::Match(ref val) => val.pos,
+            Expr::Try(ref val) => val.pos,
         }
     }
 
@@ -1880,6 +1948,7 @@ impl Expr {
             Expr::Tuple(ref val) => val.span,
             Expr::Paren(ref val) => val.span,
             Expr::Match(ref val) => val.span,
+            Expr::Try(ref val) => val.span,
         }
     }
 
@@ -1906,6 +1975,7 @@ impl Expr {
             Expr::Tuple(ref val) => val.id,
             Expr::Paren(ref val) => val.id,
             Expr::Match(ref val) => val.id,
+            Expr::Try(ref val) => val.id,
         }
     }
 }
@@ -1916,6 +1986,8 @@ pub struct ExprIfType {
     pub pos: Position,
     pub span: Span,
 
+    // Set for `if let`, `cond` is then the value matched against it.
+    pub pattern: Option<Box<MatchPattern>>,
     pub cond: Box<Expr>,
     pub then_block: Box<Expr>,
     pub else_block: Option<Box<Expr>>,
@@ -2008,6 +2080,144 @@ pub struct ExprTemplateType {
     pub span: Span,
 
     pub parts: Vec<Box<Expr>>,
+    // Format spec for each part, always None for the string literals.
+    pub specs: Vec<Option<FormatSpec>>,
+}
+
+// Format spec of a template part like `${x:>8.3}`, with the syntax
+// `[[fill]align][+][0][width][.precision][x|X|b|o]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatSpec {
+    pub pos: Position,
+    pub value: String,
+
+    pub fill: char,
+    pub align: Option<FormatAlign>,
+    pub sign_plus: bool,
+    pub zero_pad: bool,
+    pub width: Option<u32>,
+    pub precision: Option<u32>,
+    pub kind: FormatKind,
+}
+
+impl FormatSpec {
+    pub fn parse(pos: Position, value: &str) -> Option<FormatSpec> {
+        let chars: Vec<char> = value.chars().collect();
+        let mut idx = 0;
+
+        let mut fill = ' ';
+        let mut align = None;
+
+        if chars.len() >= 2 && FormatAlign::from_char(chars[1]).is_some() {
+            fill = chars[0];
+            align = FormatAlign::from_char(chars[1]);
+            idx = 2;
+        } else if !chars.is_empty() && FormatAlign::from_char(chars[0]).is_some() {
+            align = FormatAlign::from_char(chars[0]);
+            idx = 1;
+        }
+
+        let sign_plus = chars.get(idx) == Some(&'+');
+        if sign_plus {
+            idx += 1;
+        }
+
+        let zero_pad = chars.get(idx) == Some(&'0');
+        if zero_pad {
+            idx += 1;
+        }
+
+        let width = parse_format_number(&chars, &mut idx)?;
+
+        let precision = if chars.get(idx) == Some(&'.') {
+            idx += 1;
+            Some(parse_format_number(&chars, &mut idx)??)
+        } else {
+            None
+        };
+
+        let kind = match chars.get(idx) {
+            Some('x') => FormatKind::Hex,
+            Some('X') => FormatKind::UpperHex,
+            Some('b') => FormatKind::Binary,
+            Some('o') => FormatKind::Octal,
+            Some(_) => return None,
+            None => FormatKind::Display,
+        };
+
+        if kind != FormatKind::Display {
+            idx += 1;
+        }
+
+        if idx != chars.len() {
+            return None;
+        }
+
+        Some(FormatSpec {
+            pos,
+            value: value.into(),
+            fill,
+            align,
+            sign_plus,
+            zero_pad,
+            width,
+            precision,
+            kind,
+        })
+    }
+
+    // Whether the spec only pads the value, which works for every type.
+    pub fn is_padding_only(&self) -> bool {
+        !self.sign_plus
+            && !self.zero_pad
+            && self.precision.is_none()
+            && self.kind == FormatKind::Display
+    }
+}
+
+// Parses an optional number of at most 9 digits, like Formatter::parse()
+// in string.dora.
+fn parse_format_number(chars: &[char], idx: &mut usize) -> Option<Option<u32>> {
+    let start = *idx;
+    let mut value: Option<u32> = None;
+
+    while let Some(digit) = chars.get(*idx).and_then(|ch| ch.to_digit(10)) {
+        value = Some(value.unwrap_or(0) * 10 + digit);
+        *idx += 1;
+
+        if *idx - start > 9 {
+            return None;
+        }
+    }
+
+    Some(value)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FormatAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl FormatAlign {
+    fn from_char(ch: char) -> Option<FormatAlign> {
+        match ch {
+            '<' => Some(FormatAlign::Left),
+            '^' => Some(FormatAlign::Center),
+            '>' => Some(FormatAlign::Right),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FormatKind {
+    Display,
+    Hex,
+    UpperHex,
+    Binary,
+    Octal,
 }
 
 #[derive(Clone, Debug)]
@@ -2085,6 +2295,15 @@ pub struct ExprParenType {
     pub expr: Box<Expr>,
 }
 
+#[derive(Clone, Debug)]
+pub struct ExprTryType {
+    pub id: NodeId,
+    pub pos: Position,
+    pub span: Span,
+
+    pub expr: Box<Expr>,
+}
+
 #[derive(Clone, Debug)]
 pub struct ExprMatchType {
     pub id: NodeId,
@@ -2116,21 +2335,18 @@ pub struct MatchPattern {
 #[derive(Clone, Debug)]
 pub enum MatchPatternData {
     Underscore,
+    Lit(Box<Expr>),
     Ident(MatchPatternIdent),
+    Tuple(Vec<MatchPattern>),
+    Alt(Vec<MatchPattern>),
 }
 
+// Either an enum variant with optional subpatterns or, when the path is a
+// single name not referring to a variant, a binding.
 #[derive(Clone, Debug)]
 pub struct MatchPatternIdent {
     pub path: Path,
-    pub params: Option<Vec<MatchPatternParam>>,
-}
-
-#[derive(Clone, Debug)]
-pub struct MatchPatternParam {
-    pub id: NodeId,
-    pub pos: Position,
-    pub span: Span,
-    pub name: Option<Name>,
+    pub params: Option<Vec<MatchPattern>>,
     pub mutable: bool,
 }
 
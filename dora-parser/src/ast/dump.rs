@@ -493,6 +493,7 @@ impl<'a> AstDumper<'a> {
             Expr::Tuple(ref expr) => self.dump_expr_tuple(expr),
             Expr::Paren(ref expr) => self.dump_expr_paren(expr),
             Expr::Match(ref expr) => self.dump_expr_match(expr),
+            Expr::Try(ref expr) => self.dump_expr_try(expr),
         }
     }
 
@@ -577,8 +578,12 @@ impl<'a> AstDumper<'a> {
     fn dump_expr_template(&mut self, tmpl: &ExprTemplateType) {
         dump!(self, "template @ {} {}", tmpl.pos, tmpl.id);
         self.indent(|d| {
-            for part in &tmpl.parts {
-                d.dump_expr(part)
+            for (part, spec) in tmpl.parts.iter().zip(&tmpl.specs) {
+                d.dump_expr(part);
+
+                if let Some(spec) = spec {
+                    dump!(d, "format spec {:?} @ {}", spec.value, spec.pos);
+                }
             }
         });
     }
@@ -661,6 +666,13 @@ impl<'a> AstDumper<'a> {
         });
     }
 
+    fn dump_expr_try(&mut self, expr: &ExprTryType) {
+        dump!(self, "try @ {} {}", expr.pos, expr.id);
+        self.indent(|d| {
+            d.dump_expr(&expr.expr);
+        });
+    }
+
     fn dump_expr_type_param(&mut self, expr: &ExprTypeParamType) {
         dump!(self, "type param @ {} {}", expr.pos, expr.id);
 
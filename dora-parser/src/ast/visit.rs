@@ -349,6 +349,10 @@ pub fn walk_expr<V: Visitor>(v: &mut V, e: &Expr) {
             v.visit_expr(&value.expr);
         }
 
+        Expr::Try(ref value) => {
+            v.visit_expr(&value.expr);
+        }
+
         Expr::This(_) => {}
         Expr::LitChar(_) => {}
         Expr::LitInt(_) => {}
@@ -90,6 +90,9 @@ impl<'a> BuilderFct {
             internal: false,
             is_constructor: self.is_constructor,
             is_test: false,
+            is_serial: false,
+            has_errors: false,
+            allow: Vec::new(),
             params: self.params,
             return_type: self.return_type,
             block: self.block,
@@ -55,6 +55,7 @@ impl Lexer {
                 return self.read_operator();
             } else {
                 let ch = ch.unwrap();
+                self.read_char();
 
                 return Err(ParseErrorAndPos::new(pos, ParseError::UnknownChar(ch)));
             }
@@ -221,6 +222,34 @@ impl Lexer {
         self.read_string(false)
     }
 
+    // Reads the format spec of a template part after the `:` up to the
+    // closing `}`, which is left for the next token.
+    pub fn read_format_spec(&mut self) -> Result<Token, ParseErrorAndPos> {
+        let pos = self.reader.pos();
+        let idx = self.reader.idx();
+        let mut value = String::new();
+
+        while let Some(ch) = self.curr() {
+            if ch == '}' {
+                let ttype = TokenKind::FormatSpec(value);
+                let span = self.span_from(idx);
+                return Ok(Token::new(ttype, pos, span));
+            }
+
+            if is_quote(Some(ch)) || is_newline(Some(ch)) {
+                break;
+            }
+
+            value.push(ch);
+            self.read_char();
+        }
+
+        Err(ParseErrorAndPos::new(
+            pos,
+            ParseError::UnclosedStringTemplate,
+        ))
+    }
+
     fn read_operator(&mut self) -> Result<Token, ParseErrorAndPos> {
         let pos = self.reader.pos();
         let idx = self.reader.idx();
@@ -356,6 +385,7 @@ impl Lexer {
                 }
             }
             '@' => TokenKind::At,
+            '?' => TokenKind::QuestionMark,
 
             _ => {
                 return Err(ParseErrorAndPos::new(pos, ParseError::UnknownChar(ch)));
@@ -533,7 +563,7 @@ fn is_char_quote(ch: Option<char>) -> bool {
 }
 
 fn is_operator(ch: Option<char>) -> bool {
-    ch.map(|ch| "^+-*/%&|,=!~;:.()[]{}<>@".contains(ch))
+    ch.map(|ch| "^+-*/%&|,=!~;:.()[]{}<>@?".contains(ch))
         .unwrap_or(false)
 }
 
@@ -1114,5 +1144,10 @@ mod tests {
         assert_tok(&mut reader, TokenKind::GtGtGt, 1, 5);
         assert_tok(&mut reader, TokenKind::Underscore, 1, 8);
         assert_tok(&mut reader, TokenKind::ColonColon, 1, 9);
+
+        let mut reader = Lexer::from_str("x?.y");
+        assert_tok(&mut reader, TokenKind::Identifier("x".into()), 1, 1);
+        assert_tok(&mut reader, TokenKind::QuestionMark, 1, 2);
+        assert_tok(&mut reader, TokenKind::Dot, 1, 3);
     }
 }
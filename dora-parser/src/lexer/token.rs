@@ -8,6 +8,7 @@ pub enum TokenKind {
     // literals
     StringTail(String),
     StringExpr(String),
+    FormatSpec(String),
     LitChar(char),
     LitInt(String, IntBase, IntSuffix),
     LitFloat(String, FloatSuffix),
@@ -91,6 +92,7 @@ pub enum TokenKind {
     Colon,
     ColonColon,
     At,
+    QuestionMark,
     Arrow,
     DoubleArrow,
 
@@ -115,6 +117,7 @@ impl TokenKind {
             // literals
             TokenKind::StringTail(_) => "string tail",
             TokenKind::StringExpr(_) => "string epxr",
+            TokenKind::FormatSpec(_) => "format spec",
             TokenKind::LitInt(_, _, suffix) => match suffix {
                 IntSuffix::UInt8 => "byte number",
                 IntSuffix::Int32 => "int32 number",
@@ -205,6 +208,7 @@ impl TokenKind {
             TokenKind::Colon => ":",
             TokenKind::ColonColon => "::",
             TokenKind::At => "@",
+            TokenKind::QuestionMark => "?",
             TokenKind::Arrow => "->",
             TokenKind::DoubleArrow => "=>",
 
@@ -288,6 +292,7 @@ impl Token {
 
             TokenKind::StringTail(ref val) => format!("\"{}\" tail", &val),
             TokenKind::StringExpr(ref val) => format!("\"{}\" expr", &val),
+            TokenKind::FormatSpec(ref val) => format!("format spec `{}`", &val),
 
             TokenKind::Identifier(ref val) => val.clone(),
 
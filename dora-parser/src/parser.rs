@@ -22,6 +22,8 @@ pub struct Parser<'a> {
     param_idx: u32,
     in_class_or_module: bool,
     last_end: Option<u32>,
+    depth: u32,
+    errors: Vec<ParseErrorAndPos>,
 }
 
 type ExprResult = Result<Box<Expr>, ParseErrorAndPos>;
@@ -56,6 +58,8 @@ impl<'a> Parser<'a> {
             param_idx: 0,
             in_class_or_module: false,
             last_end: Some(0),
+            depth: 0,
+            errors: Vec::new(),
         };
 
         parser
@@ -65,17 +69,42 @@ impl<'a> Parser<'a> {
         self.id_generator.next()
     }
 
-    pub fn parse(mut self) -> Result<ast::File, ParseErrorAndPos> {
-        self.init()?;
+    pub fn parse(self) -> Result<ast::File, ParseErrorAndPos> {
+        let (ast_file, mut errors) = self.parse_with_errors();
+
+        if errors.is_empty() {
+            Ok(ast_file)
+        } else {
+            Err(errors.remove(0))
+        }
+    }
+
+    // Parses the whole file even if it contains errors. Elements that can't
+    // be parsed are left out of the returned file, statements that can't be
+    // parsed are left out of their function.
+    pub fn parse_with_errors(mut self) -> (ast::File, Vec<ParseErrorAndPos>) {
+        while let Err(error) = self.init() {
+            self.errors.push(error);
+        }
+
         let mut elements = vec![];
 
         while !self.token.is_eof() {
-            elements.push(self.parse_top_level_element()?);
+            let start = self.token.span.start();
+
+            match self.parse_top_level_element() {
+                Ok(element) => elements.push(element),
+
+                Err(error) => {
+                    self.errors.push(error);
+                    self.recover_top_level_element(start);
+                }
+            }
         }
 
         let ast_file = ast::File { elements };
 
-        Ok(ast_file)
+        (ast_file, self.errors)
     }
 
     fn init(&mut self) -> Result<(), ParseErrorAndPos> {
@@ -95,6 +124,8 @@ impl<'a> Parser<'a> {
                         Modifier::Internal,
                         Modifier::OptimizeImmediately,
                         Modifier::Test,
+                        Modifier::Serial,
+                        Modifier::Allow,
                         Modifier::Pub,
                     ],
                 )?;
@@ -162,8 +193,8 @@ impl<'a> Parser<'a> {
             }
 
             TokenKind::Use => {
-                self.restrict_modifiers(&modifiers, &[Modifier::Pub])?;
-                let use_stmt = self.parse_use()?;
+                self.restrict_modifiers(&modifiers, &[Modifier::Pub, Modifier::Allow])?;
+                let use_stmt = self.parse_use(&modifiers)?;
                 Ok(Elem::Use(Arc::new(use_stmt)))
             }
 
@@ -205,9 +236,10 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_use(&mut self) -> Result<Use, ParseErrorAndPos> {
+    fn parse_use(&mut self, modifiers: &Modifiers) -> Result<Use, ParseErrorAndPos> {
         self.expect_token(TokenKind::Use)?;
-        let use_declaration = self.parse_use_inner()?;
+        let mut use_declaration = self.parse_use_inner()?;
+        use_declaration.allow = modifiers.allowed();
         self.expect_semicolon()?;
 
         Ok(use_declaration)
@@ -251,6 +283,7 @@ impl<'a> Parser<'a> {
             span,
             common_path: path,
             target,
+            allow: Vec::new(),
         })
     }
 
@@ -435,7 +468,12 @@ impl<'a> Parser<'a> {
 
         while !self.token.is(TokenKind::RBrace) {
             let modifiers = self.parse_annotation_usages()?;
-            let mods = &[Modifier::Static, Modifier::Internal, Modifier::Pub];
+            let mods = &[
+                Modifier::Static,
+                Modifier::Internal,
+                Modifier::Pub,
+                Modifier::Allow,
+            ];
             self.restrict_modifiers(&modifiers, mods)?;
 
             let method = self.parse_function(&modifiers)?;
@@ -473,6 +511,8 @@ impl<'a> Parser<'a> {
         self.expect_token(TokenKind::Colon)?;
         let data_type = self.parse_type()?;
 
+        let errors = self.errors.len();
+
         let expr = if self.token.is(TokenKind::Eq) {
             self.advance_token()?;
             Some(self.parse_expression()?)
@@ -495,7 +535,8 @@ impl<'a> Parser<'a> {
         };
 
         if let Some(expr) = expr {
-            let initializer = self.generate_global_initializer(&global, expr);
+            let mut initializer = self.generate_global_initializer(&global, expr);
+            initializer.has_errors = self.errors.len() > errors;
             global.initializer = Some(Arc::new(initializer));
         }
 
@@ -514,7 +555,7 @@ impl<'a> Parser<'a> {
 
         while !self.token.is(TokenKind::RBrace) {
             let modifiers = self.parse_annotation_usages()?;
-            let mods = &[Modifier::Static];
+            let mods = &[Modifier::Static, Modifier::Allow];
             self.restrict_modifiers(&modifiers, mods)?;
 
             let method = self.parse_function(&modifiers)?;
@@ -795,6 +836,8 @@ impl<'a> Parser<'a> {
                 "pub" => Modifier::Pub,
                 "static" => Modifier::Static,
                 "Test" => Modifier::Test,
+                "serial" => Modifier::Serial,
+                "allow" => Modifier::Allow,
                 "optimizeImmediately" => Modifier::OptimizeImmediately,
                 annotation => {
                     return Err(ParseErrorAndPos::new(
@@ -811,7 +854,19 @@ impl<'a> Parser<'a> {
                 ));
             }
 
-            modifiers.add(modifier, self.token.position, self.token.span);
+            let pos = self.token.position;
+            let span = self.token.span;
+
+            let args = if modifier == Modifier::Allow {
+                self.expect_token(TokenKind::LParen)?;
+                self.parse_list(TokenKind::Comma, TokenKind::RParen, |p| {
+                    p.expect_identifier()
+                })?
+            } else {
+                Vec::new()
+            };
+
+            modifiers.add(modifier, pos, span, args);
         }
 
         Ok(modifiers)
@@ -845,6 +900,7 @@ impl<'a> Parser<'a> {
         let type_params = self.parse_type_params()?;
         let params = self.parse_function_params()?;
         let return_type = self.parse_function_type()?;
+        let errors = self.errors.len();
         let block = self.parse_function_block()?;
         let span = self.span_from(start);
 
@@ -861,6 +917,9 @@ impl<'a> Parser<'a> {
             internal: modifiers.contains(Modifier::Internal),
             is_constructor: false,
             is_test: modifiers.contains(Modifier::Test),
+            is_serial: modifiers.contains(Modifier::Serial),
+            allow: modifiers.allowed(),
+            has_errors: self.errors.len() > errors,
             params,
             return_type,
             block,
@@ -1204,7 +1263,25 @@ impl<'a> Parser<'a> {
         let mut expr = None;
 
         while !self.token.is(TokenKind::RBrace) && !self.token.is_eof() {
-            let stmt_or_expr = self.parse_statement_or_expression()?;
+            // the closing brace is most likely missing
+            if self.is_top_level_element_start() && self.token.position.column == 1 {
+                break;
+            }
+
+            let depth = self.depth;
+
+            let stmt_or_expr = match self.parse_statement_or_expression() {
+                Ok(stmt_or_expr) => stmt_or_expr,
+                Err(error) => {
+                    self.errors.push(error);
+
+                    if self.recover_statement(depth) {
+                        continue;
+                    } else {
+                        break;
+                    }
+                }
+            };
 
             match stmt_or_expr {
                 StmtOrExpr::Stmt(stmt) => stmts.push(stmt),
@@ -1274,6 +1351,7 @@ impl<'a> Parser<'a> {
         let start = self.token.span.start();
         let pos = self.expect_token(TokenKind::If)?.position;
 
+        let pattern = self.parse_let_pattern_condition()?;
         let cond = self.parse_expression()?;
 
         let then_block = self.parse_block()?;
@@ -1296,12 +1374,28 @@ impl<'a> Parser<'a> {
             self.generate_id(),
             pos,
             span,
+            pattern,
             cond,
             then_block,
             else_block,
         )))
     }
 
+    // Parses the `let <pattern> =` part of `if let` and `while let`.
+    fn parse_let_pattern_condition(
+        &mut self,
+    ) -> Result<Option<Box<MatchPattern>>, ParseErrorAndPos> {
+        if !self.token.is(TokenKind::Let) {
+            return Ok(None);
+        }
+
+        self.advance_token()?;
+        let pattern = self.parse_match_pattern_alt()?;
+        self.expect_token(TokenKind::Eq)?;
+
+        Ok(Some(Box::new(pattern)))
+    }
+
     fn parse_match(&mut self) -> ExprResult {
         let start = self.token.span.start();
         let pos = self.expect_token(TokenKind::Match)?.position;
@@ -1371,24 +1465,87 @@ impl<'a> Parser<'a> {
         let start = self.token.span.start();
         let pos = self.token.position;
 
-        let data = if self.token.is(TokenKind::Underscore) {
-            self.expect_token(TokenKind::Underscore)?;
-            MatchPatternData::Underscore
-        } else {
-            let path = self.parse_path()?;
+        let data = match self.token.kind {
+            TokenKind::Underscore => {
+                self.expect_token(TokenKind::Underscore)?;
+                MatchPatternData::Underscore
+            }
 
-            let params = if self.token.is(TokenKind::LParen) {
+            TokenKind::LParen => {
                 self.expect_token(TokenKind::LParen)?;
-                let params = self.parse_list(TokenKind::Comma, TokenKind::RParen, |this| {
-                    this.parse_match_pattern_param()
+                let mut patterns = self.parse_list(TokenKind::Comma, TokenKind::RParen, |p| {
+                    p.parse_match_pattern_alt()
                 })?;
 
-                Some(params)
-            } else {
-                None
-            };
+                if patterns.len() == 1 {
+                    return Ok(patterns.pop().unwrap());
+                }
+
+                MatchPatternData::Tuple(patterns)
+            }
+
+            TokenKind::True | TokenKind::False => MatchPatternData::Lit(self.parse_bool_literal()?),
+
+            TokenKind::LitChar(_) => MatchPatternData::Lit(self.parse_lit_char()?),
+            TokenKind::LitInt(_, _, _) => MatchPatternData::Lit(self.parse_lit_int()?),
+            TokenKind::StringTail(_) => MatchPatternData::Lit(self.parse_string()?),
+
+            TokenKind::Sub => {
+                let tok = self.advance_token()?;
+
+                match self.token.kind {
+                    TokenKind::LitInt(_, _, _) => {}
+                    _ => {
+                        return Err(ParseErrorAndPos::new(
+                            self.token.position,
+                            ParseError::ExpectedToken("number".into(), self.token.name()),
+                        ));
+                    }
+                }
+
+                let expr = self.parse_lit_int()?;
+                let span = self.span_from(start);
+
+                MatchPatternData::Lit(Box::new(Expr::create_un(
+                    self.generate_id(),
+                    tok.position,
+                    span,
+                    UnOp::Neg,
+                    expr,
+                )))
+            }
+
+            TokenKind::Mut => {
+                self.expect_token(TokenKind::Mut)?;
+                let path = self.parse_path()?;
+
+                MatchPatternData::Ident(MatchPatternIdent {
+                    path,
+                    params: None,
+                    mutable: true,
+                })
+            }
+
+            _ => {
+                let path = self.parse_path()?;
+
+                let params = if self.token.is(TokenKind::LParen) {
+                    self.expect_token(TokenKind::LParen)?;
+                    let params = self.parse_list(TokenKind::Comma, TokenKind::RParen, |p| {
+                        p.parse_match_pattern_alt()
+                    })?;
+
+                    Some(params)
+                } else {
+                    None
+                };
 
-            MatchPatternData::Ident(MatchPatternIdent { path, params })
+                MatchPatternData::Ident(MatchPatternIdent {
+                    path,
+                    params,
+                    mutable: false,
+                })
+            }
         };
 
         let span = self.span_from(start);
@@ -1401,35 +1558,30 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_match_pattern_param(&mut self) -> Result<MatchPatternParam, ParseErrorAndPos> {
+    // Nested patterns can have alternatives as well, e.g. `Some(1 | 2)`.
+    fn parse_match_pattern_alt(&mut self) -> Result<MatchPattern, ParseErrorAndPos> {
         let start = self.token.span.start();
         let pos = self.token.position;
+        let pattern = self.parse_match_pattern()?;
 
-        let (mutable, name) = if self.token.is(TokenKind::Underscore) {
-            self.expect_token(TokenKind::Underscore)?;
-
-            (false, None)
-        } else {
-            let mutable = if self.token.is(TokenKind::Mut) {
-                self.expect_token(TokenKind::Mut)?;
-                true
-            } else {
-                false
-            };
+        if !self.token.is(TokenKind::Or) {
+            return Ok(pattern);
+        }
 
-            let ident = self.expect_identifier()?;
+        let mut patterns = vec![pattern];
 
-            (mutable, Some(ident))
-        };
+        while self.token.is(TokenKind::Or) {
+            self.advance_token()?;
+            patterns.push(self.parse_match_pattern()?);
+        }
 
         let span = self.span_from(start);
 
-        Ok(MatchPatternParam {
+        Ok(MatchPattern {
             id: self.generate_id(),
             pos,
             span,
-            mutable,
-            name,
+            data: MatchPatternData::Alt(patterns),
         })
     }
 
@@ -1455,6 +1607,7 @@ impl<'a> Parser<'a> {
     fn parse_while(&mut self) -> StmtResult {
         let start = self.token.span.start();
         let pos = self.expect_token(TokenKind::While)?.position;
+        let pattern = self.parse_let_pattern_condition()?;
         let expr = self.parse_expression()?;
         let block = self.parse_block_stmt()?;
         let span = self.span_from(start);
@@ -1463,6 +1616,7 @@ impl<'a> Parser<'a> {
             self.generate_id(),
             pos,
             span,
+            pattern,
             expr,
             block,
         )))
@@ -1655,6 +1809,18 @@ impl<'a> Parser<'a> {
                     ))
                 }
 
+                TokenKind::QuestionMark => {
+                    let tok = self.advance_token()?;
+                    let span = self.span_from(start);
+
+                    Box::new(Expr::create_try(
+                        self.generate_id(),
+                        tok.position,
+                        span,
+                        left,
+                    ))
+                }
+
                 TokenKind::ColonColon => {
                     let tok = self.advance_token()?;
                     let rhs = self.parse_factor()?;
@@ -1889,17 +2055,28 @@ impl<'a> Parser<'a> {
             TokenKind::StringExpr(value) => {
                 let start = self.token.span.start();
                 let mut parts: Vec<Box<Expr>> = Vec::new();
+                let mut specs: Vec<Option<FormatSpec>> = Vec::new();
                 parts.push(Box::new(Expr::create_lit_str(
                     self.generate_id(),
                     string.position,
                     span,
                     value,
                 )));
+                specs.push(None);
 
                 loop {
                     let expr = self.parse_expression()?;
                     parts.push(expr);
 
+                    let spec = if self.token.is(TokenKind::Colon) {
+                        let token = self.lexer.read_format_spec()?;
+                        self.advance_token_with(token);
+                        Some(self.parse_format_spec()?)
+                    } else {
+                        None
+                    };
+                    specs.push(spec);
+
                     if !self.token.is(TokenKind::RBrace) {
                         return Err(ParseErrorAndPos::new(
                             self.token.position,
@@ -1925,6 +2102,7 @@ impl<'a> Parser<'a> {
                         span,
                         value,
                     )));
+                    specs.push(None);
 
                     self.advance_token()?;
 
@@ -1940,6 +2118,7 @@ impl<'a> Parser<'a> {
                     string.position,
                     span,
                     parts,
+                    specs,
                 )))
             }
 
@@ -1947,6 +2126,23 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn parse_format_spec(&mut self) -> Result<FormatSpec, ParseErrorAndPos> {
+        let token = self.advance_token()?;
+
+        let value = match token.kind {
+            TokenKind::FormatSpec(value) => value,
+            _ => unreachable!(),
+        };
+
+        match FormatSpec::parse(token.position, &value) {
+            Some(spec) => Ok(spec),
+            None => Err(ParseErrorAndPos::new(
+                token.position,
+                ParseError::InvalidFormatSpec(value),
+            )),
+        }
+    }
+
     fn parse_bool_literal(&mut self) -> ExprResult {
         let span = self.token.span;
         let tok = self.advance_token()?;
@@ -1994,6 +2190,7 @@ impl<'a> Parser<'a> {
             None
         };
 
+        let errors = self.errors.len();
         let block = self.parse_block()?;
 
         let block = match *block {
@@ -2018,6 +2215,9 @@ impl<'a> Parser<'a> {
             internal: false,
             is_constructor: false,
             is_test: false,
+            is_serial: false,
+            allow: Vec::new(),
+            has_errors: self.errors.len() > errors,
             params,
             return_type,
             block,
@@ -2061,9 +2261,88 @@ impl<'a> Parser<'a> {
 
     fn advance_token(&mut self) -> Result<Token, ParseErrorAndPos> {
         let token = self.lexer.read_token()?;
+
+        match self.token.kind {
+            TokenKind::LBrace => self.depth += 1,
+            TokenKind::RBrace => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+
         Ok(self.advance_token_with(token))
     }
 
+    // Skips tokens until the next top-level element. Tokens nested in braces
+    // are skipped as well, only elements starting in the first column are
+    // assumed to be top-level when braces aren't balanced.
+    fn recover_top_level_element(&mut self, start: u32) {
+        if self.token.span.start() == start {
+            self.skip_token();
+        }
+
+        while !self.token.is_eof() {
+            if self.is_top_level_element_start()
+                && (self.depth == 0 || self.token.position.column == 1)
+            {
+                self.depth = 0;
+                break;
+            }
+
+            self.skip_token();
+        }
+    }
+
+    // Skips tokens until the end of the current statement. Returns false
+    // when the end of the surrounding block can't be found.
+    fn recover_statement(&mut self, depth: u32) -> bool {
+        loop {
+            if self.token.is_eof() {
+                return false;
+            }
+
+            if self.depth == depth {
+                if self.token.is(TokenKind::RBrace) {
+                    return true;
+                }
+
+                if self.token.is(TokenKind::Semicolon) {
+                    self.skip_token();
+                    return true;
+                }
+            }
+
+            if self.is_top_level_element_start() && self.token.position.column == 1 {
+                return false;
+            }
+
+            self.skip_token();
+        }
+    }
+
+    fn skip_token(&mut self) {
+        if let Err(error) = self.advance_token() {
+            self.errors.push(error);
+        }
+    }
+
+    fn is_top_level_element_start(&self) -> bool {
+        matches!(
+            self.token.kind,
+            TokenKind::Fn
+                | TokenKind::Class
+                | TokenKind::Struct
+                | TokenKind::Trait
+                | TokenKind::Impl
+                | TokenKind::Annotation
+                | TokenKind::Alias
+                | TokenKind::Const
+                | TokenKind::Enum
+                | TokenKind::Mod
+                | TokenKind::Use
+                | TokenKind::Extern
+                | TokenKind::At
+        )
+    }
+
     fn advance_token_with(&mut self, token: Token) -> Token {
         self.last_end = if self.token.span.is_valid() {
             Some(self.token.span.end())
@@ -2209,6 +2488,18 @@ mod tests {
         assert_eq!(col, err.pos.column);
     }
 
+    fn parse_errors(code: &'static str) -> (File, Interner, Vec<(ParseError, u32, u32)>) {
+        let mut interner = Interner::new();
+
+        let (file, errors) = Parser::from_string(code, &mut interner).parse_with_errors();
+        let errors = errors
+            .into_iter()
+            .map(|err| (err.error, err.pos.line, err.pos.column))
+            .collect();
+
+        (file, interner, errors)
+    }
+
     #[test]
     fn parse_ident() {
         let (expr, interner) = parse_expr("a");
@@ -2610,6 +2901,30 @@ mod tests {
         assert_eq!(0, call.args.len());
     }
 
+    #[test]
+    fn parse_try() {
+        let (expr, interner) = parse_expr("foo()?");
+        let try_ = expr.to_try().unwrap();
+        let call = try_.expr.to_call().unwrap();
+        assert_eq!("foo", *interner.str(call.callee.to_ident().unwrap().name));
+    }
+
+    #[test]
+    fn parse_try_method_chain() {
+        let (expr, _) = parse_expr("a?.b()?");
+        let try_ = expr.to_try().unwrap();
+        let call = try_.expr.to_call().unwrap();
+        let dot = call.callee.to_dot().unwrap();
+        assert!(dot.lhs.is_try());
+    }
+
+    #[test]
+    fn parse_try_unary() {
+        let (expr, _) = parse_expr("-a?");
+        let un = expr.to_un().unwrap();
+        assert!(un.opnd.is_try());
+    }
+
     #[test]
     fn parse_call_with_params() {
         let (expr, interner) = parse_expr("fname2(1,2,3)");
@@ -2837,6 +3152,40 @@ mod tests {
         assert!(whilestmt.block.is_expr());
     }
 
+    #[test]
+    fn parse_if_let() {
+        let (expr, _) = parse_expr("if let Some(x) = y { 2; } else if let None = z { 3; }");
+        let ifexpr = expr.to_if().unwrap();
+
+        assert!(ifexpr.pattern.is_some());
+        assert!(ifexpr.cond.is_ident());
+
+        let else_if = ifexpr.else_block.as_ref().unwrap().to_if().unwrap();
+        assert!(else_if.pattern.is_some());
+        assert!(else_if.else_block.is_none());
+    }
+
+    #[test]
+    fn parse_while_let() {
+        let stmt = parse_stmt("while let Some(x) | Other(x) = it.next() { 2; }");
+        let whilestmt = stmt.to_while().unwrap();
+
+        let pattern = whilestmt.pattern.as_ref().unwrap();
+        assert!(matches!(pattern.data, MatchPatternData::Alt(_)));
+        assert!(whilestmt.cond.is_call());
+        assert!(whilestmt.block.is_expr());
+    }
+
+    #[test]
+    fn parse_if_let_without_eq() {
+        err_expr(
+            "if let Some(x) y { }",
+            ParseError::ExpectedToken("=".into(), "y".into()),
+            1,
+            16,
+        );
+    }
+
     #[test]
     fn parse_empty_block() {
         let (expr, _) = parse_expr("{}");
@@ -3103,6 +3452,25 @@ mod tests {
         assert!(fct.internal);
     }
 
+    #[test]
+    fn parse_allow() {
+        let (prog, interner) = parse("@allow(unusedVariable, unreachableCode) fn foo() {}");
+        let fct = prog.fct0();
+        assert_eq!(2, fct.allow.len());
+        assert_eq!("unusedVariable", *interner.str(fct.allow[0]));
+        assert_eq!("unreachableCode", *interner.str(fct.allow[1]));
+
+        let (prog, _) = parse("fn foo() {}");
+        assert!(prog.fct0().allow.is_empty());
+
+        parse_err(
+            "@allow fn foo() {}",
+            ParseError::ExpectedToken("(".into(), "fn".into()),
+            1,
+            8,
+        );
+    }
+
     #[test]
     fn parse_function_without_body() {
         let (prog, _) = parse("fn foo();");
@@ -3211,6 +3579,53 @@ mod tests {
         assert!(expr.is_lit_str());
     }
 
+    #[test]
+    fn parse_template_format_spec() {
+        let (expr, _) = parse_expr("\"a${x:*^+08.3x}b${y}c${z:<}\"");
+        let tmpl = expr.to_template().unwrap();
+        assert_eq!(tmpl.parts.len(), 7);
+        assert_eq!(tmpl.specs.len(), 7);
+        assert!(tmpl.specs[0].is_none());
+        assert!(tmpl.specs[3].is_none());
+
+        let spec = tmpl.specs[1].as_ref().unwrap();
+        assert_eq!("*^+08.3x", spec.value);
+        assert_eq!('*', spec.fill);
+        assert_eq!(Some(FormatAlign::Center), spec.align);
+        assert!(spec.sign_plus);
+        assert!(spec.zero_pad);
+        assert_eq!(Some(8), spec.width);
+        assert_eq!(Some(3), spec.precision);
+        assert_eq!(FormatKind::Hex, spec.kind);
+        assert_eq!(1, spec.pos.line);
+        assert_eq!(7, spec.pos.column);
+
+        let spec = tmpl.specs[5].as_ref().unwrap();
+        assert_eq!(' ', spec.fill);
+        assert_eq!(Some(FormatAlign::Left), spec.align);
+        assert_eq!(None, spec.width);
+        assert!(spec.is_padding_only());
+        assert_eq!("c".to_string(), tmpl.parts[4].to_lit_str().unwrap().value);
+        assert_eq!("".to_string(), tmpl.parts[6].to_lit_str().unwrap().value);
+    }
+
+    #[test]
+    fn parse_template_invalid_format_spec() {
+        err_expr(
+            "\"${x:8q}\"",
+            ParseError::InvalidFormatSpec("8q".into()),
+            1,
+            6,
+        );
+        err_expr(
+            "\"${x:.}\"",
+            ParseError::InvalidFormatSpec(".".into()),
+            1,
+            6,
+        );
+        err_expr("\"${x:8\"", ParseError::UnclosedStringTemplate, 1, 6);
+    }
+
     #[test]
     fn parse_class_type_params() {
         let (prog, interner) = parse("class Foo[T]");
@@ -3574,6 +3989,66 @@ mod tests {
         parse_expr("match x { A(x, b) => 1, B | C => 2 }");
     }
 
+    #[test]
+    fn parse_match_nested_patterns() {
+        let (expr, _) =
+            parse_expr("match x { Some(Ok(mut y)) => 1, (1 | -2, 'a', \"s\", true) => 2, _ => 3 }");
+        let match_ = expr.to_match().unwrap();
+        assert_eq!(3, match_.cases.len());
+
+        let pattern = &match_.cases[0].patterns[0];
+        let ident = match pattern.data {
+            MatchPatternData::Ident(ref ident) => ident,
+            _ => unreachable!(),
+        };
+        let params = ident.params.as_ref().unwrap();
+        let nested = match params[0].data {
+            MatchPatternData::Ident(ref ident) => ident,
+            _ => unreachable!(),
+        };
+        let binding = match nested.params.as_ref().unwrap()[0].data {
+            MatchPatternData::Ident(ref ident) => ident,
+            _ => unreachable!(),
+        };
+        assert!(binding.mutable);
+        assert!(binding.params.is_none());
+
+        let parts = match match_.cases[1].patterns[0].data {
+            MatchPatternData::Tuple(ref parts) => parts,
+            _ => unreachable!(),
+        };
+        assert_eq!(4, parts.len());
+
+        match parts[0].data {
+            MatchPatternData::Alt(ref alts) => {
+                assert_eq!(2, alts.len());
+                match alts[1].data {
+                    MatchPatternData::Lit(ref expr) => assert!(expr.to_un().is_some()),
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        for part in &parts[1..] {
+            assert!(matches!(part.data, MatchPatternData::Lit(_)));
+        }
+    }
+
+    #[test]
+    fn parse_match_paren_pattern() {
+        let (expr, _) = parse_expr("match x { (A) => 1, () => 2 }");
+        let match_ = expr.to_match().unwrap();
+        assert!(matches!(
+            match_.cases[0].patterns[0].data,
+            MatchPatternData::Ident(_)
+        ));
+        assert!(matches!(
+            match_.cases[1].patterns[0].data,
+            MatchPatternData::Tuple(ref parts) if parts.is_empty()
+        ));
+    }
+
     #[test]
     fn parse_use_declaration() {
         parse_err(
@@ -3590,4 +4065,82 @@ mod tests {
             5,
         );
     }
+
+    #[test]
+    fn parse_recover_top_level_elements() {
+        let (prog, interner, errors) =
+            parse_errors("fn f( {}\nclass A(x: Int32\nfn g() {}\nstruct 1\nfn h() {}");
+        assert_eq!(
+            vec![
+                (ParseError::ExpectedIdentifier("{".into()), 1, 7),
+                (ParseError::ExpectedToken(",".into(), "fn".into()), 3, 1),
+                (ParseError::ExpectedIdentifier("1".into()), 4, 8),
+            ],
+            errors
+        );
+        assert_eq!(2, prog.elements.len());
+        assert_eq!("g", *interner.str(prog.fct(0).name));
+        assert_eq!("h", *interner.str(prog.fct(1).name));
+    }
+
+    #[test]
+    fn parse_recover_statements() {
+        let (prog, interner, errors) =
+            parse_errors("fn f() { let = 1; 1 +; }\nfn g() { let x = 1; x }");
+        assert_eq!(
+            vec![
+                (ParseError::ExpectedIdentifier("=".into()), 1, 14),
+                (ParseError::ExpectedFactor(";".into()), 1, 22),
+            ],
+            errors
+        );
+
+        // only the statements with errors are left out
+        assert_eq!(2, prog.elements.len());
+        let f = prog.fct(0);
+        assert_eq!("f", *interner.str(f.name));
+        assert!(f.has_errors);
+        assert!(f.block.as_ref().unwrap().stmts.is_empty());
+        let g = prog.fct(1);
+        assert_eq!("g", *interner.str(g.name));
+        assert!(!g.has_errors);
+    }
+
+    #[test]
+    fn parse_recover_nested_blocks() {
+        let (prog, _, errors) =
+            parse_errors("fn f() { if x { 1 + ; } else { while { } } }\nfn g() {}");
+        assert_eq!(
+            vec![
+                (ParseError::ExpectedFactor(";".into()), 1, 21),
+                (ParseError::ExpectedToken("{".into(), "}".into()), 1, 42),
+            ],
+            errors
+        );
+        assert_eq!(2, prog.elements.len());
+        assert!(prog.fct(0).has_errors);
+    }
+
+    #[test]
+    fn parse_recover_unclosed_block() {
+        let (prog, interner, errors) = parse_errors("fn f() {\n  let x = ;\nfn g() {}");
+        assert_eq!(
+            vec![
+                (ParseError::ExpectedFactor(";".into()), 2, 11),
+                (ParseError::ExpectedToken("}".into(), "fn".into()), 3, 1),
+            ],
+            errors
+        );
+        assert_eq!(1, prog.elements.len());
+        assert_eq!("g", *interner.str(prog.fct0().name));
+    }
+
+    #[test]
+    fn parse_recover_unknown_char() {
+        let (prog, _, errors) = parse_errors("fn f() { 1 # 2; }\nfn g() {}");
+        assert_eq!(vec![(ParseError::UnknownChar('#'), 1, 12)], errors);
+        assert_eq!(2, prog.elements.len());
+        assert!(prog.fct(0).has_errors);
+        assert!(!prog.fct(1).has_errors);
+    }
 }
@@ -75,6 +75,7 @@ fn write_reachable_functions(
             return_type: fct.return_type.clone(),
             has_variadic_parameter: fct.is_variadic,
             pos: fct.pos,
+            hotness_counter: None,
             emit_debug: false,
             emit_code_comments: false,
         };
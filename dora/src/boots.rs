@@ -2,11 +2,11 @@ use std::mem;
 use std::ptr;
 
 use crate::boots::serializer::allocate_encoded_compilation_info;
-use crate::bytecode::InstructionSet;
+use crate::bytecode::{BytecodeType, ConstPoolEntry, InstructionSet};
 use crate::gc::Address;
 use crate::handle::handle;
 use crate::language::sem_analysis::FctDefinition;
-use crate::language::ty::SourceTypeArray;
+use crate::language::ty::{SourceType, SourceTypeArray};
 use crate::masm::CodeDescriptor;
 use crate::object::{Ref, UInt8Array};
 use crate::threads::current_thread;
@@ -14,7 +14,79 @@ use crate::vm::VM;
 
 mod serializer;
 
-pub fn compile(vm: &VM, fct: &FctDefinition, type_params: &SourceTypeArray) -> CodeDescriptor {
+// Boots only handles non-generic functions with primitive and
+// pointer registers for now. Everything else is left to the baseline
+// compiler without calling into Boots at all.
+pub fn supports(fct: &FctDefinition, type_params: &SourceTypeArray) -> bool {
+    let bytecode_fct = match fct.bytecode {
+        Some(ref bytecode_fct) => bytecode_fct,
+        None => return false,
+    };
+
+    // The code generator only implements the System V calling convention on x64.
+    if !type_params.is_empty() || !cfg!(all(target_arch = "x86_64", unix)) {
+        return false;
+    }
+
+    let registers_supported = bytecode_fct.registers().iter().all(|ty| {
+        matches!(
+            ty,
+            BytecodeType::Unit
+                | BytecodeType::Bool
+                | BytecodeType::Char
+                | BytecodeType::UInt8
+                | BytecodeType::Int32
+                | BytecodeType::Int64
+                | BytecodeType::Float32
+                | BytecodeType::Float64
+                | BytecodeType::Ptr
+        )
+    });
+
+    let const_pool_supported = bytecode_fct
+        .const_pool_entries()
+        .iter()
+        .all(|entry| match entry {
+            ConstPoolEntry::String(_)
+            | ConstPoolEntry::Float32(_)
+            | ConstPoolEntry::Float64(_)
+            | ConstPoolEntry::Int32(_)
+            | ConstPoolEntry::Int64(_)
+            | ConstPoolEntry::Char(_) => true,
+            ConstPoolEntry::Field(_, type_params, _) => {
+                type_params.iter().all(|ty| supports_type(&ty))
+            }
+            _ => false,
+        });
+
+    registers_supported && const_pool_supported
+}
+
+fn supports_type(ty: &SourceType) -> bool {
+    match ty {
+        SourceType::Unit
+        | SourceType::Bool
+        | SourceType::Char
+        | SourceType::UInt8
+        | SourceType::Int32
+        | SourceType::Int64
+        | SourceType::Float32
+        | SourceType::Float64
+        | SourceType::TypeParam(_) => true,
+        SourceType::Class(_, params)
+        | SourceType::Struct(_, params)
+        | SourceType::Trait(_, params)
+        | SourceType::Tuple(params) => params.iter().all(|ty| supports_type(&ty)),
+        _ => false,
+    }
+}
+
+// Returns None when Boots bailed out on the function.
+pub fn compile(
+    vm: &VM,
+    fct: &FctDefinition,
+    type_params: &SourceTypeArray,
+) -> Option<CodeDescriptor> {
     let bytecode_fct = fct.bytecode.as_ref().expect("bytecode missing");
 
     let compile_fct_id = vm.known.functions.compile();
@@ -38,6 +110,11 @@ pub fn compile(vm: &VM, fct: &FctDefinition, type_params: &SourceTypeArray) -> C
         compile_address,
         encoded_compilation_info.direct_ptr(),
     ));
+
+    if machine_code.len() == 0 {
+        return None;
+    }
+
     let mut code = vec![0; machine_code.len()];
 
     unsafe {
@@ -48,7 +125,7 @@ pub fn compile(vm: &VM, fct: &FctDefinition, type_params: &SourceTypeArray) -> C
         );
     }
 
-    CodeDescriptor::from_buffer(code)
+    Some(CodeDescriptor::from_buffer(code))
 }
 
 fn get_architecture() -> InstructionSet {
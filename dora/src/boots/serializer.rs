@@ -8,7 +8,7 @@ use crate::bytecode::{
 use crate::bytecode::{BytecodeType, BytecodeTypeKind};
 use crate::language::ty::{SourceType, SourceTypeArray};
 use crate::object::{byte_array_from_buffer, Obj, Ref};
-use crate::vm::VM;
+use crate::vm::{specialize_class_id_params, specialize_type_list, VM};
 
 pub fn allocate_encoded_compilation_info(
     vm: &VM,
@@ -28,14 +28,20 @@ fn encode_compilation_info(
     architecture: InstructionSet,
     buffer: &mut ByteBuffer,
 ) {
-    encode_bytecode_function(vm, bytecode_fct, buffer);
+    encode_bytecode_function(vm, bytecode_fct, type_params, buffer);
     encode_type_params(vm, type_params, buffer);
     encode_architecture(architecture, buffer);
+    buffer.emit_u64(vm.stubs.trap().to_usize() as u64);
 }
 
-fn encode_bytecode_function(vm: &VM, bytecode_fct: &BytecodeFunction, buffer: &mut ByteBuffer) {
+fn encode_bytecode_function(
+    vm: &VM,
+    bytecode_fct: &BytecodeFunction,
+    type_params: &SourceTypeArray,
+    buffer: &mut ByteBuffer,
+) {
     encode_bytecode_array(bytecode_fct, buffer);
-    encode_constpool_array(vm, bytecode_fct, buffer);
+    encode_constpool_array(vm, bytecode_fct, type_params, buffer);
     encode_registers_array(vm, bytecode_fct, buffer);
     buffer.emit_u32(bytecode_fct.arguments());
 }
@@ -199,18 +205,28 @@ fn encode_source_type(vm: &VM, ty: SourceType, buffer: &mut ByteBuffer) {
     }
 }
 
-fn encode_constpool_array(vm: &VM, fct: &BytecodeFunction, buffer: &mut ByteBuffer) {
+fn encode_constpool_array(
+    vm: &VM,
+    fct: &BytecodeFunction,
+    type_params: &SourceTypeArray,
+    buffer: &mut ByteBuffer,
+) {
     buffer.emit_u32(fct.const_pool_entries().len() as u32);
 
     for const_entry in fct.const_pool_entries() {
-        encode_constpool_entry(vm, const_entry, buffer);
+        encode_constpool_entry(vm, const_entry, type_params, buffer);
     }
 }
 
-fn encode_constpool_entry(vm: &VM, const_entry: &ConstPoolEntry, buffer: &mut ByteBuffer) {
+fn encode_constpool_entry(
+    vm: &VM,
+    const_entry: &ConstPoolEntry,
+    fct_type_params: &SourceTypeArray,
+    buffer: &mut ByteBuffer,
+) {
     match const_entry {
         ConstPoolEntry::String(ref value) => {
-            buffer.emit_u8(ConstPoolOpcode::Float32.to_u8());
+            buffer.emit_u8(ConstPoolOpcode::String.to_u8());
             buffer.emit_u32(value.len() as u32);
 
             for byte in value.bytes() {
@@ -222,7 +238,7 @@ fn encode_constpool_entry(vm: &VM, const_entry: &ConstPoolEntry, buffer: &mut By
             buffer.emit_u32(value.to_bits());
         }
         &ConstPoolEntry::Float64(value) => {
-            buffer.emit_u8(ConstPoolOpcode::Float32.to_u8());
+            buffer.emit_u8(ConstPoolOpcode::Float64.to_u8());
             buffer.emit_u64(value.to_bits());
         }
         &ConstPoolEntry::Int32(value) => {
@@ -258,6 +274,11 @@ fn encode_constpool_entry(vm: &VM, const_entry: &ConstPoolEntry, buffer: &mut By
             buffer.emit_id(cls_id.to_usize());
             encode_source_type_array(vm, source_type_array, buffer);
             buffer.emit_id(field_id.to_usize());
+
+            let type_params = specialize_type_list(vm, source_type_array, fct_type_params);
+            let class_instance_id = specialize_class_id_params(vm, cls_id, &type_params);
+            let cls = vm.class_instances.idx(class_instance_id);
+            buffer.emit_u32(cls.fields[field_id.to_usize()].offset as u32);
         }
         &ConstPoolEntry::FieldFixed(cls_def_id, field_id) => {
             buffer.emit_u8(ConstPoolOpcode::FieldFixed.to_u8());
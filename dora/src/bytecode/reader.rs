@@ -35,7 +35,7 @@ impl<'a> BytecodeReader<'a> {
         reader.read_opcode()
     }
 
-    fn offset(&self) -> usize {
+    pub fn offset(&self) -> usize {
         self.offset
     }
 
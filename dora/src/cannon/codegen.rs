@@ -48,6 +48,15 @@ macro_rules! comment {
     }};
 }
 
+struct StackSlot {
+    ty: BytecodeType,
+    offset: i32,
+
+    // last instruction of the live range of the register occupying
+    // this slot, None if the slot can't be reused.
+    end: Option<usize>,
+}
+
 struct ForwardJump {
     label: Label,
     offset: BytecodeOffset,
@@ -64,6 +73,7 @@ pub struct CannonCodeGen<'a> {
     return_type: SourceType,
     emit_debug: bool,
     emit_code_comments: bool,
+    hotness_counter: Option<Address>,
 
     type_params: &'a SourceTypeArray,
 
@@ -74,7 +84,8 @@ pub struct CannonCodeGen<'a> {
     current_offset: BytecodeOffset,
     argument_stack: Vec<Register>,
 
-    references: Vec<i32>,
+    // reference offsets within the stack frame for each register
+    references: Vec<Vec<i32>>,
 
     offsets: Vec<Option<i32>>,
     framesize: i32,
@@ -109,6 +120,7 @@ impl<'a> CannonCodeGen<'a> {
             asm: BaselineAssembler::new(vm),
             bytecode: compilation_data.bytecode_fct,
             emit_code_comments: compilation_data.emit_code_comments,
+            hotness_counter: compilation_data.hotness_counter,
             type_params: compilation_data.type_params,
             offset_to_address: HashMap::new(),
             offset_to_label: HashMap::new(),
@@ -134,6 +146,7 @@ impl<'a> CannonCodeGen<'a> {
 
         self.emit_prolog();
         self.emit_stack_guard();
+        self.emit_hotness_check();
         self.clear_registers();
         self.store_params_on_stack();
         self.emit_safepoint();
@@ -179,11 +192,39 @@ impl<'a> CannonCodeGen<'a> {
         let mut offset: Vec<Option<i32>> = vec![None; len];
         let mut stacksize: i32 = start;
 
-        for (index, ty) in self.bytecode.registers().iter().enumerate() {
-            let ty = self.specialize_bytecode_type(ty.clone());
-            let sz = size(self.vm, ty);
+        // Registers of the same type share a stack slot when their live ranges
+        // do not overlap. Registers that are never accessed have no live range
+        // and get a stack slot of their own.
+        let mut registers = (0..len).map(Register).collect::<Vec<_>>();
+        registers.sort_by_key(|&reg| self.liveness.live_range(reg).map(|range| range.start));
+
+        let mut slots: Vec<StackSlot> = Vec::new();
+
+        for reg in registers {
+            let ty = self.specialize_register_type(reg);
+            let range = self.liveness.live_range(reg);
+
+            let free_slot = range.and_then(|range| {
+                slots
+                    .iter_mut()
+                    .find(|slot| slot.ty == ty && slot.end.is_some_and(|end| end < range.start))
+            });
+
+            if let Some(slot) = free_slot {
+                slot.end = range.map(|range| range.end);
+                offset[reg.to_usize()] = Some(slot.offset);
+                continue;
+            }
+
+            let sz = size(self.vm, ty.clone());
             stacksize = align_i32(stacksize + sz, sz);
-            offset[index] = Some(-stacksize);
+            offset[reg.to_usize()] = Some(-stacksize);
+
+            slots.push(StackSlot {
+                ty,
+                offset: -stacksize,
+                end: range.map(|range| range.end),
+            });
         }
 
         stacksize = align_i32(stacksize, STACK_FRAME_ALIGNMENT as i32);
@@ -213,17 +254,19 @@ impl<'a> CannonCodeGen<'a> {
         assert!(self.references.is_empty());
         for (idx, ty) in self.bytecode.registers().iter().enumerate() {
             let ty = self.specialize_bytecode_type(ty.clone());
+            let mut references = Vec::new();
+
             match ty {
                 BytecodeType::Ptr | BytecodeType::Trait(_, _) => {
                     let offset = self.register_offset(Register(idx));
-                    self.references.push(offset);
+                    references.push(offset);
                 }
 
                 BytecodeType::Tuple(_) => {
                     let offset = self.register_offset(Register(idx));
                     let tuple = get_concrete_tuple_bytecode_ty(self.vm, &ty);
                     for &ref_offset in tuple.references() {
-                        self.references.push(offset + ref_offset);
+                        references.push(offset + ref_offset);
                     }
                 }
 
@@ -234,7 +277,7 @@ impl<'a> CannonCodeGen<'a> {
                     let struct_instance = self.vm.struct_instances.idx(struct_instance_id);
 
                     for &ref_offset in &struct_instance.ref_fields {
-                        self.references.push(offset + ref_offset);
+                        references.push(offset + ref_offset);
                     }
                 }
 
@@ -248,7 +291,7 @@ impl<'a> CannonCodeGen<'a> {
                         }
                         EnumLayout::Ptr | EnumLayout::Tagged => {
                             let offset = self.register_offset(Register(idx));
-                            self.references.push(offset);
+                            references.push(offset);
                         }
                     }
                 }
@@ -270,11 +313,21 @@ impl<'a> CannonCodeGen<'a> {
                     // type does not contain reference
                 }
             }
+
+            self.references.push(references);
         }
     }
 
+    // only references in registers live at the current instruction are reported,
+    // dead registers might still contain stale references.
     fn create_gcpoint(&self) -> GcPoint {
-        GcPoint::from_offsets(self.references.clone())
+        let mut offsets = Vec::new();
+
+        for reg in self.liveness.live_registers(self.current_offset) {
+            offsets.extend_from_slice(&self.references[reg.to_usize()]);
+        }
+
+        GcPoint::from_offsets(offsets)
     }
 
     fn has_result_address(&self) -> bool {
@@ -540,6 +593,13 @@ impl<'a> CannonCodeGen<'a> {
         self.asm.stack_guard(self.pos, gcpoint);
     }
 
+    fn emit_hotness_check(&mut self) {
+        if let Some(counter) = self.hotness_counter {
+            let threshold = self.vm.args.tier_up_threshold();
+            self.asm.hotness_check(counter, threshold, self.pos);
+        }
+    }
+
     fn emit_epilog(&mut self) {
         self.asm.epilog();
     }
@@ -1627,6 +1687,13 @@ impl<'a> CannonCodeGen<'a> {
         let bytecode_type = self.specialize_register_type(src);
         assert_eq!(bytecode_type, register_bty_from_ty(field.ty.clone()));
 
+        if self.vm.gc.needs_pre_write_barrier() {
+            for ref_offset in self.reference_offsets(&bytecode_type) {
+                self.asm
+                    .emit_pre_write_barrier(Mem::Base(obj_reg, field.offset + ref_offset));
+            }
+        }
+
         self.emit_store_field_raw(obj_reg, field.offset, src);
     }
 
@@ -1722,6 +1789,36 @@ impl<'a> CannonCodeGen<'a> {
         }
     }
 
+    fn reference_offsets(&self, ty: &BytecodeType) -> Vec<i32> {
+        match ty {
+            BytecodeType::Tuple(_) => get_concrete_tuple_bytecode_ty(self.vm, ty)
+                .references()
+                .to_vec(),
+
+            BytecodeType::Struct(struct_id, type_params) => {
+                let struct_instance_id =
+                    specialize_struct_id_params(self.vm, *struct_id, type_params.clone());
+                let struct_instance = self.vm.struct_instances.idx(struct_instance_id);
+                struct_instance.ref_fields.clone()
+            }
+
+            BytecodeType::Enum(enum_id, type_params) => {
+                let enum_instance_id =
+                    specialize_enum_id_params(self.vm, *enum_id, type_params.clone());
+                let enum_instance = self.vm.enum_instances.idx(enum_instance_id);
+
+                match enum_instance.layout {
+                    EnumLayout::Int => Vec::new(),
+                    EnumLayout::Ptr | EnumLayout::Tagged => vec![0],
+                }
+            }
+
+            BytecodeType::Ptr | BytecodeType::Trait(_, _) => vec![0],
+
+            _ => Vec::new(),
+        }
+    }
+
     fn emit_load_global(&mut self, dest: Register, global_id: GlobalDefinitionId) {
         let global_var = self.vm.globals.idx(global_id);
         let global_var = global_var.read();
@@ -2701,6 +2798,13 @@ impl<'a> CannonCodeGen<'a> {
                     .array_address(REG_TMP1, REG_RESULT, REG_TMP1, element_size);
                 let src_offset = self.register_offset(src);
 
+                if self.vm.gc.needs_pre_write_barrier() {
+                    for &ref_offset in tuple.references() {
+                        self.asm
+                            .emit_pre_write_barrier(Mem::Base(REG_TMP1, ref_offset));
+                    }
+                }
+
                 self.copy_tuple(
                     subtypes.clone(),
                     RegOrOffset::Reg(REG_TMP1),
@@ -2724,6 +2828,13 @@ impl<'a> CannonCodeGen<'a> {
                     .array_address(REG_TMP1, REG_RESULT, REG_TMP1, struct_instance.size);
                 let src_offset = self.register_offset(src);
 
+                if self.vm.gc.needs_pre_write_barrier() {
+                    for &ref_offset in &struct_instance.ref_fields {
+                        self.asm
+                            .emit_pre_write_barrier(Mem::Base(REG_TMP1, ref_offset));
+                    }
+                }
+
                 self.copy_struct(
                     struct_id,
                     type_params,
@@ -2748,6 +2859,15 @@ impl<'a> CannonCodeGen<'a> {
                     EnumLayout::Ptr | EnumLayout::Tagged => MachineMode::Ptr,
                 };
 
+                if self.vm.gc.needs_pre_write_barrier() && mode == MachineMode::Ptr {
+                    self.asm.emit_pre_write_barrier(Mem::Index(
+                        REG_RESULT,
+                        REG_TMP1,
+                        mode.size(),
+                        offset_of_array_data(),
+                    ));
+                }
+
                 let value_reg = REG_TMP2.into();
 
                 self.emit_load_register_as(src, value_reg, mode);
@@ -2785,6 +2905,15 @@ impl<'a> CannonCodeGen<'a> {
             | BytecodeType::Trait(_, _) => {
                 let src_mode = mode(self.vm, src_type.clone());
 
+                if self.vm.gc.needs_pre_write_barrier() && src_type.is_ptr() {
+                    self.asm.emit_pre_write_barrier(Mem::Index(
+                        REG_RESULT,
+                        REG_TMP1,
+                        src_mode.size(),
+                        offset_of_array_data(),
+                    ));
+                }
+
                 let value_reg: AnyReg = if src_mode.is_float() {
                     FREG_RESULT.into()
                 } else {
@@ -4986,6 +5115,10 @@ impl<'a> BytecodeVisitor for CannonCodeGen<'a> {
         comment!(self, format!("LoopStart"));
         let label = self.asm.create_and_bind_label();
         self.offset_to_label.insert(self.current_offset, label);
+
+        if let Some(counter) = self.hotness_counter {
+            self.asm.count_back_edge(counter);
+        }
     }
 
     fn visit_invoke_direct(&mut self, dest: Register, fctdef: ConstPoolIdx) {
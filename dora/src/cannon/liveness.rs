@@ -1,9 +1,442 @@
-use crate::bytecode::BytecodeFunction;
+use fixedbitset::FixedBitSet;
+use std::collections::HashMap;
 
-pub(super) struct BytecodeLiveness;
+use crate::bytecode::{
+    BytecodeFunction, BytecodeInstruction, BytecodeOffset, BytecodeReader, ConstPoolIdx, Register,
+};
+
+// Result of a backward dataflow analysis over the registers of a bytecode function.
+// A register is live at an instruction if its current value might still be read
+// on some path starting at that instruction.
+pub(super) struct BytecodeLiveness {
+    offset_to_index: HashMap<BytecodeOffset, usize>,
+
+    // registers live right before each instruction
+    live_in: Vec<FixedBitSet>,
+
+    // first and last instruction at which a register is either live or defined,
+    // None for registers that are never accessed by any instruction.
+    ranges: Vec<Option<LiveRange>>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) struct LiveRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LiveRange {
+    fn extend(range: Option<LiveRange>, idx: usize) -> LiveRange {
+        match range {
+            Some(range) => LiveRange {
+                start: range.start.min(idx),
+                end: range.end.max(idx),
+            },
+            None => LiveRange {
+                start: idx,
+                end: idx,
+            },
+        }
+    }
+}
 
 impl BytecodeLiveness {
-    pub(super) fn analyze(_fct: &BytecodeFunction) -> BytecodeLiveness {
-        BytecodeLiveness
+    pub(super) fn analyze(fct: &BytecodeFunction) -> BytecodeLiveness {
+        let registers = fct.registers().len();
+        let instructions = read_instructions(fct);
+
+        let offset_to_index = instructions
+            .iter()
+            .enumerate()
+            .map(|(idx, inst)| (inst.offset, idx))
+            .collect::<HashMap<_, _>>();
+
+        let successors = instructions
+            .iter()
+            .enumerate()
+            .map(|(idx, inst)| match inst.flow {
+                Flow::Next => vec![idx + 1],
+                Flow::Jump(target) => vec![offset_to_index[&target]],
+                Flow::Branch(target) => vec![idx + 1, offset_to_index[&target]],
+                Flow::Return => Vec::new(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut live_in = vec![FixedBitSet::with_capacity(registers); instructions.len()];
+        let mut changed = true;
+
+        // Iterate until fixed point is reached, backward jumps (JumpLoop) might
+        // require multiple passes until the loop body sees all registers live
+        // at the loop header.
+        while changed {
+            changed = false;
+
+            for idx in (0..instructions.len()).rev() {
+                let inst = &instructions[idx];
+                let mut live = FixedBitSet::with_capacity(registers);
+
+                for &succ in &successors[idx] {
+                    live.union_with(&live_in[succ]);
+                }
+
+                for def in &inst.defs {
+                    live.set(def.to_usize(), false);
+                }
+
+                for used in &inst.uses {
+                    live.insert(used.to_usize());
+                }
+
+                if live != live_in[idx] {
+                    live_in[idx] = live;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut ranges: Vec<Option<LiveRange>> = vec![None; registers];
+
+        for (idx, inst) in instructions.iter().enumerate() {
+            for reg in live_in[idx].ones() {
+                ranges[reg] = Some(LiveRange::extend(ranges[reg], idx));
+            }
+
+            for def in &inst.defs {
+                let reg = def.to_usize();
+                ranges[reg] = Some(LiveRange::extend(ranges[reg], idx));
+            }
+        }
+
+        BytecodeLiveness {
+            offset_to_index,
+            live_in,
+            ranges,
+        }
+    }
+
+    // registers that need to be kept alive while executing the instruction at
+    // the given offset. Registers defined by this instruction are not included.
+    pub(super) fn live_registers(
+        &self,
+        offset: BytecodeOffset,
+    ) -> impl Iterator<Item = Register> + '_ {
+        let idx = self.offset_to_index[&offset];
+        self.live_in[idx].ones().map(Register)
+    }
+
+    pub(super) fn live_range(&self, reg: Register) -> Option<LiveRange> {
+        self.ranges[reg.to_usize()]
+    }
+}
+
+enum Flow {
+    Next,
+    Jump(BytecodeOffset),
+    Branch(BytecodeOffset),
+    Return,
+}
+
+struct InstructionInfo {
+    offset: BytecodeOffset,
+    uses: Vec<Register>,
+    defs: Vec<Register>,
+    flow: Flow,
+}
+
+fn read_instructions(fct: &BytecodeFunction) -> Vec<InstructionInfo> {
+    let mut reader = BytecodeReader::new(fct.code());
+    let mut instructions = Vec::new();
+    let mut arguments = Vec::new();
+
+    loop {
+        let offset = BytecodeOffset(reader.offset() as u32);
+
+        let inst = match reader.next() {
+            Some(inst) => inst,
+            None => break,
+        };
+
+        let mut uses = Vec::new();
+        let mut defs = Vec::new();
+        let mut flow = Flow::Next;
+
+        let jump_const = |idx: ConstPoolIdx| {
+            let distance = fct.const_pool(idx).to_int32().expect("int expected");
+            BytecodeOffset((offset.to_u32() as i32 + distance) as u32)
+        };
+
+        match inst {
+            BytecodeInstruction::Add { dest, lhs, rhs }
+            | BytecodeInstruction::Sub { dest, lhs, rhs }
+            | BytecodeInstruction::Mul { dest, lhs, rhs }
+            | BytecodeInstruction::Div { dest, lhs, rhs }
+            | BytecodeInstruction::Mod { dest, lhs, rhs }
+            | BytecodeInstruction::And { dest, lhs, rhs }
+            | BytecodeInstruction::Or { dest, lhs, rhs }
+            | BytecodeInstruction::Xor { dest, lhs, rhs }
+            | BytecodeInstruction::Shl { dest, lhs, rhs }
+            | BytecodeInstruction::Shr { dest, lhs, rhs }
+            | BytecodeInstruction::Sar { dest, lhs, rhs }
+            | BytecodeInstruction::TestIdentity { dest, lhs, rhs }
+            | BytecodeInstruction::TestEq { dest, lhs, rhs }
+            | BytecodeInstruction::TestNe { dest, lhs, rhs }
+            | BytecodeInstruction::TestGt { dest, lhs, rhs }
+            | BytecodeInstruction::TestGe { dest, lhs, rhs }
+            | BytecodeInstruction::TestLt { dest, lhs, rhs }
+            | BytecodeInstruction::TestLe { dest, lhs, rhs } => {
+                uses.push(lhs);
+                uses.push(rhs);
+                defs.push(dest);
+            }
+
+            BytecodeInstruction::Neg { dest, src }
+            | BytecodeInstruction::Not { dest, src }
+            | BytecodeInstruction::Mov { dest, src }
+            | BytecodeInstruction::LoadTupleElement { dest, src, .. }
+            | BytecodeInstruction::LoadEnumElement { dest, src, .. }
+            | BytecodeInstruction::LoadEnumVariant { dest, src, .. }
+            | BytecodeInstruction::NewTraitObject { dest, src, .. } => {
+                uses.push(src);
+                defs.push(dest);
+            }
+
+            BytecodeInstruction::LoadStructField { dest, obj, .. }
+            | BytecodeInstruction::LoadField { dest, obj, .. } => {
+                uses.push(obj);
+                defs.push(dest);
+            }
+
+            BytecodeInstruction::StoreField { src, obj, .. } => {
+                uses.push(src);
+                uses.push(obj);
+            }
+
+            BytecodeInstruction::StoreGlobal { src, .. } => {
+                uses.push(src);
+            }
+
+            // Arguments are read by the following invoke or allocation instruction.
+            BytecodeInstruction::PushRegister { src } => {
+                arguments.push(src);
+            }
+
+            BytecodeInstruction::LoadGlobal { dest, .. }
+            | BytecodeInstruction::ConstTrue { dest }
+            | BytecodeInstruction::ConstFalse { dest }
+            | BytecodeInstruction::ConstUInt8 { dest, .. }
+            | BytecodeInstruction::ConstChar { dest, .. }
+            | BytecodeInstruction::ConstInt32 { dest, .. }
+            | BytecodeInstruction::ConstInt64 { dest, .. }
+            | BytecodeInstruction::ConstFloat32 { dest, .. }
+            | BytecodeInstruction::ConstFloat64 { dest, .. }
+            | BytecodeInstruction::ConstString { dest, .. }
+            | BytecodeInstruction::NewObject { dest, .. } => {
+                defs.push(dest);
+            }
+
+            BytecodeInstruction::JumpLoop { offset: distance } => {
+                flow = Flow::Jump(BytecodeOffset(offset.to_u32() - distance));
+            }
+
+            BytecodeInstruction::LoopStart => {}
+
+            BytecodeInstruction::Jump { offset: distance } => {
+                flow = Flow::Jump(BytecodeOffset(offset.to_u32() + distance));
+            }
+
+            BytecodeInstruction::JumpConst { idx } => {
+                flow = Flow::Jump(jump_const(idx));
+            }
+
+            BytecodeInstruction::JumpIfFalse {
+                opnd,
+                offset: distance,
+            }
+            | BytecodeInstruction::JumpIfTrue {
+                opnd,
+                offset: distance,
+            } => {
+                uses.push(opnd);
+                flow = Flow::Branch(BytecodeOffset(offset.to_u32() + distance));
+            }
+
+            BytecodeInstruction::JumpIfFalseConst { opnd, idx }
+            | BytecodeInstruction::JumpIfTrueConst { opnd, idx } => {
+                uses.push(opnd);
+                flow = Flow::Branch(jump_const(idx));
+            }
+
+            BytecodeInstruction::InvokeDirect { dest, .. }
+            | BytecodeInstruction::InvokeVirtual { dest, .. }
+            | BytecodeInstruction::InvokeStatic { dest, .. }
+            | BytecodeInstruction::InvokeLambda { dest, .. }
+            | BytecodeInstruction::InvokeGenericStatic { dest, .. }
+            | BytecodeInstruction::InvokeGenericDirect { dest, .. }
+            | BytecodeInstruction::NewObjectInitialized { dest, .. }
+            | BytecodeInstruction::NewTuple { dest, .. }
+            | BytecodeInstruction::NewEnum { dest, .. }
+            | BytecodeInstruction::NewStruct { dest, .. }
+            | BytecodeInstruction::NewLambda { dest, .. } => {
+                uses.append(&mut arguments);
+                defs.push(dest);
+            }
+
+            BytecodeInstruction::NewArray { dest, length, .. } => {
+                uses.push(length);
+                defs.push(dest);
+            }
+
+            BytecodeInstruction::ArrayLength { dest, arr } => {
+                uses.push(arr);
+                defs.push(dest);
+            }
+
+            BytecodeInstruction::LoadArray { dest, arr, idx } => {
+                uses.push(arr);
+                uses.push(idx);
+                defs.push(dest);
+            }
+
+            BytecodeInstruction::StoreArray { src, arr, idx } => {
+                uses.push(src);
+                uses.push(arr);
+                uses.push(idx);
+            }
+
+            BytecodeInstruction::Ret { opnd } => {
+                uses.push(opnd);
+                flow = Flow::Return;
+            }
+        }
+
+        instructions.push(InstructionInfo {
+            offset,
+            uses,
+            defs,
+            flow,
+        });
+    }
+
+    assert!(arguments.is_empty());
+    instructions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BytecodeLiveness, LiveRange};
+    use crate::bytecode::{BytecodeType, BytecodeWriter, ConstPoolEntry, Register};
+    use dora_parser::lexer::position::Position;
+
+    fn live_at(liveness: &BytecodeLiveness, idx: usize) -> Vec<Register> {
+        liveness.live_in[idx].ones().map(Register).collect()
+    }
+
+    #[test]
+    fn test_straight_line() {
+        let mut writer = BytecodeWriter::new();
+        let r0 = writer.add_register(BytecodeType::Int32);
+        let r1 = writer.add_register(BytecodeType::Int32);
+        let r2 = writer.add_register(BytecodeType::Int32);
+        writer.emit_const_int32(r0, 1);
+        writer.emit_const_int32(r1, 2);
+        writer.set_position(Position::new(1, 1));
+        writer.emit_add(r2, r0, r1);
+        writer.emit_ret(r2);
+        let fct = writer.generate();
+
+        let liveness = BytecodeLiveness::analyze(&fct);
+        assert_eq!(live_at(&liveness, 0), vec![]);
+        assert_eq!(live_at(&liveness, 1), vec![r0]);
+        assert_eq!(live_at(&liveness, 2), vec![r0, r1]);
+        assert_eq!(live_at(&liveness, 3), vec![r2]);
+
+        assert_eq!(
+            liveness.live_range(r0),
+            Some(LiveRange { start: 0, end: 2 })
+        );
+        assert_eq!(
+            liveness.live_range(r2),
+            Some(LiveRange { start: 2, end: 3 })
+        );
+    }
+
+    #[test]
+    fn test_dead_register() {
+        let mut writer = BytecodeWriter::new();
+        let r0 = writer.add_register(BytecodeType::Ptr);
+        let r1 = writer.add_register(BytecodeType::Int32);
+        let r2 = writer.add_register(BytecodeType::Int32);
+        writer.emit_const_string(r0, "dead".into());
+        writer.emit_const_int32(r1, 1);
+        writer.emit_ret(r1);
+        let fct = writer.generate();
+
+        let liveness = BytecodeLiveness::analyze(&fct);
+        assert_eq!(live_at(&liveness, 1), vec![]);
+        assert_eq!(
+            liveness.live_range(r0),
+            Some(LiveRange { start: 0, end: 0 })
+        );
+        assert_eq!(liveness.live_range(r2), None);
+    }
+
+    #[test]
+    fn test_loop() {
+        let mut writer = BytecodeWriter::new();
+        let r0 = writer.add_register(BytecodeType::Ptr);
+        let r1 = writer.add_register(BytecodeType::Bool);
+        let r2 = writer.add_register(BytecodeType::Ptr);
+        let r3 = writer.add_register(BytecodeType::Ptr);
+        writer.emit_const_string(r0, "loop".into());
+        let lbl_start = writer.define_label();
+        writer.emit_loop_start();
+        writer.emit_const_true(r1);
+        let lbl_end = writer.create_label();
+        writer.emit_jump_if_false(r1, lbl_end);
+        writer.emit_mov(r2, r0);
+        writer.emit_jump_loop(lbl_start);
+        writer.bind_label(lbl_end);
+        writer.emit_const_string(r3, "end".into());
+        writer.emit_ret(r3);
+        let fct = writer.generate();
+
+        let liveness = BytecodeLiveness::analyze(&fct);
+
+        // r0 is read in every iteration and needs to stay alive across
+        // the backward jump, but is dead after the loop.
+        assert_eq!(live_at(&liveness, 1), vec![r0]);
+        assert_eq!(live_at(&liveness, 3), vec![r0, r1]);
+        assert_eq!(live_at(&liveness, 5), vec![r0]);
+        assert_eq!(live_at(&liveness, 6), vec![]);
+        assert_eq!(live_at(&liveness, 7), vec![r3]);
+        assert_eq!(
+            liveness.live_range(r0),
+            Some(LiveRange { start: 0, end: 5 })
+        );
+        assert_eq!(
+            liveness.live_range(r2),
+            Some(LiveRange { start: 4, end: 4 })
+        );
+    }
+
+    #[test]
+    fn test_push_register() {
+        let mut writer = BytecodeWriter::new();
+        let r0 = writer.add_register(BytecodeType::Ptr);
+        let r1 = writer.add_register(BytecodeType::Ptr);
+        let fct_idx = writer.add_const(ConstPoolEntry::Int32(0));
+        writer.emit_const_string(r0, "arg".into());
+        writer.emit_push_register(r0);
+        writer.set_position(Position::new(1, 1));
+        writer.emit_invoke_static(r1, fct_idx);
+        writer.emit_ret(r1);
+        let fct = writer.generate();
+
+        let liveness = BytecodeLiveness::analyze(&fct);
+
+        // arguments stay alive until the invocation reads them.
+        assert_eq!(live_at(&liveness, 1), vec![r0]);
+        assert_eq!(live_at(&liveness, 2), vec![r0]);
+        assert_eq!(live_at(&liveness, 3), vec![r1]);
     }
 }
@@ -6,3 +6,6 @@ pub mod codegen;
 pub mod dora_entry_stub;
 pub mod dora_exit_stubs;
 pub mod lazy_compilation_stub;
+pub mod pre_write_barrier_stub;
+pub mod tier_up_stub;
+pub mod unwind_stubs;
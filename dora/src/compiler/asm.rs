@@ -13,7 +13,7 @@ use crate::masm::{CodeDescriptor, CondCode, Label, MacroAssembler, Mem, ScratchR
 use crate::mode::MachineMode;
 use crate::stdlib;
 use crate::threads::ThreadLocalData;
-use crate::vm::{GcPoint, LazyCompilationSite, Trap, VM};
+use crate::vm::{GcPoint, HotnessCounter, LazyCompilationSite, Trap, VM};
 
 pub struct BaselineAssembler<'a> {
     masm: MacroAssembler,
@@ -66,6 +66,66 @@ impl<'a> BaselineAssembler<'a> {
         ));
     }
 
+    pub fn hotness_check(&mut self, counter: Address, threshold: u32, pos: Position) {
+        let lbl_tier_up = self.masm.create_label();
+        self.load_hotness_counter(REG_TMP1, counter);
+
+        let invocations = HotnessCounter::invocations_offset();
+        self.masm.load_mem(
+            MachineMode::Int32,
+            REG_TMP2.into(),
+            Mem::Base(REG_TMP1, invocations),
+        );
+        self.masm
+            .int_add_imm(MachineMode::Int32, REG_TMP2, REG_TMP2, 1);
+        self.masm.store_mem(
+            MachineMode::Int32,
+            Mem::Base(REG_TMP1, invocations),
+            REG_TMP2.into(),
+        );
+
+        self.masm.load_mem(
+            MachineMode::Int32,
+            REG_TMP1.into(),
+            Mem::Base(REG_TMP1, HotnessCounter::back_edges_offset()),
+        );
+        self.masm
+            .int_add(MachineMode::Int32, REG_TMP2, REG_TMP2, REG_TMP1);
+        self.masm
+            .cmp_reg_imm(MachineMode::Int32, REG_TMP2, threshold as i32);
+        self.masm.jump_if(CondCode::UnsignedGreaterEq, lbl_tier_up);
+
+        let lbl_return = self.masm.create_label();
+        self.masm.bind_label(lbl_return);
+
+        self.slow_paths
+            .push(SlowPathKind::TierUp(lbl_tier_up, lbl_return, counter, pos));
+    }
+
+    pub fn count_back_edge(&mut self, counter: Address) {
+        self.load_hotness_counter(REG_TMP1, counter);
+
+        let back_edges = HotnessCounter::back_edges_offset();
+        self.masm.load_mem(
+            MachineMode::Int32,
+            REG_TMP2.into(),
+            Mem::Base(REG_TMP1, back_edges),
+        );
+        self.masm
+            .int_add_imm(MachineMode::Int32, REG_TMP2, REG_TMP2, 1);
+        self.masm.store_mem(
+            MachineMode::Int32,
+            Mem::Base(REG_TMP1, back_edges),
+            REG_TMP2.into(),
+        );
+    }
+
+    fn load_hotness_counter(&mut self, dest: Reg, counter: Address) {
+        let disp = self.masm.add_addr(counter);
+        let pos = self.masm.pos() as i32;
+        self.masm.load_constpool(dest, disp + pos);
+    }
+
     pub fn assert(&mut self, value: Reg, pos: Position) {
         let lbl_assert = self.masm.create_label();
         self.masm
@@ -240,6 +300,25 @@ impl<'a> BaselineAssembler<'a> {
         self.masm.emit_barrier(src, card_table_offset);
     }
 
+    pub fn emit_pre_write_barrier(&mut self, slot: Mem) {
+        let lbl_slow_path = self.masm.create_label();
+        self.masm.load_mem(
+            MachineMode::Int8,
+            REG_TMP2.into(),
+            Mem::Base(REG_THREAD, ThreadLocalData::concurrent_marking_offset()),
+        );
+        self.masm
+            .test_and_jump_if(CondCode::NonZero, REG_TMP2, lbl_slow_path);
+        let lbl_return = self.masm.create_label();
+        self.masm.bind_label(lbl_return);
+
+        self.slow_paths.push(SlowPathKind::PreWriteBarrier(
+            lbl_slow_path,
+            lbl_return,
+            slot,
+        ));
+    }
+
     pub fn emit_bailout(&mut self, lbl: Label, trap: Trap, pos: Position) {
         self.masm.emit_bailout(lbl, trap, pos);
     }
@@ -879,6 +958,14 @@ impl<'a> BaselineAssembler<'a> {
                 SlowPathKind::Safepoint(lbl_start, lbl_return, pos, gcpoint) => {
                     self.slow_path_safepoint(lbl_start, lbl_return, pos, gcpoint);
                 }
+
+                SlowPathKind::PreWriteBarrier(lbl_start, lbl_return, slot) => {
+                    self.slow_path_pre_write_barrier(lbl_start, lbl_return, slot);
+                }
+
+                SlowPathKind::TierUp(lbl_start, lbl_return, counter, pos) => {
+                    self.slow_path_tier_up(lbl_start, lbl_return, counter, pos);
+                }
             }
         }
 
@@ -932,6 +1019,52 @@ impl<'a> BaselineAssembler<'a> {
         self.masm.jump(lbl_return);
     }
 
+    fn slow_path_tier_up(
+        &mut self,
+        lbl_start: Label,
+        lbl_return: Label,
+        counter: Address,
+        pos: Position,
+    ) {
+        self.masm.bind_label(lbl_start);
+        self.masm.emit_comment("slow path tier up".into());
+
+        // The stub preserves the argument registers and returns the address
+        // of the optimized code in REG_TMP1, or null if execution should
+        // continue in this function. Nothing is stored in the frame yet.
+        self.load_hotness_counter(REG_TMP2, counter);
+        self.masm.raw_call(self.vm.stubs.tier_up());
+        self.masm.emit_gcpoint(GcPoint::new());
+        self.masm.emit_position(pos);
+        self.masm.cmp_zero(MachineMode::Ptr, REG_TMP1);
+        self.masm.jump_if(CondCode::Equal, lbl_return);
+
+        // Remove the stack frame and continue in the optimized code, the
+        // arguments are still in place.
+        self.masm.epilog_without_return();
+        self.masm.jump_reg(REG_TMP1);
+    }
+
+    fn slow_path_pre_write_barrier(&mut self, lbl_start: Label, lbl_return: Label, slot: Mem) {
+        self.masm.bind_label(lbl_start);
+        self.masm.emit_comment("slow path pre-write barrier".into());
+        self.masm.load_mem(MachineMode::Ptr, REG_TMP2.into(), slot);
+        self.masm.cmp_zero(MachineMode::Ptr, REG_TMP2);
+        self.masm.jump_if(CondCode::Equal, lbl_return);
+        self.masm.store_mem(
+            MachineMode::Ptr,
+            Mem::Base(REG_THREAD, ThreadLocalData::pre_barrier_value_offset()),
+            REG_TMP2.into(),
+        );
+
+        // The stub preserves all registers, only REG_TMP2 is used for the call.
+        let disp = self.masm.add_addr(self.vm.stubs.pre_write_barrier());
+        let pos = self.masm.pos() as i32;
+        self.masm.load_constpool(REG_TMP2, disp + pos);
+        self.masm.call_reg(REG_TMP2);
+        self.masm.jump(lbl_return);
+    }
+
     fn slow_path_global(
         &mut self,
         lbl_start: Label,
@@ -971,4 +1104,6 @@ enum SlowPathKind {
     Safepoint(Label, Label, Position, GcPoint),
     Assert(Label, Position),
     InitializeGlobal(Label, Label, FctDefinitionId, Address, Position, GcPoint),
+    PreWriteBarrier(Label, Label, Mem),
+    TierUp(Label, Label, Address, Position),
 }
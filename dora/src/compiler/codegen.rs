@@ -32,11 +32,8 @@ pub fn generate_fct(vm: &VM, fct: &FctDefinition, type_params: &SourceTypeArray)
         return instruction_start;
     }
 
-    let compiler = if fct.is_optimize_immediately {
-        CompilerName::Boots
-    } else {
-        CompilerName::Cannon
-    };
+    let mut compiler = select_compiler(vm, fct, type_params);
+    let tiered = matches!(compiler, CompilerName::Cannon) && should_tier_up(vm, fct, type_params);
 
     let bytecode_fct = fct.bytecode.as_ref().expect("bytecode missing");
 
@@ -49,13 +46,32 @@ pub fn generate_fct(vm: &VM, fct: &FctDefinition, type_params: &SourceTypeArray)
     }
 
     let code_descriptor = match compiler {
-        CompilerName::Cannon => {
+        CompilerName::Cannon => None,
+        CompilerName::Boots => boots::compile(vm, &fct, &type_params),
+    };
+
+    // Boots bails out on functions it can't handle yet, fall back to
+    // the baseline compiler for those.
+    let code_descriptor = match code_descriptor {
+        Some(code_descriptor) => code_descriptor,
+        None => {
+            compiler = CompilerName::Cannon;
+
             let pos = fct.pos;
             let params = fct.params_with_self();
             let params = SourceTypeArray::with(params.to_vec());
             let return_type = fct.return_type.clone();
             let has_variadic_parameter = fct.is_variadic;
 
+            let hotness_counter = if tiered {
+                Some(
+                    vm.compilation_database
+                        .hotness_counter(fct.id(), type_params.clone()),
+                )
+            } else {
+                None
+            };
+
             let compilation_data = CompilationData {
                 bytecode_fct,
                 params,
@@ -63,6 +79,7 @@ pub fn generate_fct(vm: &VM, fct: &FctDefinition, type_params: &SourceTypeArray)
                 return_type,
                 type_params,
                 pos,
+                hotness_counter,
 
                 emit_debug,
                 emit_code_comments: emit_asm,
@@ -70,7 +87,6 @@ pub fn generate_fct(vm: &VM, fct: &FctDefinition, type_params: &SourceTypeArray)
 
             cannon::compile(vm, compilation_data, CompilationFlags::jit())
         }
-        CompilerName::Boots => boots::compile(vm, &fct, &type_params),
     };
 
     let code = install_code(vm, code_descriptor, CodeKind::DoraFct(fct.id()));
@@ -111,6 +127,81 @@ pub fn generate_fct(vm: &VM, fct: &FctDefinition, type_params: &SourceTypeArray)
     code.instruction_start()
 }
 
+pub fn select_compiler(
+    vm: &VM,
+    fct: &FctDefinition,
+    type_params: &SourceTypeArray,
+) -> CompilerName {
+    // --compiler=boots only applies to the program's own functions: the
+    // standard library and Boots itself stay on the baseline compiler,
+    // since Boots can't be used to compile itself.
+    let wants_boots = fct.is_optimize_immediately
+        || (matches!(vm.args.compiler(), CompilerName::Boots)
+            && vm.program_package_id == Some(fct.package_id));
+
+    if wants_boots && vm.known.functions.compile.is_some() && boots::supports(fct, type_params) {
+        CompilerName::Boots
+    } else {
+        CompilerName::Cannon
+    }
+}
+
+// Baseline code for functions Boots can compile counts invocations and loop
+// iterations with --tiered-compilation. Like with --compiler=boots only the
+// program's own functions are considered.
+fn should_tier_up(vm: &VM, fct: &FctDefinition, type_params: &SourceTypeArray) -> bool {
+    vm.args.flag_tiered_compilation
+        && vm.program_package_id == Some(fct.package_id)
+        && vm.known.functions.compile.is_some()
+        && boots::supports(fct, type_params)
+}
+
+// Recompiles a hot function with Boots and makes the compilation database
+// return the optimized code from now on. Returns None if Boots bails out.
+pub fn optimize(vm: &VM, id: FctDefinitionId, type_params: &SourceTypeArray) -> Option<Address> {
+    let fct = vm.fcts.idx(id);
+    let fct = fct.read();
+
+    let start = if vm.args.flag_emit_compiler {
+        Some(Instant::now())
+    } else {
+        None
+    };
+
+    let code_descriptor = boots::compile(vm, &fct, type_params)?;
+    let code = install_code(vm, code_descriptor, CodeKind::DoraFct(fct.id()));
+    let code_id = vm.add_code(code.clone());
+
+    vm.compilation_database
+        .replace_compilation(fct.id(), type_params.clone(), code_id);
+
+    if vm.args.flag_emit_compiler {
+        let duration = start.expect("missing start time").elapsed();
+        println!(
+            "tier up {} using {} in {}ms.",
+            fct.display_name(vm),
+            CompilerName::Boots,
+            (duration.as_micros() as f64) / 1000.0
+        );
+    }
+
+    if vm.args.flag_enable_perf {
+        os::perf::register_with_perf(&code, vm, fct.ast.name);
+    }
+
+    if should_emit_asm(vm, &fct) {
+        disassembler::disassemble(
+            vm,
+            &fct,
+            type_params,
+            &code,
+            vm.args.flag_asm_syntax.unwrap_or(AsmSyntax::Att),
+        );
+    }
+
+    Some(code.instruction_start())
+}
+
 pub fn should_emit_debug(vm: &VM, fct: &FctDefinition) -> bool {
     if let Some(ref dbg_names) = vm.args.flag_emit_debug {
         fct_pattern_match(vm, fct, dbg_names)
@@ -248,6 +339,7 @@ pub struct CompilationData<'a> {
     pub return_type: SourceType,
     pub type_params: &'a SourceTypeArray,
     pub pos: Position,
+    pub hotness_counter: Option<Address>,
 
     pub emit_debug: bool,
     pub emit_code_comments: bool,
@@ -7,6 +7,7 @@ use crate::cpu::{
     CCALL_REG_PARAMS, FREG_PARAMS, REG_FP, REG_PARAMS, REG_RESULT, REG_SP, REG_THREAD, REG_TMP1,
 };
 use crate::gc::Address;
+use crate::handle::{handle, handle_scope, Handle};
 use crate::language::generator::register_bty_from_ty;
 use crate::language::sem_analysis::{
     find_trait_impl, AnalysisData, FctDefinition, FctDefinitionId, FctParent, TypeParamId,
@@ -15,12 +16,13 @@ use crate::language::ty::{SourceType, SourceTypeArray};
 use crate::masm::{MacroAssembler, Mem};
 use crate::mem;
 use crate::mode::MachineMode;
-use crate::object::Obj;
+use crate::object::{Obj, Ref};
 use crate::os;
 use crate::stack::DoraToNativeInfo;
 use crate::threads::ThreadLocalData;
 use crate::vm::{
-    get_vm, install_code_stub, ClassInstanceId, Code, CodeKind, LazyCompilationSite, ShapeKind, VM,
+    get_vm, install_code_stub, specialize_enum_id_params, specialize_type, ClassInstanceId, Code,
+    CodeKind, EnumLayout, LazyCompilationSite, ShapeKind, VM,
 };
 
 // This code generates the compiler stub, there should only be one instance
@@ -126,6 +128,16 @@ impl<'a> DoraCompileGen<'a> {
             CCALL_REG_PARAMS[2].into(),
             Mem::Base(REG_SP, offset_params + mem::ptr_width()),
         );
+        self.masm
+            .copy_reg(MachineMode::Ptr, CCALL_REG_PARAMS[3], REG_SP);
+        if offset_params != 0 {
+            self.masm.int_add_imm(
+                MachineMode::Ptr,
+                CCALL_REG_PARAMS[3],
+                CCALL_REG_PARAMS[3],
+                offset_params as i64,
+            );
+        }
         self.masm
             .raw_call(Address::from_ptr(compile_request as *const u8));
 
@@ -191,7 +203,12 @@ impl<'a> DoraCompileGen<'a> {
     }
 }
 
-fn compile_request(ra: usize, receiver1: Address, receiver2: Address) -> Address {
+fn compile_request(
+    ra: usize,
+    receiver1: Address,
+    receiver2: Address,
+    saved_params: Address,
+) -> Address {
     let vm = get_vm();
 
     let lazy_compilation_site = {
@@ -210,23 +227,28 @@ fn compile_request(ra: usize, receiver1: Address, receiver2: Address) -> Address
 
     match lazy_compilation_site {
         LazyCompilationSite::Direct(fct_id, disp, ref type_params) => {
-            patch_direct_call(vm, ra, fct_id, type_params, disp)
+            patch_direct_call(vm, ra, fct_id, type_params, disp, saved_params)
         }
 
         LazyCompilationSite::Virtual(receiver_is_first, fct_id, vtable_index, ref type_params) => {
+            let receiver = if receiver_is_first {
+                receiver1
+            } else {
+                receiver2
+            };
+
             patch_virtual_call(
                 vm,
-                receiver_is_first,
-                receiver1,
-                receiver2,
+                receiver,
                 fct_id,
                 vtable_index,
                 type_params,
+                saved_params,
             )
         }
 
         LazyCompilationSite::Lambda(receiver_is_first) => {
-            patch_lambda_call(vm, receiver_is_first, receiver1, receiver2)
+            patch_lambda_call(vm, receiver_is_first, receiver1, receiver2, saved_params)
         }
     }
 }
@@ -236,6 +258,7 @@ fn patch_lambda_call(
     receiver_is_first: bool,
     receiver1: Address,
     receiver2: Address,
+    saved_params: Address,
 ) -> Address {
     let receiver = if receiver_is_first {
         receiver1
@@ -252,7 +275,7 @@ fn patch_lambda_call(
         _ => unreachable!(),
     };
 
-    let fct_ptr = compiler::generate(vm, lambda_id, &type_params);
+    let fct_ptr = generate_preserving_params(vm, lambda_id, &type_params, saved_params);
 
     let methodtable = vtable.table_mut();
     methodtable[0] = fct_ptr.to_usize();
@@ -262,19 +285,12 @@ fn patch_lambda_call(
 
 fn patch_virtual_call(
     vm: &VM,
-    receiver_is_first: bool,
-    receiver1: Address,
-    receiver2: Address,
+    receiver: Address,
     trait_fct_id: FctDefinitionId,
     vtable_index: u32,
     type_params: &SourceTypeArray,
+    saved_params: Address,
 ) -> Address {
-    let receiver = if receiver_is_first {
-        receiver1
-    } else {
-        receiver2
-    };
-
     let obj = unsafe { &mut *receiver.to_mut_ptr::<Obj>() };
     let vtable = obj.header().vtbl();
     let class_instance = vtable.class_instance();
@@ -290,7 +306,7 @@ fn patch_virtual_call(
                 object_ty.clone(),
             );
 
-            compiler::generate(vm, thunk_fct_id, &all_type_params)
+            generate_preserving_params(vm, thunk_fct_id, &all_type_params, saved_params)
         }
 
         _ => unreachable!(),
@@ -308,8 +324,9 @@ fn patch_direct_call(
     fct_id: FctDefinitionId,
     type_params: &SourceTypeArray,
     disp: i32,
+    saved_params: Address,
 ) -> Address {
-    let fct_ptr = compiler::generate(vm, fct_id, type_params);
+    let fct_ptr = generate_preserving_params(vm, fct_id, type_params, saved_params);
     let fct_addr: *mut usize = (ra as isize - disp as isize) as *mut _;
 
     // update function pointer in data segment
@@ -322,6 +339,112 @@ fn patch_direct_call(
     fct_ptr
 }
 
+// Compiling a function with Boots runs Dora code, which can trigger a moving
+// GC. Waiting for another thread to finish compiling the same function parks
+// the thread as well. The parameters saved by the stub aren't visible to the
+// GC, so object parameters are kept in handles and written back afterwards.
+fn generate_preserving_params(
+    vm: &VM,
+    fct_id: FctDefinitionId,
+    type_params: &SourceTypeArray,
+    saved_params: Address,
+) -> Address {
+    preserving_object_params(vm, fct_id, type_params, saved_params, || {
+        compiler::generate(vm, fct_id, type_params)
+    })
+}
+
+// Runs `f` while the object parameters saved at `saved_params` are kept
+// alive in handles.
+pub(crate) fn preserving_object_params<R, F: FnOnce() -> R>(
+    vm: &VM,
+    fct_id: FctDefinitionId,
+    type_params: &SourceTypeArray,
+    saved_params: Address,
+    f: F,
+) -> R {
+    let slots = object_param_slots(vm, fct_id, type_params, saved_params);
+
+    if slots.is_empty() {
+        return f();
+    }
+
+    handle_scope(|| {
+        let handles: Vec<Handle<Obj>> = slots
+            .iter()
+            .map(|slot| handle(unsafe { *slot.to_ptr::<Ref<Obj>>() }))
+            .collect();
+
+        let result = f();
+
+        for (slot, handle) in slots.iter().zip(handles) {
+            unsafe {
+                *slot.to_mut_ptr::<Address>() = handle.direct_ptr();
+            }
+        }
+
+        result
+    })
+}
+
+// Determines the saved registers holding object parameters, this mirrors
+// the parameter assignment of the baseline compiler.
+fn object_param_slots(
+    vm: &VM,
+    fct_id: FctDefinitionId,
+    type_params: &SourceTypeArray,
+    saved_params: Address,
+) -> Vec<Address> {
+    let fct = vm.fcts.idx(fct_id);
+    let fct = fct.read();
+
+    let return_type = specialize_type(vm, fct.return_type.clone(), type_params);
+    let params = fct.params_with_self();
+
+    // Tuples and structs are returned through an address in the first register.
+    let mut reg_idx = if return_type.is_struct() || return_type.is_tuple() {
+        1
+    } else {
+        0
+    };
+    let mut slots = Vec::new();
+
+    for (idx, param_ty) in params.iter().enumerate() {
+        if reg_idx == REG_PARAMS.len() {
+            break;
+        }
+
+        let is_object = if idx == params.len() - 1 && fct.is_variadic {
+            true
+        } else {
+            let param_ty = specialize_type(vm, param_ty.clone(), type_params);
+
+            match param_ty {
+                SourceType::Unit | SourceType::Float32 | SourceType::Float64 => continue,
+
+                // Both are passed as a pointer to a copy on the caller's stack.
+                SourceType::Tuple(_) | SourceType::Struct(_, _) => false,
+
+                SourceType::Enum(enum_id, type_params) => {
+                    let enum_instance_id = specialize_enum_id_params(vm, enum_id, type_params);
+                    let enum_instance = vm.enum_instances.idx(enum_instance_id);
+                    !matches!(enum_instance.layout, EnumLayout::Int)
+                }
+
+                param_ty => register_bty_from_ty(param_ty).is_ptr(),
+            }
+        };
+
+        if is_object {
+            slots.push(saved_params.offset(reg_idx * mem::ptr_width_usize()));
+        }
+
+        reg_idx += 1;
+    }
+
+    slots
+}
+
 fn ensure_thunk(
     vm: &VM,
     cls_def_id: ClassInstanceId,
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use crate::cpu::{CALLER_SAVED_FREGS, CALLER_SAVED_REGS, CCALL_REG_PARAMS, REG_SP, REG_THREAD};
+use crate::gc;
+use crate::gc::Address;
+use crate::masm::{MacroAssembler, Mem};
+use crate::mem;
+use crate::mode::MachineMode;
+use crate::threads::ThreadLocalData;
+use crate::vm::{install_code_stub, Code, CodeKind, VM};
+
+// This stub is invoked by the pre-write barrier while concurrent marking is active.
+// The barrier stores the overwritten reference in the thread local data before calling
+// this stub. Since barriers are emitted in the middle of instruction sequences,
+// the stub needs to preserve all registers that a call into native code might clobber.
+
+pub fn generate(vm: &VM) -> Arc<Code> {
+    let ngen = PreWriteBarrierGen {
+        vm,
+        masm: MacroAssembler::new(),
+        dbg: vm.args.flag_emit_debug_native,
+    };
+
+    ngen.generate()
+}
+
+struct PreWriteBarrierGen<'a> {
+    vm: &'a VM,
+    masm: MacroAssembler,
+    dbg: bool,
+}
+
+impl<'a> PreWriteBarrierGen<'a> {
+    pub fn generate(mut self) -> Arc<Code> {
+        let offset_regs = if cfg!(target_family = "windows") {
+            32
+        } else {
+            0
+        };
+        let offset_fregs = offset_regs + CALLER_SAVED_REGS.len() as i32 * mem::ptr_width();
+        let framesize = offset_fregs + CALLER_SAVED_FREGS.len() as i32 * mem::ptr_width();
+        let framesize = mem::align_i32(framesize, 16);
+
+        if self.dbg {
+            self.masm.debug();
+        }
+
+        self.masm.prolog(framesize);
+
+        for (idx, &reg) in CALLER_SAVED_REGS.iter().enumerate() {
+            self.masm.store_mem(
+                MachineMode::Ptr,
+                Mem::Base(REG_SP, offset_regs + idx as i32 * mem::ptr_width()),
+                reg.into(),
+            );
+        }
+
+        for (idx, &freg) in CALLER_SAVED_FREGS.iter().enumerate() {
+            self.masm.store_mem(
+                MachineMode::Float64,
+                Mem::Base(REG_SP, offset_fregs + idx as i32 * mem::ptr_width()),
+                freg.into(),
+            );
+        }
+
+        self.masm.load_mem(
+            MachineMode::Ptr,
+            CCALL_REG_PARAMS[0].into(),
+            Mem::Base(REG_THREAD, ThreadLocalData::pre_barrier_value_offset()),
+        );
+        self.masm
+            .raw_call(Address::from_ptr(gc::pre_write_barrier_slow as *const u8));
+
+        for (idx, &freg) in CALLER_SAVED_FREGS.iter().enumerate() {
+            self.masm.load_mem(
+                MachineMode::Float64,
+                freg.into(),
+                Mem::Base(REG_SP, offset_fregs + idx as i32 * mem::ptr_width()),
+            );
+        }
+
+        for (idx, &reg) in CALLER_SAVED_REGS.iter().enumerate() {
+            self.masm.load_mem(
+                MachineMode::Ptr,
+                reg.into(),
+                Mem::Base(REG_SP, offset_regs + idx as i32 * mem::ptr_width()),
+            );
+        }
+
+        self.masm.epilog();
+
+        let code_descriptor = self.masm.code();
+        install_code_stub(self.vm, code_descriptor, CodeKind::PreWriteBarrierStub)
+    }
+}
@@ -0,0 +1,285 @@
+use std::mem::size_of;
+use std::sync::Arc;
+
+use crate::compiler;
+use crate::compiler::lazy_compilation_stub::preserving_object_params;
+use crate::cpu::{
+    CCALL_REG_PARAMS, FREG_PARAMS, REG_FP, REG_PARAMS, REG_RESULT, REG_SP, REG_THREAD, REG_TMP1,
+    REG_TMP2,
+};
+use crate::gc::Address;
+use crate::masm::{MacroAssembler, Mem};
+use crate::mem;
+use crate::mode::MachineMode;
+use crate::object::Obj;
+use crate::os;
+use crate::stack::DoraToNativeInfo;
+use crate::threads::ThreadLocalData;
+use crate::vm::{
+    get_vm, install_code_stub, Code, CodeKind, HotnessCounter, LazyCompilationSite, TierState, VM,
+};
+
+// This code generates the tier-up stub. Functions compiled with the baseline
+// compiler call this stub from their prolog once their hotness counter passed
+// the threshold, the counter is passed in REG_TMP2. The stub recompiles the
+// function with Boots and returns the address of the optimized code in REG_TMP1,
+// or null when execution should continue in the baseline code. Argument
+// registers are preserved.
+
+pub fn generate(vm: &VM) -> Arc<Code> {
+    let ngen = TierUpGen {
+        vm,
+        masm: MacroAssembler::new(),
+        dbg: vm.args.flag_emit_debug_compile,
+    };
+
+    ngen.generate()
+}
+
+struct TierUpGen<'a> {
+    vm: &'a VM,
+    masm: MacroAssembler,
+    dbg: bool,
+}
+
+impl<'a> TierUpGen<'a> {
+    pub fn generate(mut self) -> Arc<Code> {
+        let offset_shadow_stack = 0;
+        let offset_dtn = offset_shadow_stack
+            + if cfg!(target_family = "windows") {
+                32
+            } else {
+                0
+            };
+        let offset_params = offset_dtn + size_of::<DoraToNativeInfo>() as i32;
+        let offset_thread =
+            offset_params + (FREG_PARAMS.len() + REG_PARAMS.len()) as i32 * mem::ptr_width();
+        let framesize = mem::align_i32(offset_thread + mem::ptr_width(), 16) as i32;
+
+        if self.dbg {
+            self.masm.debug();
+        }
+
+        self.masm.prolog(framesize);
+
+        // store params passed in registers on the stack
+        self.store_params(offset_params);
+
+        // prepare the native call
+        self.masm.load_mem(
+            MachineMode::Ptr,
+            REG_TMP1.into(),
+            Mem::Base(REG_THREAD, ThreadLocalData::dtn_offset()),
+        );
+
+        self.masm.store_mem(
+            MachineMode::Ptr,
+            Mem::Base(REG_SP, offset_dtn + DoraToNativeInfo::last_offset()),
+            REG_TMP1.into(),
+        );
+
+        self.masm.store_mem(
+            MachineMode::Ptr,
+            Mem::Base(REG_SP, offset_dtn + DoraToNativeInfo::fp_offset()),
+            REG_FP.into(),
+        );
+
+        self.masm.copy_pc(REG_TMP1);
+
+        self.masm.store_mem(
+            MachineMode::Ptr,
+            Mem::Base(REG_SP, offset_dtn + DoraToNativeInfo::pc_offset()),
+            REG_TMP1.into(),
+        );
+
+        self.masm.copy_reg(MachineMode::Ptr, REG_TMP1, REG_SP);
+        if offset_dtn != 0 {
+            self.masm
+                .int_add_imm(MachineMode::Ptr, REG_TMP1, REG_TMP1, offset_dtn as i64);
+        }
+
+        self.masm.store_mem(
+            MachineMode::Ptr,
+            Mem::Base(REG_THREAD, ThreadLocalData::dtn_offset()),
+            REG_TMP1.into(),
+        );
+
+        // invoke the compiler with the frame of the function to optimize
+        self.masm
+            .copy_reg(MachineMode::Ptr, CCALL_REG_PARAMS[2], REG_TMP2);
+        self.masm.load_mem(
+            MachineMode::Ptr,
+            CCALL_REG_PARAMS[0].into(),
+            Mem::Base(REG_FP, 0),
+        );
+        self.masm
+            .copy_reg(MachineMode::Ptr, CCALL_REG_PARAMS[1], REG_SP);
+        if offset_params != 0 {
+            self.masm.int_add_imm(
+                MachineMode::Ptr,
+                CCALL_REG_PARAMS[1],
+                CCALL_REG_PARAMS[1],
+                offset_params as i64,
+            );
+        }
+        self.masm
+            .raw_call(Address::from_ptr(tier_up_request as *const u8));
+
+        self.masm.load_mem(
+            MachineMode::Ptr,
+            REG_TMP1.into(),
+            Mem::Base(REG_SP, offset_dtn + DoraToNativeInfo::last_offset()),
+        );
+
+        self.masm.store_mem(
+            MachineMode::Ptr,
+            Mem::Base(REG_THREAD, ThreadLocalData::dtn_offset()),
+            REG_TMP1.into(),
+        );
+
+        self.masm.copy_reg(MachineMode::Ptr, REG_TMP1, REG_RESULT);
+
+        // restore argument registers from the stack
+        self.load_params(offset_params);
+
+        // return to the baseline code, which decides where to continue
+        self.masm.epilog();
+
+        let code_descriptor = self.masm.code();
+        install_code_stub(self.vm, code_descriptor, CodeKind::CompileStub)
+    }
+
+    fn store_params(&mut self, mut offset: i32) {
+        for reg in &REG_PARAMS {
+            self.masm
+                .store_mem(MachineMode::Ptr, Mem::Base(REG_SP, offset), (*reg).into());
+            offset += mem::ptr_width();
+        }
+
+        for reg in &FREG_PARAMS {
+            self.masm.store_mem(
+                MachineMode::Float64,
+                Mem::Base(REG_SP, offset),
+                (*reg).into(),
+            );
+            offset += mem::ptr_width();
+        }
+    }
+
+    fn load_params(&mut self, mut offset: i32) {
+        for reg in &REG_PARAMS {
+            self.masm
+                .load_mem(MachineMode::Ptr, (*reg).into(), Mem::Base(REG_SP, offset));
+            offset += mem::ptr_width();
+        }
+
+        for reg in &FREG_PARAMS {
+            self.masm.load_mem(
+                MachineMode::Float64,
+                (*reg).into(),
+                Mem::Base(REG_SP, offset),
+            );
+            offset += mem::ptr_width();
+        }
+    }
+}
+
+fn tier_up_request(fp: usize, saved_params: Address, counter: Address) -> Address {
+    let vm = get_vm();
+    let counter = unsafe { &*counter.to_ptr::<HotnessCounter>() };
+
+    let fct_ptr = match counter.state() {
+        TierState::Baseline => {
+            // Another thread might have started the compilation in the meantime.
+            if !counter.start_optimization() {
+                return Address::null();
+            }
+
+            let fct_ptr = preserving_object_params(
+                vm,
+                counter.fct_id,
+                &counter.type_params,
+                saved_params,
+                || compiler::codegen::optimize(vm, counter.fct_id, &counter.type_params),
+            );
+
+            counter.finish_optimization(fct_ptr);
+
+            match fct_ptr {
+                Some(fct_ptr) => fct_ptr,
+                None => {
+                    counter.reset();
+                    return Address::null();
+                }
+            }
+        }
+
+        // Keep running the baseline code while the function is compiled.
+        TierState::Optimizing => return Address::null(),
+
+        // Boots can't compile this function, start counting again to keep
+        // the slow path rare.
+        TierState::Failed => {
+            counter.reset();
+            return Address::null();
+        }
+
+        TierState::Optimized(fct_ptr) => fct_ptr,
+    };
+
+    patch_call_site(vm, fp, saved_params, counter, fct_ptr);
+
+    fct_ptr
+}
+
+// Call sites compiled before the function was optimized still invoke the baseline
+// code. Update the call site of the current invocation, so that the next call
+// from there runs the optimized code directly.
+fn patch_call_site(
+    vm: &VM,
+    fp: usize,
+    saved_params: Address,
+    counter: &HotnessCounter,
+    fct_ptr: Address,
+) {
+    let ra = unsafe { *((fp + mem::ptr_width_usize()) as *const usize) };
+
+    let code_id = match vm.code_map.get(ra.into()) {
+        Some(code_id) => code_id,
+        None => return,
+    };
+
+    let code = vm.code_objects.get(code_id);
+    let offset = ra - code.instruction_start().to_usize();
+
+    match code.lazy_for_offset(offset as u32) {
+        Some(&LazyCompilationSite::Direct(fct_id, disp, ref type_params))
+            if fct_id == counter.fct_id && *type_params == counter.type_params =>
+        {
+            let fct_addr: *mut usize = (ra as isize - disp as isize) as *mut _;
+
+            os::jit_writable();
+            unsafe {
+                *fct_addr = fct_ptr.to_usize();
+            }
+            os::jit_executable();
+        }
+
+        Some(&LazyCompilationSite::Lambda(receiver_is_first)) => {
+            let receiver_idx = if receiver_is_first { 0 } else { 1 };
+            let receiver = unsafe {
+                *saved_params
+                    .offset(receiver_idx * mem::ptr_width_usize())
+                    .to_ptr::<Address>()
+            };
+
+            let obj = unsafe { &mut *receiver.to_mut_ptr::<Obj>() };
+            let vtable = obj.header().vtbl();
+            let methodtable = vtable.table_mut();
+            methodtable[0] = fct_ptr.to_usize();
+        }
+
+        // Trait object thunks are generic and never optimized.
+        _ => {}
+    }
+}
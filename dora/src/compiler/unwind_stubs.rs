@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use crate::cpu::{
+    CALLEE_SAVED_FREGS, CALLEE_SAVED_REGS, CCALL_REG_PARAMS, REG_FP, REG_SP, REG_THREAD, REG_TMP1,
+};
+use crate::masm::{MacroAssembler, Mem};
+use crate::mem;
+use crate::mode::MachineMode;
+use crate::vm::{install_code_stub, Code, CodeKind, VM};
+
+// Like the dora entry stub, but the called function can be left at any
+// point by passing the stack pointer stored in the third argument to the
+// unwind stub. All callee-saved registers are restored in both cases.
+pub fn install_catch_entry(vm: &VM) -> Arc<Code> {
+    let ngen = CatchEntryGen {
+        vm,
+        masm: MacroAssembler::new(),
+        dbg: vm.args.flag_emit_debug_entry,
+    };
+
+    ngen.install()
+}
+
+pub fn install_unwind(vm: &VM) -> Arc<Code> {
+    let mut masm = MacroAssembler::new();
+    masm.unwind_to(CCALL_REG_PARAMS[0]);
+    install_code_stub(vm, masm.code(), CodeKind::DoraStub)
+}
+
+struct CatchEntryGen<'a> {
+    vm: &'a VM,
+    masm: MacroAssembler,
+    dbg: bool,
+}
+
+impl<'a> CatchEntryGen<'a> {
+    pub fn install(mut self) -> Arc<Code> {
+        let saved = CALLEE_SAVED_REGS.len() + CALLEE_SAVED_FREGS.len();
+        let framesize = saved as i32 * mem::ptr_width();
+        let framesize = mem::align_i32(framesize, 16);
+
+        if self.dbg {
+            self.masm.debug();
+        }
+
+        self.masm.prolog(framesize);
+
+        for (idx, &reg) in CALLEE_SAVED_REGS.iter().enumerate() {
+            self.masm.store_mem(
+                MachineMode::Ptr,
+                Mem::Base(REG_SP, idx as i32 * mem::ptr_width()),
+                reg.into(),
+            );
+        }
+
+        for (idx, &reg) in CALLEE_SAVED_FREGS.iter().enumerate() {
+            let offset = (CALLEE_SAVED_REGS.len() + idx) as i32 * mem::ptr_width();
+            self.masm
+                .store_mem(MachineMode::Float64, Mem::Base(REG_SP, offset), reg.into());
+        }
+
+        self.masm.copy_sp(REG_TMP1);
+        self.masm.store_mem(
+            MachineMode::Ptr,
+            Mem::Base(CCALL_REG_PARAMS[2], 0),
+            REG_TMP1.into(),
+        );
+
+        self.masm
+            .copy_reg(MachineMode::Ptr, REG_THREAD, CCALL_REG_PARAMS[0]);
+        self.masm
+            .copy_reg(MachineMode::Ptr, REG_TMP1, CCALL_REG_PARAMS[1]);
+        self.masm.call_reg(REG_TMP1);
+
+        // The frame pointer is only valid when the function returned
+        // normally, recompute it from the stack pointer.
+        self.masm.copy_sp(REG_FP);
+        self.masm
+            .int_add_imm(MachineMode::Ptr, REG_FP, REG_FP, framesize as i64);
+
+        for (idx, &reg) in CALLEE_SAVED_REGS.iter().enumerate() {
+            self.masm.load_mem(
+                MachineMode::Ptr,
+                reg.into(),
+                Mem::Base(REG_SP, idx as i32 * mem::ptr_width()),
+            );
+        }
+
+        for (idx, &reg) in CALLEE_SAVED_FREGS.iter().enumerate() {
+            let offset = (CALLEE_SAVED_REGS.len() + idx) as i32 * mem::ptr_width();
+            self.masm
+                .load_mem(MachineMode::Float64, reg.into(), Mem::Base(REG_SP, offset));
+        }
+
+        self.masm.epilog();
+
+        let code_descriptor = self.masm.code();
+        install_code_stub(self.vm, code_descriptor, CodeKind::DoraStub)
+    }
+}
@@ -151,6 +151,19 @@ pub static CCALL_FREG_PARAMS: [FReg; 8] = [F0, F1, F2, F3, F4, F5, F6, F7];
 
 pub static SCRATCH: [Reg; 5] = [R9, R12, R13, R14, R15];
 
+// registers that need to be preserved by stubs that are invisible to the caller
+pub static CALLER_SAVED_REGS: [Reg; 18] = [
+    R0, R1, R2, R3, R4, R5, R6, R7, R8, R9, R10, R11, R12, R13, R14, R15, R16, R17,
+];
+pub static CALLER_SAVED_FREGS: [FReg; 24] = [
+    F0, F1, F2, F3, F4, F5, F6, F7, F16, F17, F18, F19, F20, F21, F22, F23, F24, F25, F26, F27,
+    F28, F29, F30, F31,
+];
+
+// registers that a called function needs to preserve
+pub static CALLEE_SAVED_REGS: [Reg; 10] = [R19, R20, R21, R22, R23, R24, R25, R26, R27, R28];
+pub static CALLEE_SAVED_FREGS: [FReg; 8] = [F8, F9, F10, F11, F12, F13, F14, F15];
+
 pub const REG_RESULT: Reg = R0;
 pub const REG_TMP1: Reg = R10;
 pub const REG_TMP2: Reg = R11;
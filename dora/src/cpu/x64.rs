@@ -59,6 +59,18 @@ pub static CCALL_FREG_PARAMS: [FReg; 8] = [XMM0, XMM1, XMM2, XMM3, XMM4, XMM5, X
 #[cfg(target_family = "windows")]
 pub static CCALL_FREG_PARAMS: [FReg; 4] = [XMM0, XMM1, XMM2, XMM3];
 
+// registers that a called function needs to preserve
+#[cfg(target_family = "unix")]
+pub static CALLEE_SAVED_REGS: [Reg; 5] = [RBX, R12, R13, R14, R15];
+#[cfg(target_family = "windows")]
+pub static CALLEE_SAVED_REGS: [Reg; 7] = [RBX, RSI, RDI, R12, R13, R14, R15];
+#[cfg(target_family = "unix")]
+pub static CALLEE_SAVED_FREGS: [FReg; 0] = [];
+#[cfg(target_family = "windows")]
+pub static CALLEE_SAVED_FREGS: [FReg; 10] = [
+    XMM6, XMM7, XMM8, XMM9, XMM10, XMM11, XMM12, XMM13, XMM14, XMM15,
+];
+
 pub const REG_RESULT: Reg = RAX;
 pub const REG_TMP1: Reg = R10;
 pub const REG_TMP2: Reg = R11;
@@ -71,6 +83,13 @@ pub static SCRATCH: [Reg; 4] = [RDI, RSI, RDX, RCX];
 #[cfg(target_family = "windows")]
 pub static SCRATCH: [Reg; 4] = [RCX, RDX, R8, R9];
 
+// registers that need to be preserved by stubs that are invisible to the caller
+pub static CALLER_SAVED_REGS: [Reg; 9] = [RAX, RCX, RDX, RSI, RDI, R8, R9, R10, R11];
+pub static CALLER_SAVED_FREGS: [FReg; 16] = [
+    XMM0, XMM1, XMM2, XMM3, XMM4, XMM5, XMM6, XMM7, XMM8, XMM9, XMM10, XMM11, XMM12, XMM13, XMM14,
+    XMM15,
+];
+
 pub const FREG_RESULT: FReg = XMM0;
 
 #[cfg(target_family = "unix")]
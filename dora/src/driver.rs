@@ -1,6 +1,8 @@
 pub use self::start::*;
 
+pub mod analyze_heap;
 #[cfg(feature = "aot")]
 pub mod aot;
 pub mod cmd;
 pub mod start;
+pub mod test_report;
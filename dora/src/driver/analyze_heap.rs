@@ -0,0 +1,434 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+
+use crate::driver::cmd::Args;
+use crate::gc::heap_snapshot::{parse_address, HeapSnapshot};
+
+// Node 0 is a synthetic root referencing all GC roots, objects follow in
+// snapshot order.
+const ROOT: usize = 0;
+
+pub fn analyze_heap(args: &Args) -> i32 {
+    let path = match args.arg_file {
+        Some(ref path) => path,
+        None => {
+            println!("analyze-heap: missing heap snapshot");
+            return 1;
+        }
+    };
+
+    let snapshot = match HeapSnapshot::read(Path::new(path)) {
+        Ok(snapshot) => snapshot,
+        Err(msg) => {
+            println!("analyze-heap: {}", msg);
+            return 1;
+        }
+    };
+
+    let graph = HeapGraph::new(&snapshot);
+
+    if let Some(ref address) = args.flag_path_to {
+        let address = match parse_address(address, 0) {
+            Ok(address) => address,
+            Err(_) => {
+                println!("--path-to: invalid address '{}'", address);
+                return 1;
+            }
+        };
+
+        match graph.path_to(address) {
+            Ok(path) => print!("{}", path),
+            Err(msg) => {
+                println!("{}", msg);
+                return 1;
+            }
+        }
+    } else {
+        print!("{}", graph.class_summary());
+    }
+
+    0
+}
+
+struct HeapGraph<'a> {
+    snapshot: &'a HeapSnapshot,
+    index: HashMap<usize, usize>,
+    successors: Vec<Vec<usize>>,
+}
+
+impl<'a> HeapGraph<'a> {
+    fn new(snapshot: &'a HeapSnapshot) -> HeapGraph<'a> {
+        let index: HashMap<usize, usize> = snapshot
+            .objects
+            .iter()
+            .enumerate()
+            .map(|(idx, object)| (object.address, idx + 1))
+            .collect();
+
+        let mut successors = Vec::with_capacity(snapshot.objects.len() + 1);
+
+        // References to objects outside of the heap (e.g. into the
+        // read-only space) can't retain anything and are dropped.
+        let resolve = |addresses: &mut dyn Iterator<Item = usize>| -> Vec<usize> {
+            let mut nodes: Vec<usize> = addresses
+                .filter_map(|address| index.get(&address).cloned())
+                .collect();
+            nodes.dedup();
+            nodes
+        };
+
+        successors.push(resolve(&mut snapshot.roots.iter().map(|root| root.address)));
+
+        for object in &snapshot.objects {
+            successors.push(resolve(&mut object.references.iter().cloned()));
+        }
+
+        HeapGraph {
+            snapshot,
+            index,
+            successors,
+        }
+    }
+
+    fn nodes(&self) -> usize {
+        self.successors.len()
+    }
+
+    fn size(&self, node: usize) -> usize {
+        if node == ROOT {
+            0
+        } else {
+            self.snapshot.objects[node - 1].size
+        }
+    }
+
+    fn class_name(&self, node: usize) -> &str {
+        &self.snapshot.objects[node - 1].class_name
+    }
+
+    // Nodes reachable from the root in reverse postorder.
+    fn reverse_postorder(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.nodes()];
+        let mut postorder = Vec::with_capacity(self.nodes());
+        let mut stack = vec![(ROOT, 0)];
+        visited[ROOT] = true;
+
+        while let Some((node, next)) = stack.pop() {
+            if next < self.successors[node].len() {
+                stack.push((node, next + 1));
+                let successor = self.successors[node][next];
+
+                if !visited[successor] {
+                    visited[successor] = true;
+                    stack.push((successor, 0));
+                }
+            } else {
+                postorder.push(node);
+            }
+        }
+
+        postorder.reverse();
+        postorder
+    }
+
+    // Computes the immediate dominator of every reachable node with the
+    // iterative algorithm by Cooper, Harvey and Kennedy.
+    fn immediate_dominators(&self, rpo: &[usize]) -> Vec<Option<usize>> {
+        let mut rpo_number = vec![usize::MAX; self.nodes()];
+
+        for (idx, &node) in rpo.iter().enumerate() {
+            rpo_number[node] = idx;
+        }
+
+        let mut predecessors = vec![Vec::new(); self.nodes()];
+
+        for &node in rpo {
+            for &successor in &self.successors[node] {
+                predecessors[successor].push(node);
+            }
+        }
+
+        let mut idom: Vec<Option<usize>> = vec![None; self.nodes()];
+        idom[ROOT] = Some(ROOT);
+
+        let intersect = |idom: &[Option<usize>], mut a: usize, mut b: usize| {
+            while a != b {
+                while rpo_number[a] > rpo_number[b] {
+                    a = idom[a].expect("missing dominator");
+                }
+
+                while rpo_number[b] > rpo_number[a] {
+                    b = idom[b].expect("missing dominator");
+                }
+            }
+
+            a
+        };
+
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for &node in &rpo[1..] {
+                let mut new_idom: Option<usize> = None;
+
+                for &predecessor in &predecessors[node] {
+                    if idom[predecessor].is_none() {
+                        continue;
+                    }
+
+                    new_idom = Some(match new_idom {
+                        Some(current) => intersect(&idom, predecessor, current),
+                        None => predecessor,
+                    });
+                }
+
+                if idom[node] != new_idom {
+                    idom[node] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        idom
+    }
+
+    fn class_summary(&self) -> String {
+        let rpo = self.reverse_postorder();
+        let idom = self.immediate_dominators(&rpo);
+
+        // Dominators precede the nodes they dominate in reverse postorder.
+        let mut retained = vec![0; self.nodes()];
+
+        for &node in rpo.iter().rev() {
+            retained[node] += self.size(node);
+
+            if node != ROOT {
+                let dominator = idom[node].expect("missing dominator");
+                retained[dominator] += retained[node];
+            }
+        }
+
+        let mut children = vec![Vec::new(); self.nodes()];
+
+        for &node in &rpo[1..] {
+            children[idom[node].expect("missing dominator")].push(node);
+        }
+
+        let mut classes: HashMap<&str, ClassSummary> = HashMap::new();
+
+        for node in 1..self.nodes() {
+            let summary = classes.entry(self.class_name(node)).or_default();
+            summary.count += 1;
+            summary.shallow_size += self.size(node);
+        }
+
+        // The retained size of a class only includes objects not already
+        // retained by another object of the same class.
+        let mut active: HashMap<&str, usize> = HashMap::new();
+        let mut stack = vec![(ROOT, false)];
+
+        while let Some((node, exit)) = stack.pop() {
+            if node == ROOT {
+                stack.extend(children[ROOT].iter().map(|&child| (child, false)));
+                continue;
+            }
+
+            let class_name = self.class_name(node);
+
+            if exit {
+                *active.get_mut(class_name).unwrap() -= 1;
+                continue;
+            }
+
+            let count = active.entry(class_name).or_insert(0);
+
+            if *count == 0 {
+                classes.get_mut(class_name).unwrap().retained_size += retained[node];
+            }
+
+            *count += 1;
+            stack.push((node, true));
+            stack.extend(children[node].iter().map(|&child| (child, false)));
+        }
+
+        let mut classes: Vec<(&str, ClassSummary)> = classes.into_iter().collect();
+        classes.sort_by(|(lhs_name, lhs), (rhs_name, rhs)| {
+            rhs.retained_size
+                .cmp(&lhs.retained_size)
+                .then(rhs.shallow_size.cmp(&lhs.shallow_size))
+                .then(lhs_name.cmp(rhs_name))
+        });
+
+        let total_size: usize = (1..self.nodes()).map(|node| self.size(node)).sum();
+        let reachable = rpo.len() - 1;
+        let unreachable_size = total_size - retained[ROOT];
+
+        let mut output = String::new();
+
+        output.push_str(&format!(
+            "{} objects ({} bytes), {} roots\n",
+            self.nodes() - 1,
+            total_size,
+            self.snapshot.roots.len(),
+        ));
+        output.push_str(&format!(
+            "{} objects reachable ({} bytes), {} objects unreachable ({} bytes)\n\n",
+            reachable,
+            retained[ROOT],
+            self.nodes() - 1 - reachable,
+            unreachable_size,
+        ));
+        output.push_str(&format!(
+            "{:>12} {:>12} {:>10}  {}\n",
+            "retained", "shallow", "count", "class"
+        ));
+
+        for (class_name, summary) in classes {
+            output.push_str(&format!(
+                "{:>12} {:>12} {:>10}  {}\n",
+                summary.retained_size, summary.shallow_size, summary.count, class_name
+            ));
+        }
+
+        output
+    }
+
+    // Finds the shortest reference chain from a GC root to the object.
+    fn path_to(&self, address: usize) -> Result<String, String> {
+        let target = match self.index.get(&address) {
+            Some(&node) => node,
+            None => return Err(format!("object 0x{:x} not found in snapshot", address)),
+        };
+
+        let mut parent = vec![None; self.nodes()];
+        let mut queue = VecDeque::new();
+        parent[ROOT] = Some(ROOT);
+        queue.push_back(ROOT);
+
+        while let Some(node) = queue.pop_front() {
+            if node == target {
+                break;
+            }
+
+            for &successor in &self.successors[node] {
+                if parent[successor].is_none() {
+                    parent[successor] = Some(node);
+                    queue.push_back(successor);
+                }
+            }
+        }
+
+        if parent[target].is_none() {
+            return Err(format!(
+                "object 0x{:x} is not reachable from any GC root",
+                address
+            ));
+        }
+
+        let mut chain = vec![target];
+        let mut node = target;
+
+        while parent[node] != Some(ROOT) {
+            node = parent[node].unwrap();
+            chain.push(node);
+        }
+
+        chain.reverse();
+
+        let first = &self.snapshot.objects[chain[0] - 1];
+        let root = self
+            .snapshot
+            .roots
+            .iter()
+            .find(|root| root.address == first.address)
+            .expect("missing root");
+
+        let mut output = format!("root ({})\n", root.kind);
+
+        for node in chain {
+            let object = &self.snapshot.objects[node - 1];
+            output.push_str(&format!(
+                "  -> 0x{:x} {} ({} bytes)\n",
+                object.address, object.class_name, object.size
+            ));
+        }
+
+        Ok(output)
+    }
+}
+
+#[derive(Default)]
+struct ClassSummary {
+    count: usize,
+    shallow_size: usize,
+    retained_size: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> HeapSnapshot {
+        // a and b both reference c, only a is a root. d is unreachable.
+        HeapSnapshot::parse(
+            "dora-heap-snapshot 1\n\
+             root\tstack\t0x10\n\
+             root\tglobal\t0x50\n\
+             object\t0x10\t16\tA\t0x20\t0x30\n\
+             object\t0x20\t8\tB\t0x30\n\
+             object\t0x30\t32\tC\t0x1000\n\
+             object\t0x40\t64\tD\t0x30\n\
+             object\t0x50\t8\tB\t0x60\n\
+             object\t0x60\t8\tB\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_class_summary() {
+        let snapshot = snapshot();
+        let graph = HeapGraph::new(&snapshot);
+        let summary = graph.class_summary();
+        let lines: Vec<&str> = summary.lines().collect();
+
+        assert_eq!(lines[0], "6 objects (136 bytes), 2 roots");
+        assert_eq!(
+            lines[1],
+            "5 objects reachable (72 bytes), 1 objects unreachable (64 bytes)"
+        );
+
+        let rows: Vec<Vec<&str>> = lines[4..]
+            .iter()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                vec!["56", "16", "1", "A"],
+                vec!["32", "32", "1", "C"],
+                vec!["24", "24", "3", "B"],
+                vec!["0", "64", "1", "D"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path_to() {
+        let snapshot = snapshot();
+        let graph = HeapGraph::new(&snapshot);
+
+        assert_eq!(
+            graph.path_to(0x30).unwrap(),
+            "root (stack)\n  -> 0x10 A (16 bytes)\n  -> 0x30 C (32 bytes)\n"
+        );
+        assert_eq!(
+            graph.path_to(0x60).unwrap(),
+            "root (global)\n  -> 0x50 B (8 bytes)\n  -> 0x60 B (8 bytes)\n"
+        );
+        assert!(graph.path_to(0x40).is_err());
+        assert!(graph.path_to(0x70).is_err());
+    }
+}
@@ -5,13 +5,15 @@ use std::fmt;
 use std::ops::Deref;
 use std::path::PathBuf;
 
-use crate::gc::M;
+use crate::gc::{K, M};
+use crate::language::error::lint::{Lint, LintLevel};
 
 use crate::gc::{DEFAULT_CODE_SPACE_LIMIT, DEFAULT_READONLY_SPACE_LIMIT};
 
 // Write the Docopt usage string.
 static USAGE: &'static str = "
 Usage: dora test [options] [<file>]
+       dora analyze-heap [--path-to=<address>] <snapshot>
        dora [options] <file> [--] [<argument>...]
        dora (--version | --help)
 
@@ -29,6 +31,9 @@ Options:
     --emit-debug-entry      Emits debug instruction at beginning of entry stub.
     --omit-bounds-check     Omit array index out of bounds checks.
     --check                 Only type check given program.
+    --warn=<lints>          Report lints as warnings, comma-separated or all.
+    --deny=<lints>          Report lints as errors, comma-separated or all.
+    --error-format=<name>   Format of errors and warnings. Possible values: human, short, json [default: human].
     --asm-syntax TYPE       Emits assembly with Intel or AT&T syntax.
                             Allowed values: intel, att.
     --enable-perf           Enable dump for perf.
@@ -38,17 +43,28 @@ Options:
     --gc-parallel-full      Enable parallel full collection.
     --gc-parallel-minor     Enable parallel minor collection.
     --gc-parallel           Enable both parallel minor and full collection.
+    --gc-concurrent-marking Mark old generation concurrently to the mutator (swiper only).
     --gc-stats              Print GC statistics.
     --gc-verbose            Verbose GC.
     --gc-dev-verbose        Verbose GC for developers.
     --gc-verify             Verify heap before and after collections.
     --gc-worker=<num>       Number of GC worker threads.
-    --gc=<name>             Switch GC. Possible values: zero, copy, swiper (default).
+    --gc=<name>             Switch GC. Possible values: zero, copy, region, swiper (default).
     --gc-young-size=<SIZE>  Use fixed size for young generation.
     --gc-semi-ratio=<num>   Use fixed ratio of semi space in young generation.
 
-    --compiler=<name>       Switch default compiler. Possible values: cannon [default: cannon].
+    --alloc-profile=<file>  Sample allocations and write profile into file at exit.
+    --alloc-profile-interval=<SIZE> Average number of bytes between samples [default: 512K].
+    --alloc-profile-format=<name> Format of allocation profile. Possible values: pprof, collapsed [default: pprof].
+
+    --compiler=<name>       Switch default compiler. Possible values: cannon, boots [default: cannon].
+    --tiered-compilation    Recompile hot functions with Boots.
+    --tier-up-threshold=<num> Invocations and loop iterations until a function is recompiled [default: 10000].
     --test-filter=<name>    Filter tests.
+    --test-format=<name>    Format of test results. Possible values: text, tap, junit [default: text].
+    --test-output=<file>    Write test results into file instead of stdout.
+    --test-threads=<num>    Number of threads running tests in parallel [default: 1].
+    --path-to=<address>     Print reference chain from a GC root to the object (analyze-heap).
     --clear-regs            Clear register when freeing.
 
     --disable-tlab          Disable tlab allocation.
@@ -86,6 +102,7 @@ pub struct Args {
     flag_gc_parallel_full: bool,
     flag_gc_parallel_minor: bool,
     flag_gc_parallel: bool,
+    pub flag_gc_concurrent_marking: bool,
     pub flag_gc_stats: bool,
     pub flag_gc_verbose: bool,
     pub flag_gc_dev_verbose: bool,
@@ -94,15 +111,27 @@ pub struct Args {
     flag_gc_young_size: Option<MemSize>,
     pub flag_gc_semi_ratio: Option<usize>,
     pub flag_gc: Option<CollectorName>,
+    pub flag_alloc_profile: Option<String>,
+    pub flag_alloc_profile_interval: Option<MemSize>,
+    pub flag_alloc_profile_format: Option<ProfileFormat>,
     pub flag_compiler: Option<CompilerName>,
+    pub flag_tiered_compilation: bool,
+    pub flag_tier_up_threshold: Option<usize>,
     pub flag_min_heap_size: Option<MemSize>,
     pub flag_max_heap_size: Option<MemSize>,
     pub flag_code_size: Option<MemSize>,
     pub flag_readonly_size: Option<MemSize>,
     pub flag_check: bool,
+    pub flag_warn: Vec<Lint>,
+    pub flag_deny: Vec<Lint>,
+    pub flag_error_format: Option<ErrorFormat>,
     pub flag_disable_tlab: bool,
     pub flag_disable_barrier: bool,
     pub flag_test_filter: Option<String>,
+    pub flag_test_format: Option<TestFormat>,
+    pub flag_test_output: Option<String>,
+    pub flag_test_threads: Option<usize>,
+    pub flag_path_to: Option<String>,
     pub packages: Vec<(String, PathBuf)>,
 
     pub command: Command,
@@ -134,6 +163,39 @@ impl Args {
             .unwrap_or(DEFAULT_READONLY_SPACE_LIMIT)
     }
 
+    pub fn alloc_profile_interval(&self) -> usize {
+        self.flag_alloc_profile_interval
+            .map(|s| *s)
+            .unwrap_or(512 * K)
+    }
+
+    pub fn alloc_profile_format(&self) -> ProfileFormat {
+        self.flag_alloc_profile_format
+            .unwrap_or(ProfileFormat::Pprof)
+    }
+
+    pub fn error_format(&self) -> ErrorFormat {
+        self.flag_error_format.unwrap_or(ErrorFormat::Human)
+    }
+
+    pub fn test_format(&self) -> TestFormat {
+        self.flag_test_format.unwrap_or(TestFormat::Text)
+    }
+
+    pub fn test_threads(&self) -> usize {
+        self.flag_test_threads.unwrap_or(1)
+    }
+
+    pub fn lint_level(&self, lint: Lint) -> LintLevel {
+        if self.flag_deny.contains(&lint) {
+            LintLevel::Deny
+        } else if self.flag_warn.contains(&lint) {
+            LintLevel::Warn
+        } else {
+            lint.default_level()
+        }
+    }
+
     pub fn gc_workers(&self) -> usize {
         if self.flag_gc_worker > 0 {
             self.flag_gc_worker
@@ -161,6 +223,10 @@ impl Args {
     pub fn compiler(&self) -> CompilerName {
         self.flag_compiler.unwrap_or(CompilerName::Cannon)
     }
+
+    pub fn tier_up_threshold(&self) -> u32 {
+        self.flag_tier_up_threshold.unwrap_or(10_000) as u32
+    }
 }
 
 impl Default for Args {
@@ -190,6 +256,7 @@ impl Default for Args {
             flag_gc_parallel_full: false,
             flag_gc_parallel_minor: false,
             flag_gc_parallel: false,
+            flag_gc_concurrent_marking: false,
             flag_gc_stats: false,
             flag_gc_verbose: false,
             flag_gc_dev_verbose: false,
@@ -198,15 +265,27 @@ impl Default for Args {
             flag_gc_young_size: None,
             flag_gc_semi_ratio: None,
             flag_gc: None,
+            flag_alloc_profile: None,
+            flag_alloc_profile_interval: None,
+            flag_alloc_profile_format: None,
             flag_compiler: None,
+            flag_tiered_compilation: false,
+            flag_tier_up_threshold: None,
             flag_min_heap_size: None,
             flag_max_heap_size: None,
             flag_code_size: None,
             flag_readonly_size: None,
             flag_check: false,
+            flag_warn: Vec::new(),
+            flag_deny: Vec::new(),
+            flag_error_format: None,
             flag_disable_tlab: false,
             flag_disable_barrier: false,
             flag_test_filter: None,
+            flag_test_format: None,
+            flag_test_output: None,
+            flag_test_threads: None,
+            flag_path_to: None,
             packages: Vec::new(),
 
             command: Command::Run,
@@ -241,6 +320,26 @@ impl fmt::Display for CompilerName {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProfileFormat {
+    Pprof,
+    Collapsed,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Human,
+    Short,
+    Json,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TestFormat {
+    Text,
+    Tap,
+    Junit,
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum AsmSyntax {
     Intel,
@@ -252,6 +351,7 @@ pub enum Command {
     Run,
     Test,
     Build,
+    AnalyzeHeap,
 }
 
 impl Command {
@@ -276,6 +376,13 @@ impl Command {
         }
     }
 
+    pub fn is_analyze_heap(&self) -> bool {
+        match self {
+            Command::AnalyzeHeap => true,
+            _ => false,
+        }
+    }
+
     pub fn is_build_or_run(&self) -> bool {
         match self {
             Command::Build | Command::Run => true,
@@ -307,10 +414,25 @@ pub fn parse_arguments() -> Result<Args, String> {
             args.command = Command::Test;
         } else if arg == "build" && idx == 1 {
             args.command = Command::Build;
+        } else if arg == "analyze-heap" && idx == 1 {
+            args.command = Command::AnalyzeHeap;
         } else if arg == "--version" || arg == "-v" {
             args.flag_version = true;
         } else if arg == "--check" {
             args.flag_check = true;
+        } else if arg.starts_with("--warn=") {
+            args.flag_warn.extend(argument_lints(arg)?);
+        } else if arg.starts_with("--deny=") {
+            args.flag_deny.extend(argument_lints(arg)?);
+        } else if arg.starts_with("--error-format=") {
+            let value = argument_value(arg);
+            let value = match value {
+                "human" => ErrorFormat::Human,
+                "short" => ErrorFormat::Short,
+                "json" => ErrorFormat::Json,
+                _ => return Err(format!("--error-format: unknown format '{}'", value)),
+            };
+            args.flag_error_format = Some(value);
         } else if arg == "-h" || arg == "--help" {
             args.flag_help = true;
         } else if arg.starts_with("--emit-ast=") {
@@ -349,6 +471,8 @@ pub fn parse_arguments() -> Result<Args, String> {
             args.flag_gc_parallel_minor = true;
         } else if arg == "--gc-parallel" {
             args.flag_gc_parallel = true;
+        } else if arg == "--gc-concurrent-marking" {
+            args.flag_gc_concurrent_marking = true;
         } else if arg == "--gc-stats" {
             args.flag_gc_stats = true;
         } else if arg == "--gc-verbose" {
@@ -375,6 +499,29 @@ pub fn parse_arguments() -> Result<Args, String> {
             args.flag_gc_young_size = Some(argument_mem_size(arg)?);
         } else if arg.starts_with("--gc-semi-ratio=") {
             args.flag_gc_semi_ratio = Some(argument_usize(arg)?);
+        } else if arg.starts_with("--alloc-profile=") {
+            args.flag_alloc_profile = Some(argument_value(arg).into());
+        } else if arg.starts_with("--alloc-profile-interval=") {
+            let value = argument_mem_size(arg)?;
+
+            if *value == 0 {
+                return Err("--alloc-profile-interval: needs to be greater than 0".into());
+            }
+
+            args.flag_alloc_profile_interval = Some(value);
+        } else if arg.starts_with("--alloc-profile-format=") {
+            let value = argument_value(arg);
+            let value = match value {
+                "pprof" => ProfileFormat::Pprof,
+                "collapsed" => ProfileFormat::Collapsed,
+                _ => {
+                    return Err(format!(
+                        "--alloc-profile-format: unknown format '{}'",
+                        value
+                    ))
+                }
+            };
+            args.flag_alloc_profile_format = Some(value);
         } else if arg.starts_with("--compiler=") {
             let value = argument_value(arg);
             let value = match value {
@@ -383,8 +530,39 @@ pub fn parse_arguments() -> Result<Args, String> {
                 _ => return Err(format!("--compiler: unknown compiler '{}'", value)),
             };
             args.flag_compiler = Some(value);
+        } else if arg == "--tiered-compilation" {
+            args.flag_tiered_compilation = true;
+        } else if arg.starts_with("--tier-up-threshold=") {
+            let value = argument_usize(arg)?;
+
+            if value == 0 || value > i32::MAX as usize {
+                return Err(format!("--tier-up-threshold: invalid value '{}'", value));
+            }
+
+            args.flag_tier_up_threshold = Some(value);
         } else if arg.starts_with("--test-filter=") {
             args.flag_test_filter = Some(argument_value(arg).into());
+        } else if arg.starts_with("--test-format=") {
+            let value = argument_value(arg);
+            let value = match value {
+                "text" => TestFormat::Text,
+                "tap" => TestFormat::Tap,
+                "junit" => TestFormat::Junit,
+                _ => return Err(format!("--test-format: unknown format '{}'", value)),
+            };
+            args.flag_test_format = Some(value);
+        } else if arg.starts_with("--test-output=") {
+            args.flag_test_output = Some(argument_value(arg).into());
+        } else if arg.starts_with("--test-threads=") {
+            let value = argument_usize(arg)?;
+
+            if value == 0 {
+                return Err(format!("--test-threads: invalid value '{}'", value));
+            }
+
+            args.flag_test_threads = Some(value);
+        } else if arg.starts_with("--path-to=") {
+            args.flag_path_to = Some(argument_value(arg).into());
         } else if arg == "--disable-tlab" {
             args.flag_disable_tlab = true;
         } else if arg == "--disable-barrier" {
@@ -454,6 +632,25 @@ fn argument_usize(arg: &str) -> Result<usize, String> {
     }
 }
 
+fn argument_lints(arg: &str) -> Result<Vec<Lint>, String> {
+    let idx = arg.find("=").expect("missing =");
+    let (name, value) = arg.split_at(idx);
+    let value = &value[1..];
+    let mut lints = Vec::new();
+
+    for lint_name in value.split(',') {
+        if lint_name == "all" {
+            lints.extend_from_slice(Lint::ALL);
+        } else if let Some(lint) = Lint::find(lint_name) {
+            lints.push(lint);
+        } else {
+            return Err(format!("{}: unknown lint '{}'", name, lint_name));
+        }
+    }
+
+    Ok(lints)
+}
+
 fn parse_mem_size(value: &str) -> Result<MemSize, String> {
     let suffix = if let Some(ch) = value.chars().last() {
         match ch {
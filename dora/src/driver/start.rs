@@ -1,12 +1,22 @@
-use crate::driver::cmd;
+use std::cmp::min;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::driver::analyze_heap::analyze_heap;
+use crate::driver::cmd::{self, ErrorFormat};
+use crate::driver::test_report::{TestFailure, TestReporter, TestResult};
 use crate::language;
 use crate::language::access::module_contains;
 use crate::language::error::msg::ErrorMessage;
 use crate::language::sem_analysis::{
     FctDefinition, FctDefinitionId, ModuleDefinitionId, SemAnalysis,
 };
+use crate::threads::{parked_scope, DoraThread};
 use crate::timer::Timer;
-use crate::vm::{clear_vm, execute_on_main, set_vm, VM};
+use crate::vm::{clear_vm, execute_on_main, execute_on_thread, get_vm, set_vm, VM};
 
 pub fn start() -> i32 {
     let args = cmd::parse_arguments();
@@ -30,11 +40,17 @@ pub fn start() -> i32 {
         return 0;
     }
 
+    if args.command.is_analyze_heap() {
+        return analyze_heap(&args);
+    }
+
     let mut sa = SemAnalysis::new(args);
 
     let success = language::check(&mut sa);
     assert_eq!(success, !sa.diag.lock().has_errors());
 
+    sa.diag.lock().dump_warnings(&sa);
+
     if report_errors(&sa) {
         return 1;
     }
@@ -77,6 +93,12 @@ pub fn start() -> i32 {
 
     vm.threads.join_all();
 
+    if let Some(alloc_profiler) = vm.gc.alloc_profiler() {
+        // All threads have finished, every sample can be resolved.
+        alloc_profiler.resolve_pending();
+        alloc_profiler.write(&vm);
+    }
+
     if vm.args.flag_gc_stats {
         let duration = timer.stop();
         vm.dump_gc_summary(duration);
@@ -103,6 +125,11 @@ fn report_errors(sa: &SemAnalysis) -> bool {
         sa.diag.lock().dump(&sa);
         let no_errors = sa.diag.lock().errors().len();
 
+        // json output should only contain diagnostics
+        if sa.args.error_format() == ErrorFormat::Json {
+            return true;
+        }
+
         if no_errors == 1 {
             eprintln!("{} error found.", no_errors);
         } else {
@@ -115,48 +142,203 @@ fn report_errors(sa: &SemAnalysis) -> bool {
     }
 }
 
+struct TestCase {
+    fct_id: FctDefinitionId,
+    name: String,
+    file: String,
+    serial: bool,
+}
+
 fn run_tests(vm: &VM, module_id: ModuleDefinitionId) -> i32 {
-    let mut tests = 0;
-    let mut passed = 0;
-
-    execute_on_main(|| {
-        for fct in vm.fcts.iter() {
-            let fct = fct.read();
-
-            if !module_contains(vm, module_id, fct.module_id)
-                || !is_test_fct(&*fct)
-                || !test_filter_matches(vm, &*fct)
-            {
-                continue;
-            }
+    let mut tests = Vec::new();
+
+    for fct in vm.fcts.iter() {
+        let fct = fct.read();
+
+        if module_contains(vm, module_id, fct.module_id)
+            && is_test_fct(&*fct)
+            && test_filter_matches(vm, &*fct)
+        {
+            tests.push(TestCase {
+                fct_id: fct.id(),
+                name: vm.interner.str(fct.name).to_string(),
+                file: vm.source_file(fct.file_id).path.display().to_string(),
+                serial: fct.is_serial,
+            });
+        }
+    }
 
-            tests += 1;
+    let reporter = TestReporter::open(
+        vm.args.test_format(),
+        vm.args.flag_test_output.as_deref(),
+        tests.len(),
+    );
 
-            print!("test {} ... ", vm.interner.str(fct.name));
+    let mut reporter = match reporter {
+        Ok(reporter) => reporter,
+        Err(err) => {
+            eprintln!("cannot write test results: {}", err);
+            return 1;
+        }
+    };
 
-            run_test(vm, fct.id());
-            passed += 1;
-            println!("ok");
+    let result = execute_on_main(|| {
+        if vm.args.test_threads() > 1 {
+            run_tests_parallel(vm, &tests, &mut reporter)
+        } else {
+            run_tests_serial(vm, &tests, &mut reporter)
         }
     });
 
-    println!(
-        "{} tests executed; {} passed; {} failed.",
-        tests,
-        passed,
-        tests - passed
-    );
+    let all_passed = reporter.failed() == 0;
+
+    if let Err(err) = result.and_then(|_| reporter.finish()) {
+        eprintln!("cannot write test results: {}", err);
+        return 1;
+    }
 
     // if all tests passed exit with 0, otherwise 1
-    if tests == passed {
+    if all_passed {
         0
     } else {
         1
     }
 }
 
-fn run_test(vm: &VM, fct: FctDefinitionId) {
-    vm.run_test(fct);
+fn run_tests_serial(vm: &VM, tests: &[TestCase], reporter: &mut TestReporter) -> io::Result<()> {
+    for test in tests {
+        reporter.start_test(&test.name)?;
+        let (duration, result) = run_test_timed(vm, test.fct_id);
+        report_test(reporter, test, duration, result)?;
+    }
+
+    Ok(())
+}
+
+// Runs all tests not marked with @serial on worker threads. Results are
+// still reported in the order of the tests: a finished test is held back
+// until all tests before it were reported. Serial tests run on the main
+// thread once all workers are done.
+fn run_tests_parallel(vm: &VM, tests: &[TestCase], reporter: &mut TestReporter) -> io::Result<()> {
+    let parallel: Vec<(usize, FctDefinitionId)> = tests
+        .iter()
+        .enumerate()
+        .filter(|(_, test)| !test.serial)
+        .map(|(idx, test)| (idx, test.fct_id))
+        .collect();
+    let parallel = Arc::new(parallel);
+    let next = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel();
+
+    let workers = min(vm.args.test_threads(), parallel.len());
+    let workers: Vec<Arc<DoraThread>> = (0..workers)
+        .map(|_| {
+            let parallel = parallel.clone();
+            let next = next.clone();
+            let sender = sender.clone();
+
+            execute_on_thread(vm, move || {
+                let vm = get_vm();
+
+                loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+
+                    if idx >= parallel.len() {
+                        break;
+                    }
+
+                    let (test_idx, fct_id) = parallel[idx];
+                    let (duration, result) = run_test_timed(vm, fct_id);
+                    sender
+                        .send((test_idx, duration, result))
+                        .expect("receiver missing");
+                }
+            })
+        })
+        .collect();
+
+    // Only the workers should keep the channel open.
+    drop(sender);
+
+    let mut results: Vec<Option<(Duration, Result<(), TestFailure>)>> =
+        tests.iter().map(|_| None).collect();
+    let mut reported = 0;
+    let mut status = Ok(());
+
+    // Keep receiving after a write error, the workers need to finish.
+    while let Ok((idx, duration, result)) = parked_scope(|| receiver.recv()) {
+        results[idx] = Some((duration, result));
+
+        while status.is_ok() && reported < tests.len() {
+            let (duration, result) = match results[reported].take() {
+                Some(finished) => finished,
+                None => break,
+            };
+
+            let test = &tests[reported];
+            status = reporter
+                .start_test(&test.name)
+                .and_then(|_| report_test(reporter, test, duration, result));
+            reported += 1;
+        }
+    }
+
+    for worker in &workers {
+        worker.join();
+    }
+
+    status?;
+
+    for (test, finished) in tests.iter().zip(results).skip(reported) {
+        reporter.start_test(&test.name)?;
+
+        let (duration, result) = match finished {
+            Some(finished) => finished,
+            None => {
+                debug_assert!(test.serial);
+                run_test_timed(vm, test.fct_id)
+            }
+        };
+
+        report_test(reporter, test, duration, result)?;
+    }
+
+    Ok(())
+}
+
+fn report_test(
+    reporter: &mut TestReporter,
+    test: &TestCase,
+    duration: Duration,
+    result: Result<(), TestFailure>,
+) -> io::Result<()> {
+    reporter.finish_test(TestResult {
+        name: test.name.clone(),
+        file: test.file.clone(),
+        duration,
+        failure: result.err(),
+    })
+}
+
+fn run_test_timed(vm: &VM, fct: FctDefinitionId) -> (Duration, Result<(), TestFailure>) {
+    let start = Instant::now();
+    let result = run_test(vm, fct);
+    (start.elapsed(), result)
+}
+
+fn run_test(vm: &VM, fct: FctDefinitionId) -> Result<(), TestFailure> {
+    vm.run_test(fct).map_err(|failure| {
+        let mut stacktrace = Vec::new();
+        failure
+            .stacktrace
+            .dump(vm, &mut stacktrace)
+            .expect("output broken");
+
+        TestFailure {
+            message: failure.message,
+            stacktrace: String::from_utf8_lossy(&stacktrace).into_owned(),
+        }
+    })
 }
 
 fn is_test_fct(fct: &FctDefinition) -> bool {
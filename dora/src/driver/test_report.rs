@@ -0,0 +1,380 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::driver::cmd::TestFormat;
+
+pub struct TestResult {
+    pub name: String,
+    pub file: String,
+    pub duration: Duration,
+    pub failure: Option<TestFailure>,
+}
+
+pub struct TestFailure {
+    pub message: String,
+    pub stacktrace: String,
+}
+
+// Writes the results of `dora test` in one of the supported formats. Text
+// and TAP are written while the tests run, JUnit XML once all finished.
+pub struct TestReporter {
+    format: TestFormat,
+    out: Box<dyn Write>,
+    results: Vec<TestResult>,
+}
+
+impl TestReporter {
+    // Writes into the file at `output` or to stdout if not given.
+    pub fn open(
+        format: TestFormat,
+        output: Option<&str>,
+        tests: usize,
+    ) -> io::Result<TestReporter> {
+        let out: Box<dyn Write> = match output {
+            Some(path) => Box::new(File::create(path)?),
+            None => Box::new(io::stdout()),
+        };
+
+        TestReporter::new(format, out, tests)
+    }
+
+    pub fn new(format: TestFormat, out: Box<dyn Write>, tests: usize) -> io::Result<TestReporter> {
+        let mut reporter = TestReporter {
+            format,
+            out,
+            results: Vec::new(),
+        };
+
+        if format == TestFormat::Tap {
+            writeln!(reporter.out, "TAP version 13")?;
+            writeln!(reporter.out, "1..{}", tests)?;
+            reporter.out.flush()?;
+        }
+
+        Ok(reporter)
+    }
+
+    pub fn start_test(&mut self, name: &str) -> io::Result<()> {
+        if self.format == TestFormat::Text {
+            write!(self.out, "test {} ... ", name)?;
+            self.out.flush()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish_test(&mut self, result: TestResult) -> io::Result<()> {
+        let duration = duration_ms(result.duration);
+
+        match self.format {
+            TestFormat::Text => {
+                let status = if result.failure.is_some() {
+                    "FAILED"
+                } else {
+                    "ok"
+                };
+                writeln!(self.out, "{} ({:.2}ms)", status, duration)?;
+            }
+
+            TestFormat::Tap => {
+                let number = self.results.len() + 1;
+
+                if let Some(ref failure) = result.failure {
+                    writeln!(self.out, "not ok {} - {}", number, result.name)?;
+                    writeln!(self.out, "  ---")?;
+                    writeln!(self.out, "  duration_ms: {:.2}", duration)?;
+                    writeln!(self.out, "  message: {}", yaml_string(&failure.message))?;
+                    writeln!(self.out, "  stacktrace: |")?;
+                    for line in failure.stacktrace.lines() {
+                        writeln!(self.out, "    {}", line.trim_start())?;
+                    }
+                    writeln!(self.out, "  ...")?;
+                } else {
+                    writeln!(self.out, "ok {} - {}", number, result.name)?;
+                    writeln!(self.out, "  ---")?;
+                    writeln!(self.out, "  duration_ms: {:.2}", duration)?;
+                    writeln!(self.out, "  ...")?;
+                }
+            }
+
+            TestFormat::Junit => {}
+        }
+
+        self.out.flush()?;
+        self.results.push(result);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.format {
+            TestFormat::Text => self.write_text_summary()?,
+            TestFormat::Tap => {
+                writeln!(self.out, "# tests {}", self.results.len())?;
+                writeln!(self.out, "# pass {}", self.passed())?;
+                writeln!(self.out, "# fail {}", self.failed())?;
+            }
+            TestFormat::Junit => self.write_junit()?,
+        }
+
+        self.out.flush()
+    }
+
+    pub fn passed(&self) -> usize {
+        self.results.len() - self.failed()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.failure.is_some())
+            .count()
+    }
+
+    fn write_text_summary(&mut self) -> io::Result<()> {
+        if self.failed() > 0 {
+            writeln!(self.out)?;
+            writeln!(self.out, "failures:")?;
+
+            for result in &self.results {
+                if let Some(ref failure) = result.failure {
+                    writeln!(self.out)?;
+                    writeln!(self.out, "---- {} ----", result.name)?;
+                    writeln!(self.out, "{}", failure.message)?;
+                    write!(self.out, "{}", failure.stacktrace)?;
+                }
+            }
+
+            writeln!(self.out)?;
+        }
+
+        writeln!(
+            self.out,
+            "{} tests executed; {} passed; {} failed.",
+            self.results.len(),
+            self.passed(),
+            self.failed()
+        )
+    }
+
+    fn write_junit(&mut self) -> io::Result<()> {
+        // Group the tests by file into test suites, in order of appearance.
+        let mut files: Vec<&str> = Vec::new();
+
+        for result in &self.results {
+            if !files.contains(&result.file.as_str()) {
+                files.push(&result.file);
+            }
+        }
+
+        let total: Duration = self.results.iter().map(|result| result.duration).sum();
+
+        writeln!(self.out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            self.out,
+            r#"<testsuites name="dora" tests="{}" failures="{}" time="{:.6}">"#,
+            self.results.len(),
+            self.failed(),
+            total.as_secs_f64()
+        )?;
+
+        for file in files {
+            let results: Vec<&TestResult> = self
+                .results
+                .iter()
+                .filter(|result| result.file == file)
+                .collect();
+            let failures = results
+                .iter()
+                .filter(|result| result.failure.is_some())
+                .count();
+            let time: Duration = results.iter().map(|result| result.duration).sum();
+
+            writeln!(
+                self.out,
+                r#"  <testsuite name="{}" tests="{}" failures="{}" time="{:.6}">"#,
+                xml_escape(file),
+                results.len(),
+                failures,
+                time.as_secs_f64()
+            )?;
+
+            for result in results {
+                write!(
+                    self.out,
+                    r#"    <testcase name="{}" classname="{}" time="{:.6}""#,
+                    xml_escape(&result.name),
+                    xml_escape(file),
+                    result.duration.as_secs_f64()
+                )?;
+
+                if let Some(ref failure) = result.failure {
+                    writeln!(self.out, ">")?;
+                    writeln!(
+                        self.out,
+                        r#"      <failure message="{}">{}</failure>"#,
+                        xml_escape(&failure.message),
+                        xml_escape(&failure.stacktrace)
+                    )?;
+                    writeln!(self.out, "    </testcase>")?;
+                } else {
+                    writeln!(self.out, "/>")?;
+                }
+            }
+
+            writeln!(self.out, "  </testsuite>")?;
+        }
+
+        writeln!(self.out, "</testsuites>")
+    }
+}
+
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+
+    for ch in value.chars() {
+        match ch {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            _ => result.push(ch),
+        }
+    }
+
+    result
+}
+
+fn yaml_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+
+    for ch in value.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            _ => result.push(ch),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone)]
+    struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn report(format: TestFormat) -> String {
+        let buffer = Buffer(Rc::new(RefCell::new(Vec::new())));
+        let mut reporter = TestReporter::new(format, Box::new(buffer.clone()), 2).unwrap();
+
+        reporter.start_test("passes").unwrap();
+        reporter
+            .finish_test(TestResult {
+                name: "passes".into(),
+                file: "foo.dora".into(),
+                duration: Duration::from_micros(1500),
+                failure: None,
+            })
+            .unwrap();
+
+        reporter.start_test("fails").unwrap();
+        reporter
+            .finish_test(TestResult {
+                name: "fails".into(),
+                file: "foo.dora".into(),
+                duration: Duration::from_micros(250),
+                failure: Some(TestFailure {
+                    message: "fatal error: a < \"b\"".into(),
+                    stacktrace: "    fails (foo.dora:7)\n".into(),
+                }),
+            })
+            .unwrap();
+
+        assert_eq!(reporter.passed(), 1);
+        assert_eq!(reporter.failed(), 1);
+        reporter.finish().unwrap();
+
+        let output = buffer.0.borrow().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_text_report() {
+        assert_eq!(
+            report(TestFormat::Text),
+            "test passes ... ok (1.50ms)\n\
+             test fails ... FAILED (0.25ms)\n\
+             \n\
+             failures:\n\
+             \n\
+             ---- fails ----\n\
+             fatal error: a < \"b\"\n\
+             \x20   fails (foo.dora:7)\n\
+             \n\
+             2 tests executed; 1 passed; 1 failed.\n"
+        );
+    }
+
+    #[test]
+    fn test_tap_report() {
+        assert_eq!(
+            report(TestFormat::Tap),
+            "TAP version 13\n\
+             1..2\n\
+             ok 1 - passes\n\
+             \x20 ---\n\
+             \x20 duration_ms: 1.50\n\
+             \x20 ...\n\
+             not ok 2 - fails\n\
+             \x20 ---\n\
+             \x20 duration_ms: 0.25\n\
+             \x20 message: \"fatal error: a < \\\"b\\\"\"\n\
+             \x20 stacktrace: |\n\
+             \x20   fails (foo.dora:7)\n\
+             \x20 ...\n\
+             # tests 2\n\
+             # pass 1\n\
+             # fail 1\n"
+        );
+    }
+
+    #[test]
+    fn test_junit_report() {
+        assert_eq!(
+            report(TestFormat::Junit),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuites name=\"dora\" tests=\"2\" failures=\"1\" time=\"0.001750\">\n\
+             \x20 <testsuite name=\"foo.dora\" tests=\"2\" failures=\"1\" time=\"0.001750\">\n\
+             \x20   <testcase name=\"passes\" classname=\"foo.dora\" time=\"0.001500\"/>\n\
+             \x20   <testcase name=\"fails\" classname=\"foo.dora\" time=\"0.000250\">\n\
+             \x20     <failure message=\"fatal error: a &lt; &quot;b&quot;\">    fails (foo.dora:7)\n\
+             </failure>\n\
+             \x20   </testcase>\n\
+             \x20 </testsuite>\n\
+             </testsuites>\n"
+        );
+    }
+}
@@ -6,6 +6,7 @@ use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::sync::Arc;
 
 use crate::driver::cmd::{Args, CollectorName};
+use crate::gc::alloc_profiler::AllocationProfiler;
 use crate::gc::code::CodeSpace;
 use crate::gc::compact::MarkCompactCollector;
 use crate::gc::copy::CopyCollector;
@@ -19,16 +20,19 @@ use crate::mem;
 use crate::object::{Header, Obj};
 use crate::os;
 use crate::threads::DoraThread;
-use crate::vm::VM;
+use crate::vm::{get_vm, VM};
 use crate::vtable::VTable;
 
+pub use crate::gc::heap_snapshot::dump_heap;
 pub use crate::gc::root::{iterate_strong_roots, iterate_weak_roots, Slot};
 
+pub mod alloc_profiler;
 pub mod bump;
 pub mod code;
 pub mod compact;
 pub mod copy;
 pub mod freelist;
+pub mod heap_snapshot;
 pub mod marking;
 pub mod pmarking;
 pub mod region;
@@ -59,6 +63,7 @@ pub struct Gc {
     epoch: AtomicUsize,
 
     finalizers: Mutex<Vec<(Address, Arc<DoraThread>)>>,
+    alloc_profiler: Option<AllocationProfiler>,
 }
 
 impl Gc {
@@ -94,9 +99,14 @@ impl Gc {
             epoch: AtomicUsize::new(0),
 
             finalizers: Mutex::new(Vec::new()),
+            alloc_profiler: AllocationProfiler::new(args),
         }
     }
 
+    pub fn alloc_profiler(&self) -> Option<&AllocationProfiler> {
+        self.alloc_profiler.as_ref()
+    }
+
     pub fn add_finalizer(&self, object: Address, thread: Arc<DoraThread>) {
         let mut finalizers = self.finalizers.lock();
         finalizers.push((object, thread));
@@ -110,6 +120,14 @@ impl Gc {
         self.collector.card_table_offset()
     }
 
+    pub fn needs_pre_write_barrier(&self) -> bool {
+        self.collector.needs_pre_write_barrier()
+    }
+
+    pub fn concurrent_marking_active(&self) -> bool {
+        self.collector.concurrent_marking_active()
+    }
+
     pub fn alloc_code(&self, size: usize) -> Address {
         self.code_space.alloc(size)
     }
@@ -130,7 +148,17 @@ impl Gc {
         if size < TLAB_OBJECT_SIZE && self.supports_tlab {
             self.alloc_tlab(vm, size, array_ref)
         } else {
-            self.collector.alloc(vm, size, array_ref)
+            let object = self.collector.alloc(vm, size, array_ref);
+            self.sample_allocation(vm, object, size, size);
+            object
+        }
+    }
+
+    fn sample_allocation(&self, vm: &VM, object: Address, size: usize, bytes: usize) {
+        if let Some(ref alloc_profiler) = self.alloc_profiler {
+            if object.is_non_null() {
+                alloc_profiler.record(vm, object, size, bytes);
+            }
         }
     }
 
@@ -151,6 +179,9 @@ impl Gc {
             // initialize TLAB to new boundaries
             tlab::initialize(tlab);
 
+            // allocations in the new TLAB are accounted to this object
+            self.sample_allocation(vm, object_start, size, tlab.end.offset_from(object_start));
+
             // object is allocated before TLAB
             object_start
         } else {
@@ -184,6 +215,10 @@ impl Gc {
         self.collector.verify_ref(vm, reference);
     }
 
+    pub fn shutdown(&self) {
+        self.collector.shutdown();
+    }
+
     pub fn drop_all_native_code_objects(&mut self) {
         self.code_space.drop_all_native_code_objects();
     }
@@ -215,6 +250,29 @@ trait Collector {
         0
     }
 
+    // decides whether to emit snapshot-at-the-beginning barriers
+    // before reference stores, needed for concurrent marking
+    fn needs_pre_write_barrier(&self) -> bool {
+        false
+    }
+
+    // records the overwritten reference while concurrent marking is active
+    fn pre_write_barrier_slow(&self, _object: Address) {
+        unreachable!()
+    }
+
+    // determines the initial value of the marking flag for new threads
+    fn concurrent_marking_active(&self) -> bool {
+        false
+    }
+
+    // stops all background threads of the collector
+    fn shutdown(&self) {}
+
+    // visits every object in the heap, all threads need to be stopped
+    // and their TLABs made iterable
+    fn iterate_objects(&self, vm: &VM, f: &mut dyn FnMut(Address));
+
     // prints GC summary: minor/full collections, etc.
     fn dump_summary(&self, _runtime: f32);
 
@@ -508,6 +566,7 @@ pub enum GcReason {
     ForceMinorCollect,
     Stress,
     StressMinor,
+    Remark,
 }
 
 impl GcReason {
@@ -519,6 +578,7 @@ impl GcReason {
             GcReason::ForceMinorCollect => "force minor collect",
             GcReason::Stress => "stress",
             GcReason::StressMinor => "stress minor",
+            GcReason::Remark => "remark",
         }
     }
 }
@@ -529,6 +589,16 @@ impl fmt::Display for GcReason {
     }
 }
 
+pub extern "C" fn pre_write_barrier_slow(object: Address) {
+    let vm = get_vm();
+    vm.gc.collector.pre_write_barrier_slow(object);
+}
+
+// visits all objects in the region, null fillers are skipped
+fn iterate_objects_in_region(region: Region, f: &mut dyn FnMut(Address)) {
+    swiper::walk_region(region, |_object, address, _size| f(address));
+}
+
 pub fn fill_region(vm: &VM, start: Address, end: Address) {
     if start == end {
         // nothing to do
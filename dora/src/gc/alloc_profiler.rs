@@ -0,0 +1,468 @@
+use parking_lot::Mutex;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::driver::cmd::{Args, ProfileFormat};
+use crate::gc::Address;
+use crate::language::sem_analysis::FctDefinitionId;
+use crate::object::Obj;
+use crate::stack::stacktrace_from_last_dtn;
+use crate::threads::current_thread;
+use crate::vm::{ClassInstance, CodeId, VM};
+
+// Samples allocations on the allocation slow path. Whenever the number of
+// bytes allocated since the last sample reaches the sampling interval, the
+// stack trace of the current allocation is recorded. The sample represents
+// all bytes allocated since the previous sample.
+pub struct AllocationProfiler {
+    path: String,
+    format: ProfileFormat,
+    interval: usize,
+    start: SystemTime,
+    state: Mutex<ProfilerState>,
+}
+
+struct ProfilerState {
+    bytes_since_sample: usize,
+    pending: Vec<PendingSample>,
+    samples: Vec<Sample>,
+}
+
+// The object isn't initialized while still in the allocator, so its class
+// is only determined later: at the thread's next sample, before the next
+// collection moves the object or at exit.
+struct PendingSample {
+    thread_id: usize,
+    object: Address,
+    sample: Sample,
+}
+
+struct Sample {
+    frames: Vec<(CodeId, u32)>,
+    class_instance: *const ClassInstance,
+    size: usize,
+    weight: usize,
+}
+
+unsafe impl Send for Sample {}
+
+impl AllocationProfiler {
+    pub fn new(args: &Args) -> Option<AllocationProfiler> {
+        let path = args.flag_alloc_profile.clone()?;
+
+        Some(AllocationProfiler {
+            path,
+            format: args.alloc_profile_format(),
+            interval: args.alloc_profile_interval(),
+            start: SystemTime::now(),
+            state: Mutex::new(ProfilerState {
+                bytes_since_sample: 0,
+                pending: Vec::new(),
+                samples: Vec::new(),
+            }),
+        })
+    }
+
+    // Called for allocations on the slow path, `bytes` is the amount of memory
+    // this allocation accounts for: the size of the new TLAB or the object size
+    // for objects allocated outside of TLABs.
+    pub fn record(&self, vm: &VM, object: Address, size: usize, bytes: usize) {
+        let weight = {
+            let mut state = self.state.lock();
+            state.bytes_since_sample += bytes;
+
+            if state.bytes_since_sample < self.interval {
+                return;
+            }
+
+            std::mem::replace(&mut state.bytes_since_sample, 0)
+        };
+
+        let stacktrace = stacktrace_from_last_dtn(vm);
+        let thread_id = current_thread().id();
+
+        let mut state = self.state.lock();
+
+        // Objects from earlier samples of this thread are initialized by now.
+        state.resolve_pending(|sample| sample.thread_id == thread_id);
+
+        state.pending.push(PendingSample {
+            thread_id,
+            object,
+            sample: Sample {
+                frames: stacktrace.entries().collect(),
+                class_instance: std::ptr::null(),
+                size,
+                weight,
+            },
+        });
+    }
+
+    // Needs to be called while all threads are stopped.
+    pub fn resolve_pending(&self) {
+        self.state.lock().resolve_pending(|_| true);
+    }
+
+    // Writes all resolved samples into the profile. Samples that are still
+    // pending are ignored.
+    pub fn write(&self, vm: &VM) {
+        let state = self.state.lock();
+
+        let result = match self.format {
+            ProfileFormat::Pprof => self.write_pprof(vm, &state.samples),
+            ProfileFormat::Collapsed => self.write_collapsed(vm, &state.samples),
+        };
+
+        if let Err(error) = result {
+            eprintln!(
+                "failed to write allocation profile to {}: {}",
+                self.path, error
+            );
+        }
+    }
+
+    fn write_collapsed(&self, vm: &VM, samples: &[Sample]) -> io::Result<()> {
+        let mut stacks: BTreeMap<String, usize> = BTreeMap::new();
+
+        for sample in samples {
+            let mut frames: Vec<String> = sample
+                .frames
+                .iter()
+                .rev()
+                .map(|&(code_id, _)| {
+                    let (fct_id, _) = frame_location(vm, code_id, 0);
+                    let fct = vm.fcts.idx(fct_id);
+                    let fct = fct.read();
+                    fct.display_name(vm)
+                })
+                .collect();
+
+            frames.push(class_name(vm, sample.class_instance));
+
+            *stacks.entry(frames.join(";")).or_insert(0) += sample.weight;
+        }
+
+        let mut file = File::create(&self.path)?;
+
+        for (stack, bytes) in stacks {
+            writeln!(file, "{} {}", stack, bytes)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_pprof(&self, vm: &VM, samples: &[Sample]) -> io::Result<()> {
+        let mut builder = PprofBuilder::new();
+
+        let alloc_objects = builder.string("alloc_objects");
+        let count = builder.string("count");
+        let alloc_space = builder.string("alloc_space");
+        let bytes = builder.string("bytes");
+        let class = builder.string("class");
+        let object_size = builder.string("object_size");
+
+        let mut profile = ProtobufWriter::new();
+
+        // sample_type
+        profile.message(1, |msg| {
+            msg.int64(1, alloc_objects);
+            msg.int64(2, count);
+        });
+        profile.message(1, |msg| {
+            msg.int64(1, alloc_space);
+            msg.int64(2, bytes);
+        });
+
+        for sample in samples {
+            let locations: Vec<u64> = sample
+                .frames
+                .iter()
+                .map(|&(code_id, line)| {
+                    let (fct_id, line) = frame_location(vm, code_id, line);
+                    builder.location(vm, fct_id, line)
+                })
+                .collect();
+
+            let objects = std::cmp::max(sample.weight / sample.size, 1);
+            let class_name = builder.string(&class_name(vm, sample.class_instance));
+
+            // sample
+            profile.message(2, |msg| {
+                msg.packed_uint64(1, &locations);
+                msg.packed_int64(2, &[objects as i64, sample.weight as i64]);
+                msg.message(3, |label| {
+                    label.int64(1, class);
+                    label.int64(2, class_name);
+                });
+                msg.message(3, |label| {
+                    label.int64(1, object_size);
+                    label.int64(3, sample.size as i64);
+                    label.int64(4, bytes);
+                });
+            });
+        }
+
+        builder.finish(&mut profile);
+
+        let time_nanos = self
+            .start
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as i64)
+            .unwrap_or(0);
+        let duration_nanos = self
+            .start
+            .elapsed()
+            .map(|duration| duration.as_nanos() as i64)
+            .unwrap_or(0);
+
+        profile.int64(9, time_nanos);
+        profile.int64(10, duration_nanos);
+
+        // period_type and period
+        profile.message(11, |msg| {
+            msg.int64(1, alloc_space);
+            msg.int64(2, bytes);
+        });
+        profile.int64(12, self.interval as i64);
+
+        let mut file = File::create(&self.path)?;
+        file.write_all(&profile.finish())
+    }
+}
+
+impl ProfilerState {
+    fn resolve_pending<F: Fn(&PendingSample) -> bool>(&mut self, filter: F) {
+        let mut idx = 0;
+
+        while idx < self.pending.len() {
+            if filter(&self.pending[idx]) {
+                let pending = self.pending.swap_remove(idx);
+                let obj = unsafe { &*pending.object.to_ptr::<Obj>() };
+                let class_instance = obj.header().vtbl().class_instance();
+
+                let mut sample = pending.sample;
+                sample.class_instance = class_instance as *const ClassInstance;
+                self.samples.push(sample);
+            } else {
+                idx += 1;
+            }
+        }
+    }
+}
+
+fn frame_location(vm: &VM, code_id: CodeId, line: u32) -> (FctDefinitionId, u32) {
+    let code = vm.code_objects.get(code_id);
+    let fct_id = code.fct_id();
+
+    let line = if line == 0 {
+        vm.fcts.idx(fct_id).read().pos.line
+    } else {
+        line
+    };
+
+    (fct_id, line)
+}
+
+fn class_name(vm: &VM, class_instance: *const ClassInstance) -> String {
+    let class_instance = unsafe { &*class_instance };
+    class_instance.name(vm)
+}
+
+// Collects the string, function and location tables of the pprof profile.
+struct PprofBuilder {
+    strings: Vec<String>,
+    string_ids: HashMap<String, i64>,
+    functions: HashMap<FctDefinitionId, u64>,
+    function_table: Vec<(u64, i64, i64, i64)>,
+    locations: HashMap<(FctDefinitionId, u32), u64>,
+    location_table: Vec<(u64, u64, u32)>,
+}
+
+impl PprofBuilder {
+    fn new() -> PprofBuilder {
+        let mut builder = PprofBuilder {
+            strings: Vec::new(),
+            string_ids: HashMap::new(),
+            functions: HashMap::new(),
+            function_table: Vec::new(),
+            locations: HashMap::new(),
+            location_table: Vec::new(),
+        };
+
+        // The first entry of the string table needs to be the empty string.
+        builder.string("");
+        builder
+    }
+
+    fn string(&mut self, value: &str) -> i64 {
+        if let Some(&id) = self.string_ids.get(value) {
+            return id;
+        }
+
+        let id = self.strings.len() as i64;
+        self.strings.push(value.into());
+        self.string_ids.insert(value.into(), id);
+        id
+    }
+
+    fn function(&mut self, vm: &VM, fct_id: FctDefinitionId) -> u64 {
+        if let Some(&id) = self.functions.get(&fct_id) {
+            return id;
+        }
+
+        let fct = vm.fcts.idx(fct_id);
+        let fct = fct.read();
+        let name = self.string(&fct.display_name(vm));
+        let path = vm.source_file(fct.file_id).path.display().to_string();
+        let filename = self.string(&path);
+
+        let id = self.function_table.len() as u64 + 1;
+        self.function_table
+            .push((id, name, filename, fct.pos.line as i64));
+        self.functions.insert(fct_id, id);
+        id
+    }
+
+    fn location(&mut self, vm: &VM, fct_id: FctDefinitionId, line: u32) -> u64 {
+        if let Some(&id) = self.locations.get(&(fct_id, line)) {
+            return id;
+        }
+
+        let function_id = self.function(vm, fct_id);
+        let id = self.location_table.len() as u64 + 1;
+        self.location_table.push((id, function_id, line));
+        self.locations.insert((fct_id, line), id);
+        id
+    }
+
+    fn finish(self, profile: &mut ProtobufWriter) {
+        for &(id, function_id, line) in &self.location_table {
+            // location
+            profile.message(4, |msg| {
+                msg.uint64(1, id);
+                msg.message(4, |line_msg| {
+                    line_msg.uint64(1, function_id);
+                    line_msg.int64(2, line as i64);
+                });
+            });
+        }
+
+        for &(id, name, filename, start_line) in &self.function_table {
+            // function
+            profile.message(5, |msg| {
+                msg.uint64(1, id);
+                msg.int64(2, name);
+                msg.int64(3, name);
+                msg.int64(4, filename);
+                msg.int64(5, start_line);
+            });
+        }
+
+        for string in &self.strings {
+            // string_table
+            profile.bytes(6, string.as_bytes());
+        }
+    }
+}
+
+// Minimal encoder for the protobuf wire format used by pprof.
+struct ProtobufWriter {
+    data: Vec<u8>,
+}
+
+impl ProtobufWriter {
+    fn new() -> ProtobufWriter {
+        ProtobufWriter { data: Vec::new() }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.data
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.data.push((value as u8 & 0x7F) | 0x80);
+            value >>= 7;
+        }
+
+        self.data.push(value as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u32) {
+        self.varint(((field << 3) | wire_type) as u64);
+    }
+
+    fn uint64(&mut self, field: u32, value: u64) {
+        self.key(field, 0);
+        self.varint(value);
+    }
+
+    fn int64(&mut self, field: u32, value: i64) {
+        self.uint64(field, value as u64);
+    }
+
+    fn bytes(&mut self, field: u32, value: &[u8]) {
+        self.key(field, 2);
+        self.varint(value.len() as u64);
+        self.data.extend_from_slice(value);
+    }
+
+    fn packed_uint64(&mut self, field: u32, values: &[u64]) {
+        let mut packed = ProtobufWriter::new();
+
+        for &value in values {
+            packed.varint(value);
+        }
+
+        self.bytes(field, &packed.data);
+    }
+
+    fn packed_int64(&mut self, field: u32, values: &[i64]) {
+        let values: Vec<u64> = values.iter().map(|&value| value as u64).collect();
+        self.packed_uint64(field, &values);
+    }
+
+    fn message<F: FnOnce(&mut ProtobufWriter)>(&mut self, field: u32, f: F) {
+        let mut msg = ProtobufWriter::new();
+        f(&mut msg);
+        self.bytes(field, &msg.data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProtobufWriter;
+
+    #[test]
+    fn test_varint() {
+        let mut writer = ProtobufWriter::new();
+        writer.varint(1);
+        writer.varint(300);
+        writer.varint(u64::MAX);
+
+        let mut expected = vec![0x01, 0xAC, 0x02];
+        expected.extend_from_slice(&[0xFF; 9]);
+        expected.push(0x01);
+        assert_eq!(writer.finish(), expected);
+    }
+
+    #[test]
+    fn test_message() {
+        let mut writer = ProtobufWriter::new();
+        writer.message(1, |msg| {
+            msg.int64(1, 150);
+            msg.packed_uint64(2, &[3, 270]);
+        });
+        writer.bytes(6, b"ab");
+
+        assert_eq!(
+            writer.finish(),
+            vec![
+                0x0A, 0x08, 0x08, 0x96, 0x01, 0x12, 0x03, 0x03, 0x8E, 0x02, 0x32, 0x02, b'a', b'b'
+            ]
+        );
+    }
+}
@@ -7,7 +7,8 @@ use crate::gc::root::{determine_strong_roots, Slot};
 use crate::gc::space::Space;
 use crate::gc::tlab;
 use crate::gc::{
-    formatted_size, iterate_weak_roots, Address, CollectionStats, Collector, GcReason, Region,
+    formatted_size, iterate_objects_in_region, iterate_weak_roots, Address, CollectionStats,
+    Collector, GcReason, Region,
 };
 use crate::object::Obj;
 use crate::os;
@@ -99,6 +100,10 @@ impl Collector for MarkCompactCollector {
         self.collect(vm, reason);
     }
 
+    fn iterate_objects(&self, _vm: &VM, f: &mut dyn FnMut(Address)) {
+        iterate_objects_in_region(Region::new(self.heap.start, self.alloc.top()), f);
+    }
+
     fn dump_summary(&self, runtime: f32) {
         let stats = self.stats.lock();
         let (mutator, gc) = stats.percentage(runtime);
@@ -6,8 +6,8 @@ use crate::driver::cmd::Args;
 use crate::gc::bump::BumpAllocator;
 use crate::gc::tlab;
 use crate::gc::{
-    formatted_size, iterate_strong_roots, iterate_weak_roots, Address, CollectionStats, Collector,
-    GcReason, Region,
+    formatted_size, iterate_objects_in_region, iterate_strong_roots, iterate_weak_roots, Address,
+    CollectionStats, Collector, GcReason, Region,
 };
 use crate::mem;
 use crate::object::Obj;
@@ -106,6 +106,11 @@ impl Collector for CopyCollector {
         self.collect(vm, reason);
     }
 
+    fn iterate_objects(&self, _vm: &VM, f: &mut dyn FnMut(Address)) {
+        let from_space = self.from_space();
+        iterate_objects_in_region(Region::new(from_space.start, self.alloc.top()), f);
+    }
+
     fn dump_summary(&self, runtime: f32) {
         let stats = self.stats.lock();
         let (mutator, gc) = stats.percentage(runtime);
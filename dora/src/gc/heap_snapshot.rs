@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::gc::root::iterate_strong_roots_with_kind;
+use crate::gc::{tlab, Address};
+use crate::safepoint;
+use crate::threads::DoraThread;
+use crate::vm::{ClassInstanceId, VM};
+
+// A heap snapshot lists all GC roots and every object in the heap. The
+// format is line based, fields are separated by tabs and addresses are
+// written in hex:
+//
+//   dora-heap-snapshot 1
+//   root    <kind>    <address>
+//   object  <address> <size> <class> <reference>*
+//
+// Objects aren't filtered by reachability, the snapshot also contains
+// garbage that wasn't collected yet.
+pub const SNAPSHOT_HEADER: &str = "dora-heap-snapshot 1";
+
+pub fn dump_heap(vm: &VM, path: &Path) -> io::Result<()> {
+    safepoint::stop_the_world(vm, |threads| {
+        tlab::make_iterable_all(vm, threads);
+        write_snapshot(vm, threads, path)
+    })
+}
+
+fn write_snapshot(vm: &VM, threads: &[Arc<DoraThread>], path: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", SNAPSHOT_HEADER)?;
+
+    let mut roots = Vec::new();
+
+    iterate_strong_roots_with_kind(vm, threads, |kind, slot| {
+        let object = slot.get();
+
+        if object.is_non_null() {
+            roots.push((kind, object));
+        }
+    });
+
+    for (kind, object) in roots {
+        writeln!(writer, "root\t{}\t{}", kind.name(), object)?;
+    }
+
+    let fillers = [
+        vm.known.free_object_class_instance(),
+        vm.known.free_array_class_instance(),
+    ];
+    let mut class_names: HashMap<ClassInstanceId, String> = HashMap::new();
+    let mut result = Ok(());
+
+    vm.gc.collector.iterate_objects(vm, &mut |address| {
+        if result.is_err() {
+            return;
+        }
+
+        let object = address.to_mut_obj();
+        let class_instance = object.header().vtbl().class_instance();
+        let class_instance_id = class_instance.id();
+
+        if fillers.contains(&class_instance_id) {
+            return;
+        }
+
+        let class_name = class_names
+            .entry(class_instance_id)
+            .or_insert_with(|| class_instance.name(vm));
+
+        let mut references = Vec::new();
+
+        object.visit_reference_fields(|slot| {
+            let reference = slot.get();
+
+            if reference.is_non_null() {
+                references.push(reference);
+            }
+        });
+
+        result = write_object(&mut writer, address, object.size(), class_name, &references);
+    });
+
+    result?;
+    writer.flush()
+}
+
+fn write_object(
+    writer: &mut dyn Write,
+    address: Address,
+    size: usize,
+    class_name: &str,
+    references: &[Address],
+) -> io::Result<()> {
+    write!(writer, "object\t{}\t{}\t{}", address, size, class_name)?;
+
+    for reference in references {
+        write!(writer, "\t{}", reference)?;
+    }
+
+    writeln!(writer)
+}
+
+pub struct HeapSnapshot {
+    pub roots: Vec<SnapshotRoot>,
+    pub objects: Vec<SnapshotObject>,
+}
+
+pub struct SnapshotRoot {
+    pub kind: String,
+    pub address: usize,
+}
+
+pub struct SnapshotObject {
+    pub address: usize,
+    pub size: usize,
+    pub class_name: String,
+    pub references: Vec<usize>,
+}
+
+impl HeapSnapshot {
+    pub fn read(path: &Path) -> Result<HeapSnapshot, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+        HeapSnapshot::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<HeapSnapshot, String> {
+        let mut lines = content.lines().enumerate();
+
+        match lines.next() {
+            Some((_, SNAPSHOT_HEADER)) => {}
+            _ => return Err("not a heap snapshot".into()),
+        }
+
+        let mut snapshot = HeapSnapshot {
+            roots: Vec::new(),
+            objects: Vec::new(),
+        };
+
+        for (idx, line) in lines {
+            let line_number = idx + 1;
+            let fields: Vec<&str> = line.split('\t').collect();
+
+            match fields[0] {
+                "root" if fields.len() == 3 => {
+                    snapshot.roots.push(SnapshotRoot {
+                        kind: fields[1].into(),
+                        address: parse_address(fields[2], line_number)?,
+                    });
+                }
+
+                "object" if fields.len() >= 4 => {
+                    let size = fields[2]
+                        .parse::<usize>()
+                        .map_err(|_| format!("line {}: invalid size", line_number))?;
+
+                    let references = fields[4..]
+                        .iter()
+                        .map(|field| parse_address(field, line_number))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    snapshot.objects.push(SnapshotObject {
+                        address: parse_address(fields[1], line_number)?,
+                        size,
+                        class_name: fields[3].into(),
+                        references,
+                    });
+                }
+
+                "" if fields.len() == 1 => {}
+
+                _ => return Err(format!("line {}: invalid entry", line_number)),
+            }
+        }
+
+        Ok(snapshot)
+    }
+}
+
+pub fn parse_address(value: &str, line_number: usize) -> Result<usize, String> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+
+    usize::from_str_radix(digits, 16)
+        .map_err(|_| format!("line {}: invalid address '{}'", line_number, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let snapshot = HeapSnapshot::parse(
+            "dora-heap-snapshot 1\n\
+             root\tstack\t0x1000\n\
+             object\t0x1000\t24\tVec[Foo]\t0x2000\n\
+             object\t0x2000\t16\t<trait object for Foo>\n",
+        )
+        .unwrap();
+
+        assert_eq!(snapshot.roots.len(), 1);
+        assert_eq!(snapshot.roots[0].kind, "stack");
+        assert_eq!(snapshot.roots[0].address, 0x1000);
+
+        assert_eq!(snapshot.objects.len(), 2);
+        assert_eq!(snapshot.objects[0].size, 24);
+        assert_eq!(snapshot.objects[0].class_name, "Vec[Foo]");
+        assert_eq!(snapshot.objects[0].references, vec![0x2000]);
+        assert_eq!(snapshot.objects[1].class_name, "<trait object for Foo>");
+        assert!(snapshot.objects[1].references.is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(HeapSnapshot::parse("object\t0x1000\t8\tFoo\n").is_err());
+        assert!(HeapSnapshot::parse("dora-heap-snapshot 1\nobject\tfoo\t8\tFoo\n").is_err());
+        assert!(HeapSnapshot::parse("dora-heap-snapshot 1\nroot\tstack\n").is_err());
+    }
+}
@@ -1,23 +1,50 @@
+use std::cmp::max;
 use std::fmt;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use fixedbitset::FixedBitSet;
 use parking_lot::Mutex;
 
 use crate::driver::cmd::Args;
-use crate::gc::{Address, Collector, GcReason, Region};
-use crate::os::{self, Reservation};
+use crate::gc::marking;
+use crate::gc::root::{determine_strong_roots, Slot};
+use crate::gc::tlab;
+use crate::gc::{
+    fill_region, formatted_size, iterate_weak_roots, Address, CollectionStats, Collector, GcReason,
+    Region,
+};
+use crate::mem;
+use crate::object::Obj;
+use crate::os::{self, MemoryPermission, Reservation};
+use crate::safepoint;
+use crate::threads::DoraThread;
+use crate::timer::Timer;
 use crate::utils::enumeration;
 use crate::vm::VM;
 
 pub const REGION_SIZE_BITS: usize = 20;
 pub const REGION_SIZE: usize = 1 << REGION_SIZE_BITS;
 
+// regions with more live data than this percentage are never evacuated,
+// copying them would cost more than the space it frees up.
+const LIVE_THRESHOLD_PERCENT: usize = 85;
+
+// percentage of regions mutators can't allocate into, these are
+// kept free as to-space for evacuation.
+const EVACUATION_RESERVE_PERCENT: usize = 10;
+
 pub struct RegionCollector {
     reservation: Reservation,
     regions: Vec<HeapRegion>,
     number_regions: usize,
+    evacuation_reserve: usize,
+
+    // Current region for regular allocation. This lock also
+    // guards all region state transitions from free to used.
     regular_alloc_region: Mutex<Option<RegionId>>,
+
+    stats: Mutex<CollectionStats>,
 }
 
 impl RegionCollector {
@@ -26,6 +53,7 @@ impl RegionCollector {
 
         let reservation = os::reserve_align(max_heap_size, REGION_SIZE, false);
         let number_regions = max_heap_size / REGION_SIZE;
+        let evacuation_reserve = max(1, number_regions * EVACUATION_RESERVE_PERCENT / 100);
         let mut regions = Vec::with_capacity(number_regions);
 
         let mut next_region_start = reservation.start;
@@ -42,11 +70,22 @@ impl RegionCollector {
             next_region_start = next_region_start.offset(REGION_SIZE);
         }
 
+        if args.flag_gc_verbose {
+            println!(
+                "GC: {} {}; {} regions",
+                reservation.region(),
+                formatted_size(max_heap_size),
+                number_regions
+            );
+        }
+
         RegionCollector {
             reservation,
             regions,
             number_regions,
+            evacuation_reserve,
             regular_alloc_region: Mutex::new(None),
+            stats: Mutex::new(CollectionStats::new()),
         }
     }
 }
@@ -59,6 +98,24 @@ impl Collector for RegionCollector {
     fn alloc_tlab_area(&self, vm: &VM, size: usize) -> Option<Region> {
         let result = self.regular_alloc(vm, size);
 
+        if result.is_non_null() {
+            return Some(result.region_start(size));
+        }
+
+        self.collect(vm, GcReason::AllocationFailure);
+
+        let result = self.regular_alloc(vm, size);
+
+        if result.is_non_null() {
+            return Some(result.region_start(size));
+        }
+
+        // Other threads might have used up the freed regions before
+        // this thread could allocate, collect once more before giving up.
+        self.collect(vm, GcReason::AllocationFailure);
+
+        let result = self.regular_alloc(vm, size);
+
         if result.is_null() {
             None
         } else {
@@ -67,39 +124,110 @@ impl Collector for RegionCollector {
     }
 
     fn alloc(&self, vm: &VM, size: usize, _array_ref: bool) -> Address {
-        if size < REGION_SIZE / 2 {
-            self.regular_alloc(vm, size)
-        } else {
-            unimplemented!()
+        let ptr = self.inner_alloc(vm, size);
+
+        if ptr.is_non_null() {
+            return ptr;
+        }
+
+        self.collect(vm, GcReason::AllocationFailure);
+
+        let ptr = self.inner_alloc(vm, size);
+
+        if ptr.is_non_null() {
+            return ptr;
         }
+
+        self.collect(vm, GcReason::AllocationFailure);
+        self.inner_alloc(vm, size)
     }
 
-    fn collect(&self, _vm: &VM, _reason: GcReason) {
-        unimplemented!()
+    fn collect(&self, vm: &VM, reason: GcReason) {
+        let mut timer = Timer::new(vm.args.flag_gc_stats);
+
+        safepoint::stop_the_world(vm, |threads| {
+            tlab::make_iterable_all(vm, threads);
+            self.evacuate_collect(vm, threads, reason);
+        });
+
+        if vm.args.flag_gc_stats {
+            let duration = timer.stop();
+            let mut stats = self.stats.lock();
+            stats.add(duration);
+        }
     }
 
-    fn minor_collect(&self, _vm: &VM, _reason: GcReason) {
-        unimplemented!()
+    fn minor_collect(&self, vm: &VM, reason: GcReason) {
+        self.collect(vm, reason);
     }
 
     fn needs_write_barrier(&self) -> bool {
         false
     }
 
-    fn card_table_offset(&self) -> usize {
-        unimplemented!()
+    fn dump_summary(&self, runtime: f32) {
+        let stats = self.stats.lock();
+        let (mutator, gc) = stats.percentage(runtime);
+
+        println!("GC stats: total={:.1}", runtime);
+        println!("GC stats: mutator={:.1}", stats.mutator(runtime));
+        println!("GC stats: collection={:.1}", stats.pause());
+
+        println!();
+        println!("GC stats: collection-count={}", stats.collections());
+        println!("GC stats: collection-pauses={}", stats.pauses());
+
+        println!(
+            "GC summary: {:.1}ms collection ({}), {:.1}ms mutator, {:.1}ms total ({}% mutator, {}% GC)",
+            stats.pause(),
+            stats.collections(),
+            stats.mutator(runtime),
+            runtime,
+            mutator,
+            gc,
+        );
     }
 
-    fn dump_summary(&self, _runtime: f32) {
-        unimplemented!()
+    fn iterate_objects(&self, _vm: &VM, f: &mut dyn FnMut(Address)) {
+        for region in &self.regions {
+            let state = region.state.load();
+
+            if state == RegionState::Used {
+                walk_region(
+                    region.area_start,
+                    region.top(),
+                    |_object, address, _size| f(address),
+                );
+            } else if state == RegionState::Humongous {
+                f(region.area_start);
+            }
+        }
     }
 
-    fn verify_ref(&self, _vm: &VM, _reference: Address) {
-        unimplemented!()
+    fn verify_ref(&self, vm: &VM, reference: Address) {
+        let found = vm.gc.readonly_space.contains(reference) || self.is_allocated(reference);
+        assert!(found, "write barrier found invalid reference");
+    }
+}
+
+impl Drop for RegionCollector {
+    fn drop(&mut self) {
+        os::free(
+            self.reservation.unaligned_start,
+            self.reservation.unaligned_size,
+        );
     }
 }
 
 impl RegionCollector {
+    fn inner_alloc(&self, vm: &VM, size: usize) -> Address {
+        if size < REGION_SIZE / 2 {
+            self.regular_alloc(vm, size)
+        } else {
+            self.humongous_alloc(vm, size)
+        }
+    }
+
     fn regular_alloc(&self, _vm: &VM, size: usize) -> Address {
         assert!(size < REGION_SIZE / 2);
 
@@ -109,7 +237,7 @@ impl RegionCollector {
             let region_id = match *regular_alloc_region {
                 Some(region_id) => region_id,
                 None => {
-                    let allocated_region_id = self.find_free_region();
+                    let allocated_region_id = self.find_free_region_for_mutator();
 
                     if allocated_region_id.is_none() {
                         return Address::null();
@@ -134,6 +262,49 @@ impl RegionCollector {
         }
     }
 
+    fn humongous_alloc(&self, _vm: &VM, size: usize) -> Address {
+        let number_regions = align_region(size) >> REGION_SIZE_BITS;
+
+        // humongous objects can't race with regular allocation for free regions.
+        let _regular_alloc_region = self.regular_alloc_region.lock();
+
+        if self.count_free_regions() < number_regions + self.evacuation_reserve {
+            return Address::null();
+        }
+
+        let first_region_id = match self.find_free_regions(number_regions) {
+            Some(region_id) => region_id,
+            None => return Address::null(),
+        };
+
+        let first_region = self.region(first_region_id);
+        let object_start = first_region.area_start;
+
+        os::commit_at(
+            object_start,
+            mem::page_align(size),
+            MemoryPermission::ReadWrite,
+        );
+
+        for idx in 0..number_regions {
+            let region = self.region((first_region_id.to_usize() + idx).into());
+
+            if idx == 0 {
+                region.state.store(RegionState::Humongous);
+                region
+                    .top
+                    .store(object_start.offset(size).to_usize(), Ordering::Relaxed);
+            } else {
+                region.state.store(RegionState::HumongousCont);
+                region
+                    .top
+                    .store(region.area_start.to_usize(), Ordering::Relaxed);
+            }
+        }
+
+        object_start
+    }
+
     fn bump_pointer_alloc(&self, region: &HeapRegion, size: usize) -> Address {
         let mut top = region.top.load(Ordering::Relaxed);
         let limit = region.area_end.to_usize();
@@ -162,6 +333,10 @@ impl RegionCollector {
     fn init_region(&self, region_id: RegionId) {
         let region = self.region(region_id);
         region.state.store(RegionState::Used);
+        region
+            .top
+            .store(region.area_start.to_usize(), Ordering::Relaxed);
+        region.live_bytes.store(0, Ordering::Relaxed);
 
         os::commit_at(
             region.area_start,
@@ -170,6 +345,31 @@ impl RegionCollector {
         );
     }
 
+    fn free_region(&self, region_id: RegionId) {
+        let region = self.region(region_id);
+        let state = region.state.load();
+
+        let size = if state == RegionState::Humongous {
+            let object_end = region.top();
+            let number_regions =
+                align_region(object_end.offset_from(region.area_start)) >> REGION_SIZE_BITS;
+
+            for idx in 1..number_regions {
+                let cont = self.region((region_id.to_usize() + idx).into());
+                debug_assert!(cont.state.load() == RegionState::HumongousCont);
+                cont.reset();
+            }
+
+            mem::page_align(object_end.offset_from(region.area_start))
+        } else {
+            debug_assert!(state == RegionState::Used);
+            region.area_size()
+        };
+
+        os::discard(region.area_start, size);
+        region.reset();
+    }
+
     fn find_free_region(&self) -> Option<RegionId> {
         for region_idx in 0..self.number_regions {
             let region = self.region(region_idx.into());
@@ -182,9 +382,471 @@ impl RegionCollector {
         None
     }
 
+    fn find_free_region_for_mutator(&self) -> Option<RegionId> {
+        if self.count_free_regions() <= self.evacuation_reserve {
+            return None;
+        }
+
+        self.find_free_region()
+    }
+
+    fn find_free_regions(&self, count: usize) -> Option<RegionId> {
+        let mut start = 0;
+
+        while start + count <= self.number_regions {
+            let occupied = (start..start + count)
+                .rev()
+                .find(|&idx| self.region(idx.into()).state.load() != RegionState::Free);
+
+            match occupied {
+                Some(idx) => start = idx + 1,
+                None => return Some(start.into()),
+            }
+        }
+
+        None
+    }
+
+    fn count_free_regions(&self) -> usize {
+        self.regions
+            .iter()
+            .filter(|region| region.state.load() == RegionState::Free)
+            .count()
+    }
+
     fn region(&self, id: RegionId) -> &HeapRegion {
         &self.regions[id.to_usize()]
     }
+
+    fn region_id_for(&self, addr: Address) -> RegionId {
+        debug_assert!(self.reservation.region().contains(addr));
+        (addr.offset_from(self.reservation.start) >> REGION_SIZE_BITS).into()
+    }
+
+    fn is_allocated(&self, addr: Address) -> bool {
+        if !self.reservation.region().contains(addr) {
+            return false;
+        }
+
+        let region = self.region(self.region_id_for(addr));
+        let state = region.state.load();
+
+        (state == RegionState::Used || state == RegionState::Humongous)
+            && region.area_start <= addr
+            && addr < region.top()
+    }
+
+    fn evacuate_collect(&self, vm: &VM, threads: &[Arc<DoraThread>], reason: GcReason) {
+        let timer = Timer::new(vm.args.flag_gc_verbose);
+        let rootset = determine_strong_roots(vm, threads);
+
+        let mut collector = EvacuationCollector {
+            vm,
+            collector: self,
+            rootset: &rootset,
+            readonly: vm.gc.readonly_space.total(),
+            collection_set: RegionSet::new(self.number_regions),
+            largest_live_object: Vec::with_capacity(self.number_regions),
+            evacuation_region: None,
+            used_before: 0,
+            freed_regions: 0,
+            evacuated_regions: 0,
+        };
+
+        collector.collect();
+
+        if vm.args.flag_gc_verify {
+            self.verify(vm, &rootset);
+        }
+
+        timer.stop_with(|time_pause| {
+            let used_after = self.used_bytes();
+            let garbage = collector.used_before.saturating_sub(used_after);
+
+            println!(
+                "Region GC: {:.1} ms, {}->{} size, {} garbage, {} regions evacuated, {} regions freed ({})",
+                time_pause,
+                formatted_size(collector.used_before),
+                formatted_size(used_after),
+                formatted_size(garbage),
+                collector.evacuated_regions,
+                collector.freed_regions,
+                reason
+            );
+        });
+    }
+
+    fn used_bytes(&self) -> usize {
+        self.regions
+            .iter()
+            .filter(|region| {
+                let state = region.state.load();
+                state == RegionState::Used || state == RegionState::Humongous
+            })
+            .map(|region| region.top().offset_from(region.area_start))
+            .sum()
+    }
+
+    fn verify(&self, vm: &VM, rootset: &[Slot]) {
+        let readonly = vm.gc.readonly_space.total();
+
+        let verify_reference = |slot: Slot, name: &str| {
+            let reference = slot.get();
+
+            if reference.is_null() || readonly.contains(reference) {
+                return;
+            }
+
+            assert!(
+                self.is_allocated(reference),
+                "{} references object {} outside of used regions",
+                name,
+                reference
+            );
+            let object = reference.to_obj();
+            assert!(
+                object.header().vtblptr_forwarded().is_none(),
+                "{} references forwarded object {}",
+                name,
+                reference
+            );
+        };
+
+        for &root in rootset {
+            verify_reference(root, "root");
+        }
+
+        for region_idx in 0..self.number_regions {
+            let region = self.region(region_idx.into());
+            let state = region.state.load();
+
+            if state != RegionState::Used && state != RegionState::Humongous {
+                continue;
+            }
+
+            walk_region(
+                region.area_start,
+                region.top(),
+                |object, _address, _size| {
+                    assert!(
+                        !object.header().is_marked_non_atomic(),
+                        "object still marked after collection"
+                    );
+
+                    object.visit_reference_fields(|field| {
+                        verify_reference(field, "field");
+                    });
+                },
+            );
+        }
+    }
+}
+
+struct EvacuationCollector<'a> {
+    vm: &'a VM,
+    collector: &'a RegionCollector,
+    rootset: &'a [Slot],
+    readonly: Region,
+
+    collection_set: RegionSet,
+    largest_live_object: Vec<usize>,
+    evacuation_region: Option<RegionId>,
+
+    used_before: usize,
+    freed_regions: usize,
+    evacuated_regions: usize,
+}
+
+impl<'a> EvacuationCollector<'a> {
+    fn collect(&mut self) {
+        self.used_before = self.collector.used_bytes();
+
+        self.mark_live();
+        self.compute_live_bytes();
+        self.select_collection_set();
+        self.evacuate();
+        self.update_references();
+        self.free_collection_set();
+
+        // let mutators continue allocating in the last evacuation region.
+        *self.collector.regular_alloc_region.lock() = self.evacuation_region;
+    }
+
+    fn mark_live(&mut self) {
+        marking::start(
+            self.rootset,
+            self.collector.reservation.region(),
+            self.readonly,
+        );
+    }
+
+    fn compute_live_bytes(&mut self) {
+        for region in &self.collector.regions {
+            let state = region.state.load();
+
+            let live_bytes = if state == RegionState::Used {
+                let mut live_bytes = 0;
+                let mut largest_object = 0;
+
+                walk_region(region.area_start, region.top(), |object, _address, size| {
+                    if object.header().is_marked_non_atomic() {
+                        live_bytes += size;
+                        largest_object = max(largest_object, size);
+                    }
+                });
+
+                self.largest_live_object.push(largest_object);
+                live_bytes
+            } else if state == RegionState::Humongous {
+                let object = region.area_start.to_obj();
+
+                if object.header().is_marked_non_atomic() {
+                    region.top().offset_from(region.area_start)
+                } else {
+                    0
+                }
+            } else {
+                0
+            };
+
+            if state != RegionState::Used {
+                self.largest_live_object.push(0);
+            }
+
+            region.live_bytes.store(live_bytes, Ordering::Relaxed);
+        }
+    }
+
+    fn select_collection_set(&mut self) {
+        let mut candidates = Vec::new();
+
+        for region_idx in 0..self.collector.number_regions {
+            let region_id: RegionId = region_idx.into();
+            let region = self.collector.region(region_id);
+
+            if region.state.load() != RegionState::Used {
+                continue;
+            }
+
+            let live_bytes = region.live_bytes.load(Ordering::Relaxed);
+
+            if live_bytes == 0 {
+                // nothing to copy, region can be freed right away.
+                self.collection_set.insert(region_id);
+            } else if live_bytes * 100 <= region.area_size() * LIVE_THRESHOLD_PERCENT {
+                candidates.push((live_bytes, region_id));
+            }
+        }
+
+        // evacuate regions with the least live data first.
+        candidates.sort_by_key(|&(live_bytes, _)| live_bytes);
+
+        let free_regions = self.collector.count_free_regions();
+        let area_size = REGION_SIZE - os::page_size();
+
+        let mut live_bytes_to_copy = 0;
+        let mut largest_object = 0;
+
+        for (live_bytes, region_id) in candidates {
+            // An evacuation region wastes less than the size of the largest
+            // copied object at its end, which keeps this estimate on the safe side.
+            let largest_object_with_region = max(
+                largest_object,
+                self.largest_live_object[region_id.to_usize()],
+            );
+            let usable_size = area_size - largest_object_with_region;
+            let needed_regions = (live_bytes_to_copy + live_bytes).div_ceil(usable_size);
+
+            if needed_regions > free_regions {
+                break;
+            }
+
+            live_bytes_to_copy += live_bytes;
+            largest_object = largest_object_with_region;
+            self.collection_set.insert(region_id);
+        }
+    }
+
+    fn evacuate(&mut self) {
+        for region_idx in 0..self.collector.number_regions {
+            let region_id: RegionId = region_idx.into();
+
+            if !self.collection_set.contains(region_id) {
+                continue;
+            }
+
+            let region = self.collector.region(region_id);
+
+            if region.live_bytes.load(Ordering::Relaxed) == 0 {
+                continue;
+            }
+
+            walk_region(region.area_start, region.top(), |object, _address, size| {
+                if object.header().is_marked_non_atomic() {
+                    let new_address = self.allocate_for_evacuation(size);
+                    object.copy_to(new_address, size);
+                    object.header_mut().vtblptr_forward(new_address);
+                }
+            });
+
+            self.evacuated_regions += 1;
+        }
+    }
+
+    fn allocate_for_evacuation(&mut self, size: usize) -> Address {
+        loop {
+            if let Some(region_id) = self.evacuation_region {
+                let region = self.collector.region(region_id);
+                let address = self.collector.bump_pointer_alloc(region, size);
+
+                if address.is_non_null() {
+                    region.live_bytes.fetch_add(size, Ordering::Relaxed);
+                    return address;
+                }
+            }
+
+            let region_id = self
+                .collector
+                .find_free_region()
+                .expect("FAIL: Not enough space for evacuation.");
+            self.collector.init_region(region_id);
+            self.evacuation_region = Some(region_id);
+        }
+    }
+
+    fn update_references(&mut self) {
+        for &root in self.rootset {
+            self.forward_reference(root);
+        }
+
+        iterate_weak_roots(self.vm, |current_address| {
+            let object = current_address.to_mut_obj();
+
+            if self.in_collection_set(current_address) {
+                object.header().vtblptr_forwarded()
+            } else if object.header().is_marked_non_atomic() {
+                Some(current_address)
+            } else {
+                None
+            }
+        });
+
+        for region_idx in 0..self.collector.number_regions {
+            let region_id: RegionId = region_idx.into();
+
+            if self.collection_set.contains(region_id) {
+                continue;
+            }
+
+            let region = self.collector.region(region_id);
+            let state = region.state.load();
+
+            if state == RegionState::Used {
+                self.update_region(region);
+            } else if state == RegionState::Humongous {
+                let object = region.area_start.to_mut_obj();
+
+                if object.header().is_marked_non_atomic() {
+                    object.visit_reference_fields(|field| {
+                        self.forward_reference(field);
+                    });
+
+                    object.header_mut().unmark_non_atomic();
+                } else {
+                    self.collector.free_region(region_id);
+                    self.freed_regions += 1;
+                }
+            }
+        }
+    }
+
+    // Updates references of all live objects in the region and
+    // overwrites dead objects with filler objects.
+    fn update_region(&self, region: &HeapRegion) {
+        let mut garbage_start = Address::null();
+
+        walk_region(region.area_start, region.top(), |object, address, _size| {
+            if object.header().is_marked_non_atomic() {
+                if garbage_start.is_non_null() {
+                    fill_region(self.vm, garbage_start, address);
+                    garbage_start = Address::null();
+                }
+
+                object.visit_reference_fields(|field| {
+                    self.forward_reference(field);
+                });
+
+                object.header_mut().unmark_non_atomic();
+            } else if garbage_start.is_null() {
+                garbage_start = address;
+            }
+        });
+
+        if garbage_start.is_non_null() {
+            fill_region(self.vm, garbage_start, region.top());
+        }
+    }
+
+    fn forward_reference(&self, slot: Slot) {
+        let object_address = slot.get();
+
+        if self.in_collection_set(object_address) {
+            let object = object_address.to_obj();
+            let new_address = object
+                .header()
+                .vtblptr_forwarded()
+                .expect("live object was not evacuated");
+            slot.set(new_address);
+        } else {
+            debug_assert!(
+                object_address.is_null()
+                    || self.readonly.contains(object_address)
+                    || self.collector.is_allocated(object_address)
+            );
+        }
+    }
+
+    fn in_collection_set(&self, address: Address) -> bool {
+        self.collector.reservation.region().contains(address)
+            && self
+                .collection_set
+                .contains(self.collector.region_id_for(address))
+    }
+
+    fn free_collection_set(&mut self) {
+        for region_idx in 0..self.collector.number_regions {
+            let region_id: RegionId = region_idx.into();
+
+            if self.collection_set.contains(region_id) {
+                self.collector.free_region(region_id);
+                self.freed_regions += 1;
+            }
+        }
+
+        self.collection_set.clear();
+    }
+}
+
+fn walk_region<F>(start: Address, end: Address, mut fct: F)
+where
+    F: FnMut(&mut Obj, Address, usize),
+{
+    let mut scan = start;
+
+    while scan < end {
+        let object = scan.to_mut_obj();
+
+        if object.header().vtblptr().is_null() {
+            scan = scan.add_ptr(1);
+            continue;
+        }
+
+        let object_size = object.size();
+
+        fct(object, scan, object_size);
+
+        scan = scan.offset(object_size);
+    }
 }
 
 struct HeapRegion {
@@ -206,9 +868,25 @@ impl HeapRegion {
     fn area_size(&self) -> usize {
         self.area_end.to_usize() - self.area_start.to_usize()
     }
+
+    fn top(&self) -> Address {
+        self.top.load(Ordering::Relaxed).into()
+    }
+
+    fn reset(&self) {
+        self.top
+            .store(self.area_start.to_usize(), Ordering::Relaxed);
+        self.live_bytes.store(0, Ordering::Relaxed);
+        self.state.store(RegionState::Free);
+    }
 }
 
-enumeration!(RegionState { Free, Used });
+enumeration!(RegionState {
+    Free,
+    Used,
+    Humongous,
+    HumongousCont
+});
 
 struct AtomicRegionState {
     value: AtomicUsize,
@@ -249,6 +927,14 @@ impl RegionSet {
         }
     }
 
+    fn insert(&mut self, id: RegionId) {
+        self.bits.insert(id.to_usize());
+    }
+
+    fn contains(&self, id: RegionId) -> bool {
+        self.bits.contains(id.to_usize())
+    }
+
     fn clear(&mut self) {
         self.bits.clear();
     }
@@ -20,15 +20,43 @@ pub fn determine_strong_roots(vm: &VM, threads: &[Arc<DoraThread>]) -> Vec<Slot>
 }
 
 pub fn iterate_strong_roots<F: FnMut(Slot)>(vm: &VM, threads: &[Arc<DoraThread>], mut callback: F) {
+    iterate_strong_roots_with_kind(vm, threads, |_kind, slot| callback(slot));
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RootKind {
+    Stack,
+    Handle,
+    Global,
+    WaitList,
+}
+
+impl RootKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RootKind::Stack => "stack",
+            RootKind::Handle => "handle",
+            RootKind::Global => "global",
+            RootKind::WaitList => "wait-list",
+        }
+    }
+}
+
+// Same as iterate_strong_roots but also reports where the root was found.
+pub fn iterate_strong_roots_with_kind<F: FnMut(RootKind, Slot)>(
+    vm: &VM,
+    threads: &[Arc<DoraThread>],
+    mut callback: F,
+) {
     for thread in threads {
-        iterate_roots_from_stack(vm, thread, &mut callback);
-        iterate_roots_from_handles(thread, &mut callback);
+        iterate_roots_from_stack(vm, thread, &mut |slot| callback(RootKind::Stack, slot));
+        iterate_roots_from_handles(thread, &mut |slot| callback(RootKind::Handle, slot));
     }
 
-    iterate_roots_from_code_space(vm, &mut callback);
+    iterate_roots_from_code_space(vm, &mut |slot| callback(RootKind::Global, slot));
 
-    iterate_roots_from_globals(vm, &mut callback);
-    iterate_roots_from_wait_list(vm, &mut callback);
+    iterate_roots_from_globals(vm, &mut |slot| callback(RootKind::Global, slot));
+    iterate_roots_from_wait_list(vm, &mut |slot| callback(RootKind::WaitList, slot));
 }
 
 fn iterate_roots_from_wait_list<F: FnMut(Slot)>(vm: &VM, callback: &mut F) {
@@ -190,7 +218,9 @@ fn iterate_roots_from_stack_frame<F: FnMut(Slot)>(
             CodeKind::SafepointStub => true,
             CodeKind::CompileStub => true,
 
-            CodeKind::VerifyStub | CodeKind::TrapStub => unreachable!(),
+            CodeKind::VerifyStub | CodeKind::TrapStub | CodeKind::PreWriteBarrierStub => {
+                unreachable!()
+            }
         }
     } else {
         println!("no code found at pc = {:x}", pc);
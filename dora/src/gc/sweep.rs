@@ -7,8 +7,8 @@ use crate::gc::root::{determine_strong_roots, Slot};
 use crate::gc::space::Space;
 use crate::gc::tlab;
 use crate::gc::{
-    fill_region_with_free, formatted_size, iterate_weak_roots, Address, CollectionStats, Collector,
-    GcReason, Region,
+    fill_region_with_free, formatted_size, iterate_objects_in_region, iterate_weak_roots, Address,
+    CollectionStats, Collector, GcReason, Region,
 };
 use crate::os;
 use crate::safepoint;
@@ -99,6 +99,11 @@ impl Collector for SweepCollector {
         self.collect(vm, reason);
     }
 
+    fn iterate_objects(&self, _vm: &VM, f: &mut dyn FnMut(Address)) {
+        let top = self.alloc.lock().top;
+        iterate_objects_in_region(Region::new(self.heap.start, top), f);
+    }
+
     fn dump_summary(&self, runtime: f32) {
         let stats = self.stats.lock();
         let (mutator, gc) = stats.percentage(runtime);
@@ -8,6 +8,7 @@ use crate::driver::cmd::Args;
 use crate::gc::root::{determine_strong_roots, Slot};
 use crate::gc::swiper::card::CardTable;
 use crate::gc::swiper::compact::FullCollector;
+use crate::gc::swiper::concurrent::ConcurrentMarker;
 use crate::gc::swiper::controller::{HeapConfig, SharedHeapConfig};
 use crate::gc::swiper::crossing::CrossingMap;
 use crate::gc::swiper::large::LargeSpace;
@@ -30,6 +31,7 @@ use crate::vm::VM;
 
 pub mod card;
 mod compact;
+mod concurrent;
 mod controller;
 mod crossing;
 mod large;
@@ -53,6 +55,10 @@ pub const CARD_REFS: usize = CARD_SIZE / size_of::<usize>();
 
 pub const LARGE_OBJECT_SIZE: usize = 16 * K;
 
+// concurrent marking starts after a minor collection when old generation
+// and large space occupy at least this percentage of the old limit.
+const CONCURRENT_MARKING_THRESHOLD_PERCENT: usize = 50;
+
 pub struct Swiper {
     // contiguous memory for young/old generation and large space
     heap: Region,
@@ -79,6 +85,8 @@ pub struct Swiper {
 
     threadpool: Option<Mutex<Pool>>,
     config: SharedHeapConfig,
+
+    concurrent_marker: Option<ConcurrentMarker>,
 }
 
 impl Swiper {
@@ -187,6 +195,15 @@ impl Swiper {
             None
         };
 
+        let concurrent_marker = if args.flag_gc_concurrent_marking && emit_write_barrier {
+            Some(ConcurrentMarker::new(
+                Region::new(old_start, old_end),
+                Region::new(large_start, large_end),
+            ))
+        } else {
+            None
+        };
+
         Swiper {
             heap: Region::new(heap_start, heap_end),
             reserved_area,
@@ -207,6 +224,7 @@ impl Swiper {
             max_heap_size,
 
             threadpool,
+            concurrent_marker,
         }
     }
 
@@ -222,12 +240,19 @@ impl Swiper {
         mut reason: GcReason,
     ) -> CollectionKind {
         safepoint::stop_the_world(vm, |threads| {
+            let _pause = self.concurrent_marker.as_ref().map(|marker| marker.pause());
             controller::start(&self.config, &self.young, &self.old, &self.large);
 
             tlab::make_iterable_all(vm, threads);
             let rootset = determine_strong_roots(vm, threads);
 
             let kind = match kind {
+                CollectionKind::Minor if self.concurrent_marking_finished() => {
+                    reason = GcReason::Remark;
+                    self.remark_collect(vm, reason, threads, &rootset);
+                    CollectionKind::Full
+                }
+
                 CollectionKind::Minor => {
                     let promotion_failed = self.minor_collect(vm, reason, &rootset, threads);
 
@@ -256,10 +281,109 @@ impl Swiper {
                 reason,
             );
 
+            if kind.is_minor() {
+                self.start_concurrent_marking(vm, threads, &rootset);
+            }
+
             kind
         })
     }
 
+    fn concurrent_marking_finished(&self) -> bool {
+        self.concurrent_marker
+            .as_ref()
+            .is_some_and(|marker| marker.is_finished())
+    }
+
+    fn start_concurrent_marking(&self, vm: &VM, threads: &[Arc<DoraThread>], rootset: &[Slot]) {
+        let marker = match self.concurrent_marker {
+            Some(ref marker) => marker,
+            None => return,
+        };
+
+        if marker.is_active() {
+            return;
+        }
+
+        let old_size = self.old.active_size() + self.large.committed_size();
+        let old_limit = self.config.lock().old_limit;
+
+        if old_size * 100 < old_limit * CONCURRENT_MARKING_THRESHOLD_PERCENT {
+            return;
+        }
+
+        marker.start(&self.young, &self.old, &self.large, rootset, threads);
+
+        if vm.args.flag_gc_verbose {
+            println!(
+                "GC: Concurrent marking started ({} in old generation)",
+                formatted_size(old_size)
+            );
+        }
+    }
+
+    fn remark_collect(
+        &self,
+        vm: &VM,
+        reason: GcReason,
+        threads: &[Arc<DoraThread>],
+        rootset: &[Slot],
+    ) {
+        let promotion_failed = self.minor_collect(vm, reason, rootset, threads);
+
+        if promotion_failed {
+            self.full_collect(vm, GcReason::PromotionFailure, threads, rootset);
+            return;
+        }
+
+        let marker = self.concurrent_marker.as_ref().expect("missing marker");
+        marker.finish(&self.young, &self.old, &self.large, threads);
+
+        self.verify(
+            vm,
+            VerifierPhase::PreFull,
+            CollectionKind::Full,
+            "pre-remark",
+            rootset,
+            false,
+            Vec::new(),
+        );
+
+        {
+            let mut collector = FullCollector::new(
+                vm,
+                self.heap,
+                &self.young,
+                &self.old,
+                &self.large,
+                &self.card_table,
+                &self.crossing_map,
+                &vm.gc.readonly_space,
+                rootset,
+                threads,
+                reason,
+                self.min_heap_size,
+                self.max_heap_size,
+            );
+            collector.collect_marked();
+
+            if vm.args.flag_gc_stats {
+                let mut config = self.config.lock();
+                config.add_full(collector.phases());
+            }
+        }
+
+        self.verify(
+            vm,
+            VerifierPhase::PostFull,
+            CollectionKind::Full,
+            "post-remark",
+            rootset,
+            false,
+            Vec::new(),
+        );
+    }
+
     fn minor_collect(
         &self,
         vm: &VM,
@@ -358,6 +482,10 @@ impl Swiper {
         threads: &[Arc<DoraThread>],
         rootset: &[Slot],
     ) {
+        if let Some(ref marker) = self.concurrent_marker {
+            marker.abort(threads);
+        }
+
         self.verify(
             vm,
             VerifierPhase::PreFull,
@@ -545,6 +673,45 @@ impl Collector for Swiper {
         self.card_table_offset
     }
 
+    fn needs_pre_write_barrier(&self) -> bool {
+        self.concurrent_marker.is_some()
+    }
+
+    fn pre_write_barrier_slow(&self, object: Address) {
+        let marker = self.concurrent_marker.as_ref().expect("missing marker");
+        marker.enqueue(object);
+    }
+
+    fn concurrent_marking_active(&self) -> bool {
+        self.concurrent_marker
+            .as_ref()
+            .is_some_and(|marker| marker.is_active())
+    }
+
+    fn shutdown(&self) {
+        if let Some(ref marker) = self.concurrent_marker {
+            marker.stop();
+        }
+    }
+
+    fn iterate_objects(&self, _vm: &VM, f: &mut dyn FnMut(Address)) {
+        let mut regions = vec![
+            self.young.eden_active(),
+            self.young.from_active(),
+            self.young.to_active(),
+        ];
+
+        for old_region in &self.old.protected().regions {
+            regions.push(old_region.active_region());
+        }
+
+        for region in regions {
+            walk_region(region, |_object, address, _size| f(address));
+        }
+
+        self.large.visit_objects(f);
+    }
+
     fn dump_summary(&self, runtime: f32) {
         let config = self.config.lock();
         let total_gc = config.total_minor_pause + config.total_full_pause;
@@ -631,6 +798,8 @@ impl Collector for Swiper {
 
 impl Drop for Swiper {
     fn drop(&mut self) {
+        self.shutdown();
+
         os::free(
             self.unaligned_reserved.start,
             self.unaligned_reserved.size(),
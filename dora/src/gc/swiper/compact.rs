@@ -95,6 +95,15 @@ impl<'a> FullCollector<'a> {
     }
 
     pub fn collect(&mut self) {
+        self.mark_and_compact(true);
+    }
+
+    // compacts the heap using marks that were already set by concurrent marking.
+    pub fn collect_marked(&mut self) {
+        self.mark_and_compact(false);
+    }
+
+    fn mark_and_compact(&mut self, mark: bool) {
         let dev_verbose = self.vm.args.flag_gc_dev_verbose;
         let stats = self.vm.args.flag_gc_stats;
         self.init_old_top = self.old_protected.regions.iter().map(|r| r.top()).collect();
@@ -105,7 +114,9 @@ impl<'a> FullCollector<'a> {
             println!("Full GC: Start");
         }
 
-        self.mark_live();
+        if mark {
+            self.mark_live();
+        }
 
         if stats {
             let duration = timer.stop();
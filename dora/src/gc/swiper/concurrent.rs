@@ -0,0 +1,363 @@
+use parking_lot::{Condvar, Mutex, MutexGuard};
+use std::collections::HashSet;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::gc::root::Slot;
+use crate::gc::swiper::large::LargeSpace;
+use crate::gc::swiper::old::OldGen;
+use crate::gc::swiper::walk_region;
+use crate::gc::swiper::young::YoungGen;
+use crate::gc::{Address, Region};
+use crate::threads::DoraThread;
+
+// number of objects traced before the marker thread gives
+// stop-the-world collections the chance to run.
+const MARKING_CHUNK: usize = 1024;
+
+// Marks the old generation concurrently to the mutator.
+//
+// Marking is snapshot-at-the-beginning: all old objects that are reachable
+// at the initial mark get marked. Mutators record each reference they are
+// about to overwrite (pre-write barrier) while the concurrent marking flag in
+// their thread local data is set. Objects allocated or promoted after the initial
+// mark are not traced by the marker, the final remark considers them live.
+//
+// The marker thread holds the pause lock while processing a chunk of objects.
+// Collections acquire this lock while all mutators are stopped, so the marker
+// never runs concurrently to a collection.
+pub struct ConcurrentMarker {
+    shared: Arc<Shared>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+struct Shared {
+    pause: Mutex<()>,
+    state: Mutex<MarkingState>,
+    cv: Condvar,
+    satb_queue: Mutex<Vec<Address>>,
+
+    active: AtomicBool,
+    finished: AtomicBool,
+    shutdown: AtomicBool,
+
+    old_total: Region,
+    large_total: Region,
+}
+
+impl ConcurrentMarker {
+    pub fn new(old_total: Region, large_total: Region) -> ConcurrentMarker {
+        let shared = Arc::new(Shared {
+            pause: Mutex::new(()),
+            state: Mutex::new(MarkingState::new(old_total, large_total)),
+            cv: Condvar::new(),
+            satb_queue: Mutex::new(Vec::new()),
+
+            active: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            shutdown: AtomicBool::new(false),
+
+            old_total,
+            large_total,
+        });
+
+        let thread_shared = shared.clone();
+        let thread = thread::Builder::new()
+            .name("concurrent-marker".into())
+            .spawn(move || run_marker(thread_shared))
+            .expect("failed to spawn marker thread");
+
+        ConcurrentMarker {
+            shared,
+            thread: Mutex::new(Some(thread)),
+        }
+    }
+
+    // stops the marker thread until the returned guard is dropped.
+    pub fn pause(&self) -> MutexGuard<'_, ()> {
+        self.shared.pause.lock()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.shared.active.load(Ordering::Relaxed)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.is_active() && self.shared.finished.load(Ordering::Acquire)
+    }
+
+    // called by the pre-write barrier with the overwritten reference.
+    pub fn enqueue(&self, object: Address) {
+        if self.shared.old_total.contains(object) || self.shared.large_total.contains(object) {
+            let mut queue = self.shared.satb_queue.lock();
+            queue.push(object);
+        }
+    }
+
+    // Initial mark: needs to be invoked right after a minor collection while
+    // all mutators are stopped. Since the young generation only contains survivors
+    // at this point, it is treated like a set of roots.
+    pub fn start(
+        &self,
+        young: &YoungGen,
+        old: &OldGen,
+        large: &LargeSpace,
+        rootset: &[Slot],
+        threads: &[Arc<DoraThread>],
+    ) {
+        let mut state = self.shared.state.lock();
+        assert!(!self.is_active());
+
+        state.snapshot_regions = old
+            .protected()
+            .regions
+            .iter()
+            .map(|r| r.active_region())
+            .collect();
+
+        large.visit_objects(|object| {
+            state.snapshot_large.insert(object);
+        });
+
+        for root in rootset {
+            state.mark_grey(root.get());
+        }
+
+        for region in young_regions(young) {
+            walk_region(region, |object, _address, _size| {
+                object.visit_reference_fields(|field| {
+                    state.mark_grey(field.get());
+                });
+            });
+        }
+
+        self.shared.finished.store(false, Ordering::Relaxed);
+        self.shared.active.store(true, Ordering::Relaxed);
+        set_marking_flag(threads, true);
+
+        self.shared.cv.notify_one();
+    }
+
+    // Final remark: needs to be invoked right after a minor collection while all
+    // mutators are stopped. Completes marking, afterwards every live object in
+    // the heap is marked and can be compacted.
+    pub fn finish(
+        &self,
+        young: &YoungGen,
+        old: &OldGen,
+        large: &LargeSpace,
+        threads: &[Arc<DoraThread>],
+    ) {
+        let mut state = self.shared.state.lock();
+        assert!(self.is_active());
+
+        state.drain_satb_queue(&self.shared.satb_queue);
+        let done = state.trace(usize::MAX);
+        assert!(done);
+
+        // Everything allocated after the initial mark is considered live.
+        // References from these objects into the snapshot were already marked
+        // through the pre-write barrier.
+        let regions = old
+            .protected()
+            .regions
+            .iter()
+            .map(|r| r.active_region())
+            .collect::<Vec<_>>();
+
+        for region in regions {
+            let tams = state.top_at_mark_start(&region);
+            walk_region(Region::new(tams, region.end), |object, _address, _size| {
+                object.header_mut().mark_non_atomic();
+            });
+        }
+
+        for region in young_regions(young) {
+            walk_region(region, |object, _address, _size| {
+                object.header_mut().mark_non_atomic();
+            });
+        }
+
+        large.visit_objects(|address| {
+            if !state.snapshot_large.contains(&address) {
+                address.to_mut_obj().header_mut().mark_non_atomic();
+            }
+        });
+
+        self.deactivate(&mut state, threads);
+    }
+
+    // Stops concurrent marking and removes all marks, needs to be invoked
+    // while all mutators are stopped.
+    pub fn abort(&self, threads: &[Arc<DoraThread>]) {
+        if !self.is_active() {
+            return;
+        }
+
+        let mut state = self.shared.state.lock();
+
+        for region in &state.snapshot_regions {
+            walk_region(*region, |object, _address, _size| {
+                object.header_mut().unmark_non_atomic();
+            });
+        }
+
+        for &address in &state.snapshot_large {
+            address.to_mut_obj().header_mut().unmark_non_atomic();
+        }
+
+        self.deactivate(&mut state, threads);
+    }
+
+    pub fn stop(&self) {
+        {
+            let _state = self.shared.state.lock();
+            self.shared.shutdown.store(true, Ordering::Relaxed);
+            self.shared.cv.notify_one();
+        }
+
+        if let Some(thread) = self.thread.lock().take() {
+            thread.join().expect("marker thread failed");
+        }
+    }
+
+    fn deactivate(&self, state: &mut MarkingState, threads: &[Arc<DoraThread>]) {
+        state.marking_stack.clear();
+        state.snapshot_regions.clear();
+        state.snapshot_large.clear();
+        self.shared.satb_queue.lock().clear();
+
+        set_marking_flag(threads, false);
+        self.shared.active.store(false, Ordering::Relaxed);
+        self.shared.finished.store(false, Ordering::Relaxed);
+    }
+}
+
+fn run_marker(shared: Arc<Shared>) {
+    loop {
+        {
+            let mut state = shared.state.lock();
+
+            while !shared.shutdown.load(Ordering::Relaxed) && !has_work(&shared) {
+                shared.cv.wait(&mut state);
+            }
+
+            if shared.shutdown.load(Ordering::Relaxed) {
+                return;
+            }
+        }
+
+        let pause = shared.pause.lock();
+        let mut state = shared.state.lock();
+
+        // a collection might have finished or aborted marking in the meantime.
+        if has_work(&shared) {
+            state.drain_satb_queue(&shared.satb_queue);
+
+            if state.trace(MARKING_CHUNK) && shared.satb_queue.lock().is_empty() {
+                shared.finished.store(true, Ordering::Release);
+            }
+        }
+
+        drop(state);
+
+        // give waiting collections the chance to acquire the lock.
+        MutexGuard::unlock_fair(pause);
+    }
+}
+
+fn has_work(shared: &Shared) -> bool {
+    shared.active.load(Ordering::Relaxed) && !shared.finished.load(Ordering::Relaxed)
+}
+
+struct MarkingState {
+    marking_stack: Vec<Address>,
+
+    // active part of each old generation region at the initial mark,
+    // the end of each region is the top-at-mark-start.
+    snapshot_regions: Vec<Region>,
+
+    // large objects that existed at the initial mark.
+    snapshot_large: HashSet<Address>,
+
+    old_total: Region,
+    large_total: Region,
+}
+
+impl MarkingState {
+    fn new(old_total: Region, large_total: Region) -> MarkingState {
+        MarkingState {
+            marking_stack: Vec::new(),
+            snapshot_regions: Vec::new(),
+            snapshot_large: HashSet::new(),
+            old_total,
+            large_total,
+        }
+    }
+
+    fn in_snapshot(&self, object: Address) -> bool {
+        if self.old_total.contains(object) {
+            self.snapshot_regions
+                .iter()
+                .any(|region| region.contains(object))
+        } else if self.large_total.contains(object) {
+            self.snapshot_large.contains(&object)
+        } else {
+            false
+        }
+    }
+
+    fn top_at_mark_start(&self, region: &Region) -> Address {
+        self.snapshot_regions
+            .iter()
+            .find(|snapshot| snapshot.start == region.start)
+            .map(|snapshot| snapshot.end)
+            .unwrap_or(region.start)
+    }
+
+    fn mark_grey(&mut self, object: Address) {
+        if self.in_snapshot(object) && object.to_obj().header().try_mark_non_atomic() {
+            self.marking_stack.push(object);
+        }
+    }
+
+    fn drain_satb_queue(&mut self, queue: &Mutex<Vec<Address>>) {
+        let objects = mem::take(&mut *queue.lock());
+
+        for object in objects {
+            self.mark_grey(object);
+        }
+    }
+
+    // traces at most `limit` objects, returns true when the marking stack is empty.
+    fn trace(&mut self, limit: usize) -> bool {
+        let mut traced = 0;
+
+        while traced < limit {
+            let object = match self.marking_stack.pop() {
+                Some(object) => object,
+                None => return true,
+            };
+
+            object.to_mut_obj().visit_reference_fields(|field| {
+                self.mark_grey(field.get());
+            });
+
+            traced += 1;
+        }
+
+        self.marking_stack.is_empty()
+    }
+}
+
+fn young_regions(young: &YoungGen) -> [Region; 3] {
+    [young.eden_active(), young.from_active(), young.to_active()]
+}
+
+fn set_marking_flag(threads: &[Arc<DoraThread>], value: bool) {
+    for thread in threads {
+        thread.tld.set_concurrent_marking(value);
+    }
+}
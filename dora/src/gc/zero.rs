@@ -1,6 +1,6 @@
 use crate::driver::cmd::Args;
 use crate::gc::bump::BumpAllocator;
-use crate::gc::{Address, Collector, GcReason, Region};
+use crate::gc::{iterate_objects_in_region, Address, Collector, GcReason, Region};
 use crate::os::{self, MemoryPermission};
 use crate::vm::VM;
 
@@ -55,6 +55,10 @@ impl Collector for ZeroCollector {
         // do nothing
     }
 
+    fn iterate_objects(&self, _vm: &VM, f: &mut dyn FnMut(Address)) {
+        iterate_objects_in_region(Region::new(self.start, self.alloc.top()), f);
+    }
+
     fn dump_summary(&self, runtime: f32) {
         let mutator = runtime;
         let gc = 0.0f32;
@@ -37,6 +37,19 @@ impl HandleMemory {
         self.inner.lock().pop_border();
     }
 
+    pub fn borders(&self) -> usize {
+        self.inner.lock().borders.len()
+    }
+
+    // Pops borders until only `count` are left.
+    pub fn pop_borders_to(&self, count: usize) {
+        let mut inner = self.inner.lock();
+
+        while inner.borders.len() > count {
+            inner.pop_border();
+        }
+    }
+
     pub fn iter(&self) -> HandleMemoryIter {
         let inner = self.inner.lock();
         let len = inner.blocks.len();
@@ -1,10 +1,11 @@
 use crate::bytecode;
-use crate::language::error::msg::ErrorMessage;
+use crate::language::error::lint::{Lint, LintLevel};
+use crate::language::error::msg::{ErrorDescriptor, ErrorMessage};
 use crate::language::sem_analysis::{FctDefinition, SemAnalysis, SourceFileId};
 use crate::language::sym::Sym;
 use dora_parser::ast;
 use dora_parser::interner::Name;
-use dora_parser::lexer::position::Position;
+use dora_parser::lexer::position::{Position, Span};
 
 pub use readty::{read_type, read_type_unchecked, AllowSelf, TypeParamContext};
 
@@ -22,6 +23,8 @@ mod generator_tests;
 mod globaldefck;
 mod implck;
 mod impldefck;
+mod lintck;
+mod patterns;
 mod program_parser;
 mod readty;
 mod returnck;
@@ -40,7 +43,7 @@ mod useck;
 
 macro_rules! return_on_error {
     ($vm: ident) => {{
-        if $vm.diag.lock().has_errors() {
+        if $vm.diag.lock().has_semantic_errors() {
             return false;
         }
     }};
@@ -106,7 +109,11 @@ pub fn check(sa: &mut SemAnalysis) -> bool {
     fctbodyck::check(sa);
     return_on_error!(sa);
 
-    true
+    // report lints that need the whole program
+    lintck::check(sa);
+    return_on_error!(sa);
+
+    !sa.diag.lock().has_errors()
 }
 
 pub fn emit_ast(sa: &SemAnalysis) {
@@ -232,6 +239,66 @@ pub fn expr_block_always_returns(e: &ast::ExprBlockType) -> bool {
     returnck::expr_block_returns_value(e).is_ok()
 }
 
+pub fn expr_block_unreachable_code(e: &ast::ExprBlockType) -> Option<Span> {
+    returnck::expr_block_unreachable_code(e)
+}
+
+// Reports the lint as warning or error depending on its level. Lints
+// are only reported for the program, not for the stdlib or other packages.
+pub fn report_lint(
+    sa: &SemAnalysis,
+    allowed_lints: &[Lint],
+    lint: Lint,
+    file: SourceFileId,
+    pos: Position,
+    msg: ErrorMessage,
+) {
+    if sa.source_file(file).package_id != sa.program_package_id() {
+        return;
+    }
+
+    let level = if allowed_lints.contains(&lint) {
+        LintLevel::Allow
+    } else {
+        sa.args.lint_level(lint)
+    };
+
+    let desc = match level {
+        LintLevel::Allow => return,
+        LintLevel::Warn => ErrorDescriptor::new_warning(file, pos, msg),
+        LintLevel::Deny => ErrorDescriptor::new(file, pos, msg),
+    };
+
+    let note = format!(
+        "lint `{}` can be silenced with `@allow({})`.",
+        lint.name(),
+        lint.name()
+    );
+    sa.diag.lock().report_descriptor(desc.with_note(note));
+}
+
+pub fn read_allowed_lints(
+    sa: &SemAnalysis,
+    file: SourceFileId,
+    pos: Position,
+    names: &[Name],
+) -> Vec<Lint> {
+    let mut lints = Vec::new();
+
+    for &name in names {
+        let name = sa.interner.str(name);
+
+        if let Some(lint) = Lint::find(&name) {
+            lints.push(lint);
+        } else {
+            let msg = ErrorMessage::UnknownLint(name.to_string());
+            sa.diag.lock().report(file, pos, msg);
+        }
+    }
+
+    lints
+}
+
 pub fn report_sym_shadow(
     sa: &SemAnalysis,
     name: Name,
@@ -255,7 +322,57 @@ pub fn report_sym_shadow(
         _ => unreachable!(),
     };
 
-    sa.diag.lock().report(file, pos, msg);
+    let mut desc = ErrorDescriptor::new(file, pos, msg);
+
+    if let Some((definition_file, definition_pos)) = sym_definition(sa, &sym) {
+        desc = desc.with_label(definition_file, definition_pos, "first defined here");
+    }
+
+    sa.diag.lock().report_descriptor(desc);
+}
+
+fn sym_definition(sa: &SemAnalysis, sym: &Sym) -> Option<(SourceFileId, Position)> {
+    match *sym {
+        Sym::Class(id) => {
+            let cls = sa.classes.idx(id);
+            let cls = cls.read();
+            Some((cls.file_id?, cls.pos?))
+        }
+        Sym::Struct(id) => {
+            let xstruct = sa.structs.idx(id);
+            let xstruct = xstruct.read();
+            Some((xstruct.file_id, xstruct.pos))
+        }
+        Sym::Trait(id) => {
+            let xtrait = sa.traits[id].read();
+            Some((xtrait.file_id, xtrait.pos))
+        }
+        Sym::Enum(id) => {
+            let xenum = sa.enums[id].read();
+            Some((xenum.file_id, xenum.pos))
+        }
+        Sym::Fct(id) => {
+            let fct = sa.fcts.idx(id);
+            let fct = fct.read();
+            Some((fct.file_id, fct.pos))
+        }
+        Sym::Global(id) => {
+            let global = sa.globals.idx(id);
+            let global = global.read();
+            Some((global.file_id, global.pos))
+        }
+        Sym::Const(id) => {
+            let const_ = sa.consts.idx(id);
+            let const_ = const_.read();
+            Some((const_.file_id, const_.pos))
+        }
+        Sym::Module(id) => {
+            let module = sa.modules.idx(id);
+            let module = module.read();
+            Some((module.file_id?, module.ast.as_ref()?.pos))
+        }
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -327,6 +444,41 @@ pub mod tests {
         });
     }
 
+    pub fn ok_without_warnings(code: &'static str) {
+        test::check(code, |vm| {
+            let diag = vm.diag.lock();
+
+            for e in diag.errors().iter().chain(diag.warnings()) {
+                println!("{}", e.message(vm));
+                println!("{:?}", e);
+                println!();
+            }
+
+            assert!(!diag.has_errors(), "program should not have errors.");
+            assert!(!diag.has_warnings(), "program should not have warnings.");
+        });
+    }
+
+    pub fn warn(code: &'static str, pos: Position, msg: ErrorMessage) {
+        test::check(code, |vm| {
+            let diag = vm.diag.lock();
+            let warnings = diag.warnings();
+
+            println!("errors = {:?}", diag.errors());
+            println!("warnings = {:?}", warnings);
+
+            assert!(!diag.has_errors(), "program should not have errors.");
+            assert_eq!(
+                1,
+                warnings.len(),
+                "found {} warnings instead",
+                warnings.len()
+            );
+            assert_eq!(Some(pos), warnings[0].pos);
+            assert_eq!(msg, warnings[0].msg);
+        });
+    }
+
     pub fn errors(code: &'static str, vec: &[(Position, ErrorMessage)]) {
         test::check(code, |vm| {
             let diag = vm.diag.lock();
@@ -1,2 +1,4 @@
 pub mod diag;
+pub mod lint;
 pub mod msg;
+pub mod render;
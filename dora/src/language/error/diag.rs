@@ -1,25 +1,60 @@
 use crate::language::error::msg::{ErrorDescriptor, ErrorMessage};
+use crate::language::error::render::render;
 use crate::language::sem_analysis::{SemAnalysis, SourceFileId};
 
-use dora_parser::lexer::position::Position;
+use dora_parser::lexer::position::{Position, Span};
 
 pub struct Diagnostic {
     errors: Vec<ErrorDescriptor>,
+    warnings: Vec<ErrorDescriptor>,
+    syntax_errors: usize,
 }
 
 impl Diagnostic {
     pub fn new() -> Diagnostic {
-        Diagnostic { errors: Vec::new() }
+        Diagnostic {
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            syntax_errors: 0,
+        }
     }
 
     pub fn errors(&self) -> &[ErrorDescriptor] {
         &self.errors
     }
 
+    pub fn warnings(&self) -> &[ErrorDescriptor] {
+        &self.warnings
+    }
+
     pub fn report(&mut self, file: SourceFileId, pos: Position, msg: ErrorMessage) {
         self.errors.push(ErrorDescriptor::new(file, pos, msg));
     }
 
+    pub fn report_syntax_error(&mut self, file: SourceFileId, pos: Position, msg: ErrorMessage) {
+        self.syntax_errors += 1;
+        self.report(file, pos, msg);
+    }
+
+    pub fn report_with_span(
+        &mut self,
+        file: SourceFileId,
+        pos: Position,
+        span: Span,
+        msg: ErrorMessage,
+    ) {
+        self.errors
+            .push(ErrorDescriptor::new(file, pos, msg).with_span(span));
+    }
+
+    pub fn report_descriptor(&mut self, desc: ErrorDescriptor) {
+        if desc.is_warning() {
+            self.warnings.push(desc);
+        } else {
+            self.errors.push(desc);
+        }
+    }
+
     pub fn report_without_location(&mut self, msg: ErrorMessage) {
         self.errors.push(ErrorDescriptor::new_without_location(msg));
     }
@@ -28,9 +63,25 @@ impl Diagnostic {
         !self.errors.is_empty()
     }
 
+    // Syntax errors don't stop the semantic analysis, since the parts of the
+    // program that parsed successfully can still be checked.
+    pub fn has_semantic_errors(&self) -> bool {
+        self.errors.len() > self.syntax_errors
+    }
+
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
     pub fn dump(&self, sa: &SemAnalysis) {
         for err in &self.errors {
-            eprintln!("{}", &err.message(sa));
+            eprintln!("{}", render(sa, err, sa.args.error_format()));
+        }
+    }
+
+    pub fn dump_warnings(&self, sa: &SemAnalysis) {
+        for warning in &self.warnings {
+            eprintln!("{}", render(sa, warning, sa.args.error_format()));
         }
     }
 }
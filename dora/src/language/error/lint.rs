@@ -0,0 +1,48 @@
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Lint {
+    UnusedVariable,
+    UnusedImport,
+    UnusedFunction,
+    UnreachableCode,
+    ShadowedBinding,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Lint {
+    pub const ALL: &'static [Lint] = &[
+        Lint::UnusedVariable,
+        Lint::UnusedImport,
+        Lint::UnusedFunction,
+        Lint::UnreachableCode,
+        Lint::ShadowedBinding,
+    ];
+
+    pub fn find(name: &str) -> Option<Lint> {
+        Lint::ALL.iter().cloned().find(|lint| lint.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unusedVariable",
+            Lint::UnusedImport => "unusedImport",
+            Lint::UnusedFunction => "unusedFunction",
+            Lint::UnreachableCode => "unreachableCode",
+            Lint::ShadowedBinding => "shadowedBinding",
+        }
+    }
+
+    pub fn default_level(self) -> LintLevel {
+        match self {
+            // Rebinding a name after converting its value is common,
+            // only report shadowing when asked for it.
+            Lint::ShadowedBinding => LintLevel::Allow,
+            _ => LintLevel::Warn,
+        }
+    }
+}
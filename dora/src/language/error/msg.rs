@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use crate::language::sem_analysis::{SemAnalysis, SourceFileId};
-use dora_parser::lexer::position::Position;
+use dora_parser::lexer::position::{Position, Span};
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum ErrorMessage {
@@ -48,8 +48,10 @@ pub enum ErrorMessage {
     MatchPatternWrongNumberOfParams(usize, usize),
     EnumExpected,
     EnumVariantExpected,
-    MatchUncoveredVariant,
+    MatchUncoveredPattern(String),
     MatchUnreachablePattern,
+    PatternTypeMismatch(String),
+    PatternAlternativesBindingMismatch(String),
     VarNeedsTypeInfo(String),
     ParamTypesIncompatible(String, Vec<String>, Vec<String>),
     LambdaParamTypesIncompatible(Vec<String>, Vec<String>),
@@ -98,6 +100,8 @@ pub enum ErrorMessage {
     ExpectedType(String),
     ExpectedIdentifier(String),
     ExpectedStringable(String),
+    ExpectedFormattable(String, String),
+    FormatSpecNotForType(String, String),
     ExpectedSomeIdentifier,
     ExpectedModule,
     ExpectedPath,
@@ -144,6 +148,7 @@ pub enum ErrorMessage {
     MissingAbstractOverride(String, String),
     ModifierNotAllowedForStaticMethod(String),
     InvalidTestAnnotationUsage,
+    InvalidSerialAnnotationUsage,
     GlobalInitializerNotSupported,
     TypeNotUsableInForIn(String),
     UnknownStructField(String, String),
@@ -167,6 +172,14 @@ pub enum ErrorMessage {
     MissingFileArgument,
     PackageAlreadyExists(String),
     UnknownPackage(String),
+    TryExpectsOptionOrResult(String),
+    TryIncompatibleReturnType(String, String),
+    UnknownLint(String),
+    UnusedVariable(String),
+    UnusedImport(String),
+    UnusedFunction(String),
+    UnreachableCode,
+    ShadowedBinding(String),
 }
 
 impl ErrorMessage {
@@ -294,10 +307,19 @@ impl ErrorMessage {
                 )
             }
             ErrorMessage::VarAlreadyInPattern => "var is already used in pattern.".into(),
+            ErrorMessage::PatternTypeMismatch(ref ty) => {
+                format!("pattern can't match value of type `{}`.", ty)
+            }
+            ErrorMessage::PatternAlternativesBindingMismatch(ref name) => format!(
+                "variable `{}` needs to be bound with the same type in all alternatives.",
+                name
+            ),
             ErrorMessage::EnumExpected => format!("enum expected."),
             ErrorMessage::EnumVariantExpected => format!("enum variant expected."),
-            ErrorMessage::MatchUncoveredVariant => "not all variants are covered.".into(),
-            ErrorMessage::MatchUnreachablePattern => "not all variants are covered.".into(),
+            ErrorMessage::MatchUncoveredPattern(ref pattern) => {
+                format!("pattern `{}` not covered.", pattern)
+            }
+            ErrorMessage::MatchUnreachablePattern => "unreachable pattern.".into(),
             ErrorMessage::VarNeedsTypeInfo(ref name) => format!(
                 "variable `{}` needs either type declaration or expression.",
                 name
@@ -444,6 +466,13 @@ impl ErrorMessage {
             ErrorMessage::ExpectedStringable(ref ty) => {
                 format!("type {} does not implement Stringable.", ty)
             }
+            ErrorMessage::ExpectedFormattable(ref ty, ref spec) => format!(
+                "type {} does not implement Formattable, required by format spec `{}`.",
+                ty, spec
+            ),
+            ErrorMessage::FormatSpecNotForType(ref spec, ref ty) => {
+                format!("format spec `{}` can't be used with type {}.", spec, ty)
+            }
             ErrorMessage::MisplacedAnnotation(ref modifier) => {
                 format!("misplaced annotation `{}`.", modifier)
             }
@@ -544,6 +573,9 @@ impl ErrorMessage {
                 format!("modifier `{}` not allowed for static method.", modifier)
             }
             ErrorMessage::InvalidTestAnnotationUsage => "invalid usage of @Test annotation.".into(),
+            ErrorMessage::InvalidSerialAnnotationUsage => {
+                "@serial annotation is only allowed on @Test functions.".into()
+            }
             ErrorMessage::GlobalInitializerNotSupported => {
                 "global variables do no support initial assignment for now.".into()
             }
@@ -600,15 +632,59 @@ impl ErrorMessage {
             ErrorMessage::UnknownPackage(ref name) => {
                 format!("no package with name `{}` was found.", name)
             }
+            ErrorMessage::TryExpectsOptionOrResult(ref ty) => format!(
+                "`?` expects value of type `Option` or `Result` but got `{}`.",
+                ty
+            ),
+            ErrorMessage::TryIncompatibleReturnType(ref expr_ty, ref fct_ty) => format!(
+                "`?` on `{}` can't be used in function returning `{}`.",
+                expr_ty, fct_ty
+            ),
+            ErrorMessage::UnknownLint(ref name) => format!("unknown lint `{}`.", name),
+            ErrorMessage::UnusedVariable(ref name) => format!("unused variable `{}`.", name),
+            ErrorMessage::UnusedImport(ref name) => format!("unused import `{}`.", name),
+            ErrorMessage::UnusedFunction(ref name) => {
+                format!("function `{}` is never used.", name)
+            }
+            ErrorMessage::UnreachableCode => "unreachable code.".into(),
+            ErrorMessage::ShadowedBinding(ref name) => {
+                format!("variable `{}` shadows an earlier binding.", name)
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
         }
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct ErrorLabel {
+    pub file: SourceFileId,
+    pub pos: Position,
+    pub message: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct ErrorDescriptor {
     pub file: Option<SourceFileId>,
     pub pos: Option<Position>,
+    pub span: Option<Span>,
     pub msg: ErrorMessage,
+    pub severity: Severity,
+    pub labels: Vec<ErrorLabel>,
+    pub notes: Vec<String>,
 }
 
 impl ErrorDescriptor {
@@ -616,7 +692,18 @@ impl ErrorDescriptor {
         ErrorDescriptor {
             file: Some(file),
             pos: Some(pos),
+            span: None,
             msg,
+            severity: Severity::Error,
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn new_warning(file: SourceFileId, pos: Position, msg: ErrorMessage) -> ErrorDescriptor {
+        ErrorDescriptor {
+            severity: Severity::Warning,
+            ..ErrorDescriptor::new(file, pos, msg)
         }
     }
 
@@ -624,8 +711,45 @@ impl ErrorDescriptor {
         ErrorDescriptor {
             file: None,
             pos: None,
+            span: None,
             msg,
+            severity: Severity::Error,
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    // Underlines the whole span instead of only the token at `pos`.
+    pub fn with_span(mut self, span: Span) -> ErrorDescriptor {
+        if span.is_valid() {
+            self.span = Some(span);
         }
+
+        self
+    }
+
+    pub fn with_label(
+        mut self,
+        file: SourceFileId,
+        pos: Position,
+        message: impl Into<String>,
+    ) -> ErrorDescriptor {
+        self.labels.push(ErrorLabel {
+            file,
+            pos,
+            message: message.into(),
+        });
+
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> ErrorDescriptor {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
     }
 
     pub fn message(&self, sa: &SemAnalysis) -> String {
@@ -634,14 +758,15 @@ impl ErrorDescriptor {
 
             let file = sa.source_file(file);
             format!(
-                "error in {:?} at {}: {}",
+                "{} in {:?} at {}: {}",
+                self.severity.name(),
                 file.path,
                 pos,
                 self.msg.message()
             )
         } else {
             assert!(self.pos.is_none());
-            format!("error: {}", self.msg.message())
+            format!("{}: {}", self.severity.name(), self.msg.message())
         }
     }
 }
@@ -0,0 +1,408 @@
+use std::fmt::Write;
+
+use crate::driver::cmd::ErrorFormat;
+use crate::language::error::msg::ErrorDescriptor;
+use crate::language::sem_analysis::{SemAnalysis, SourceFileId};
+
+use dora_parser::lexer::position::{Position, Span};
+
+// Same tab width as the lexer uses for computing columns.
+const TAB_WIDTH: u32 = 4;
+
+pub fn render(sa: &SemAnalysis, desc: &ErrorDescriptor, format: ErrorFormat) -> String {
+    match format {
+        ErrorFormat::Human => render_human(sa, desc),
+        ErrorFormat::Short => desc.message(sa),
+        ErrorFormat::Json => render_json(sa, desc),
+    }
+}
+
+// Source range of a diagnostic, lines and columns are 1-based and
+// the end column is exclusive.
+struct Location {
+    line: u32,
+    column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+struct Snippet {
+    location: Location,
+    text: String,
+}
+
+impl Snippet {
+    fn new(sa: &SemAnalysis, file: SourceFileId, pos: Position, span: Option<Span>) -> Snippet {
+        let content = &sa.source_file(file).content;
+
+        let (start, end) = match span {
+            Some(span) if (span.end() as usize) <= content.len() => (
+                position_of(content, span.start() as usize),
+                position_of(content, span.end() as usize),
+            ),
+            _ => (pos, pos),
+        };
+
+        let text = content
+            .lines()
+            .nth(start.line as usize - 1)
+            .map(expand_tabs)
+            .unwrap_or_default();
+
+        let (end_line, end_column) = if span.is_none() || end == start {
+            (start.line, start.column + token_length(&text, start.column))
+        } else {
+            (end.line, end.column)
+        };
+
+        Snippet {
+            location: Location {
+                line: start.line,
+                column: start.column,
+                end_line,
+                end_column,
+            },
+            text,
+        }
+    }
+
+    // Length of the underline, multi-line spans are only underlined
+    // until the end of their first line.
+    fn underline_length(&self) -> usize {
+        let column = self.location.column as usize;
+
+        let length = if self.location.end_line == self.location.line {
+            self.location.end_column as usize - column
+        } else {
+            (self.text.chars().count() + 1).saturating_sub(column)
+        };
+
+        length.max(1)
+    }
+}
+
+fn render_human(sa: &SemAnalysis, desc: &ErrorDescriptor) -> String {
+    let mut output = format!("{}: {}\n", desc.severity.name(), desc.msg.message());
+
+    let file = match desc.file {
+        Some(file) => file,
+        None => return output,
+    };
+
+    let pos = desc.pos.expect("uninitialized pos");
+    let snippet = Snippet::new(sa, file, pos, desc.span);
+
+    let labels = desc
+        .labels
+        .iter()
+        .map(|label| (label, Snippet::new(sa, label.file, label.pos, None)))
+        .collect::<Vec<_>>();
+
+    let max_line = labels
+        .iter()
+        .map(|(_, snippet)| snippet.location.line)
+        .chain(std::iter::once(snippet.location.line))
+        .max()
+        .unwrap();
+    let width = max_line.to_string().len();
+    let gutter = " ".repeat(width);
+
+    writeln!(
+        output,
+        "{}--> {}:{}:{}",
+        gutter,
+        sa.source_file(file).path.display(),
+        snippet.location.line,
+        snippet.location.column
+    )
+    .unwrap();
+    write_snippet(&mut output, width, &snippet, '^', "");
+
+    for (label, label_snippet) in &labels {
+        if label.file != file {
+            writeln!(
+                output,
+                "{}::: {}:{}:{}",
+                gutter,
+                sa.source_file(label.file).path.display(),
+                label_snippet.location.line,
+                label_snippet.location.column
+            )
+            .unwrap();
+        }
+
+        write_snippet(&mut output, width, label_snippet, '-', &label.message);
+    }
+
+    if !desc.notes.is_empty() {
+        writeln!(output, "{} |", gutter).unwrap();
+    }
+
+    for note in &desc.notes {
+        writeln!(output, "{} = note: {}", gutter, note).unwrap();
+    }
+
+    output
+}
+
+fn write_snippet(output: &mut String, width: usize, snippet: &Snippet, mark: char, label: &str) {
+    let gutter = " ".repeat(width);
+    let indent = " ".repeat(snippet.location.column as usize - 1);
+    let underline = mark.to_string().repeat(snippet.underline_length());
+
+    writeln!(output, "{} |", gutter).unwrap();
+    writeln!(
+        output,
+        "{:>width$} | {}",
+        snippet.location.line,
+        snippet.text,
+        width = width
+    )
+    .unwrap();
+
+    if label.is_empty() {
+        writeln!(output, "{} | {}{}", gutter, indent, underline).unwrap();
+    } else {
+        writeln!(output, "{} | {}{} {}", gutter, indent, underline, label).unwrap();
+    }
+}
+
+fn render_json(sa: &SemAnalysis, desc: &ErrorDescriptor) -> String {
+    let mut output = String::new();
+
+    write!(
+        output,
+        "{{\"severity\":\"{}\",\"message\":{}",
+        desc.severity.name(),
+        json_string(&desc.msg.message())
+    )
+    .unwrap();
+
+    if let Some(file) = desc.file {
+        let pos = desc.pos.expect("uninitialized pos");
+        let snippet = Snippet::new(sa, file, pos, desc.span);
+        write_json_location(&mut output, sa, file, &snippet.location);
+    } else {
+        output.push_str(",\"file\":null");
+    }
+
+    output.push_str(",\"labels\":[");
+
+    for (idx, label) in desc.labels.iter().enumerate() {
+        if idx > 0 {
+            output.push(',');
+        }
+
+        let snippet = Snippet::new(sa, label.file, label.pos, None);
+        write!(output, "{{\"message\":{}", json_string(&label.message)).unwrap();
+        write_json_location(&mut output, sa, label.file, &snippet.location);
+        output.push('}');
+    }
+
+    output.push_str("],\"notes\":[");
+
+    for (idx, note) in desc.notes.iter().enumerate() {
+        if idx > 0 {
+            output.push(',');
+        }
+
+        output.push_str(&json_string(note));
+    }
+
+    write!(
+        output,
+        "],\"rendered\":{}}}",
+        json_string(&render_human(sa, desc))
+    )
+    .unwrap();
+
+    output
+}
+
+fn write_json_location(
+    output: &mut String,
+    sa: &SemAnalysis,
+    file: SourceFileId,
+    location: &Location,
+) {
+    let path = sa.source_file(file).path.display().to_string();
+
+    write!(
+        output,
+        ",\"file\":{},\"line\":{},\"column\":{},\"end_line\":{},\"end_column\":{}",
+        json_string(&path),
+        location.line,
+        location.column,
+        location.end_line,
+        location.end_column
+    )
+    .unwrap();
+}
+
+fn json_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+
+    for ch in value.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => write!(result, "\\u{:04x}", ch as u32).unwrap(),
+            ch => result.push(ch),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+// Computes the position of a byte offset the same way the lexer does.
+pub fn position_of(content: &str, offset: usize) -> Position {
+    let mut line = 1;
+    let mut column = 1;
+
+    for (idx, ch) in content.char_indices() {
+        if idx >= offset {
+            break;
+        }
+
+        match ch {
+            '\n' => {
+                line += 1;
+                column = 1;
+            }
+
+            '\t' => {
+                column = 1 + TAB_WIDTH * ((column - 1) / TAB_WIDTH + 1);
+            }
+
+            _ => column += 1,
+        }
+    }
+
+    Position::new(line, column)
+}
+
+fn expand_tabs(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for ch in line.chars() {
+        if ch == '\t' {
+            let width = TAB_WIDTH - column % TAB_WIDTH;
+            result.push_str(&" ".repeat(width as usize));
+            column += width;
+        } else {
+            result.push(ch);
+            column += 1;
+        }
+    }
+
+    result
+}
+
+// Without a span the identifier or number starting at `column` gets
+// underlined, otherwise only a single character.
+fn token_length(text: &str, column: u32) -> u32 {
+    let length = text
+        .chars()
+        .skip(column as usize - 1)
+        .take_while(|ch| ch.is_alphanumeric() || *ch == '_')
+        .count();
+
+    length.max(1) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_tabs, json_string, position_of, render, token_length};
+    use crate::driver::cmd::ErrorFormat;
+    use crate::language::test;
+    use dora_parser::lexer::position::Position;
+
+    fn render_first_error(code: &'static str, format: ErrorFormat) -> String {
+        test::check(code, |sa| {
+            let diag = sa.diag.lock();
+            render(sa, &diag.errors()[0], format)
+        })
+    }
+
+    #[test]
+    fn test_render_human() {
+        assert_eq!(
+            "error: cannot assign `Bool` to variable `x` of type `Int64`.\n \
+             --> <<code>>:1:25\n  \
+             |\n\
+             1 | fn f() { let x: Int64 = true; x; }\n  \
+             |                         ^^^^\n",
+            render_first_error("fn f() { let x: Int64 = true; x; }", ErrorFormat::Human)
+        );
+
+        assert_eq!(
+            "error: can not shadow function `f`.\n \
+             --> <<code>>:2:1\n  \
+             |\n\
+             2 | fn f() {}\n  \
+             | ^^\n  \
+             |\n\
+             1 | fn f() {}\n  \
+             | -- first defined here\n",
+            render_first_error("fn f() {}\nfn f() {}", ErrorFormat::Human)
+        );
+    }
+
+    #[test]
+    fn test_render_short() {
+        assert_eq!(
+            "error in \"<<code>>\" at 1:10: cannot assign `Bool` to variable `x` of type `Int64`.",
+            render_first_error("fn f() { let x: Int64 = true; x; }", ErrorFormat::Short)
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json = render_first_error("fn f() {}\nfn f() {}", ErrorFormat::Json);
+
+        assert!(json.starts_with(
+            "{\"severity\":\"error\",\"message\":\"can not shadow function `f`.\",\
+             \"file\":\"<<code>>\",\"line\":2,\"column\":1,\"end_line\":2,\"end_column\":3,\
+             \"labels\":[{\"message\":\"first defined here\",\"file\":\"<<code>>\",\
+             \"line\":1,\"column\":1,\"end_line\":1,\"end_column\":3}],\"notes\":[],\
+             \"rendered\":\"error: can not shadow function `f`.\\n"
+        ));
+        assert!(json.ends_with("\"}"));
+    }
+
+    #[test]
+    fn test_position_of() {
+        let content = "fn main() {\n\tlet x = 1;\n}";
+
+        assert_eq!(Position::new(1, 1), position_of(content, 0));
+        assert_eq!(Position::new(1, 4), position_of(content, 3));
+        assert_eq!(Position::new(2, 5), position_of(content, 13));
+        assert_eq!(Position::new(2, 9), position_of(content, 17));
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        assert_eq!("    let x", expand_tabs("\tlet x"));
+        assert_eq!("ab  c", expand_tabs("ab\tc"));
+    }
+
+    #[test]
+    fn test_token_length() {
+        assert_eq!(4, token_length("let name = 1;", 5));
+        assert_eq!(1, token_length("let name = 1;", 10));
+        assert_eq!(1, token_length("", 1));
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!("\"abc\"", json_string("abc"));
+        assert_eq!("\"a\\\"b\\\\c\\n\"", json_string("a\"b\\c\n"));
+        assert_eq!("\"\\u0001\"", json_string("\u{1}"));
+    }
+}
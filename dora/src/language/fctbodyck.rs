@@ -48,6 +48,12 @@ fn check_function(sa: &mut SemAnalysis, id: FctDefinitionId) {
             return;
         }
 
+        if fct.ast.has_errors {
+            // Parts of the body are missing, checking it would only report
+            // errors caused by the syntax errors.
+            return;
+        }
+
         let mut analysis = AnalysisData::new();
         let mut symtable = ModuleSymTable::new(sa, fct.module_id);
         let mut vars = VarManager::new();
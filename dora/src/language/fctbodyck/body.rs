@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::sync::Arc;
 use std::{f32, f64};
@@ -9,8 +9,11 @@ use crate::language::access::{
     method_accessible_from, module_accessible_from, struct_accessible_from,
     struct_field_accessible_from,
 };
+use crate::language::error::lint::Lint;
 use crate::language::error::msg::ErrorMessage;
+use crate::language::error::render::position_of;
 use crate::language::fctbodyck::lookup::MethodLookup;
+use crate::language::patterns::{find_missing, is_useful, pattern_name, Pattern};
 use crate::language::sem_analysis::{
     create_tuple, find_field_in_class, find_methods_in_class, find_methods_in_enum,
     find_methods_in_struct, implements_trait, AnalysisData, CallType, ClassDefinition,
@@ -24,6 +27,7 @@ use crate::language::sym::{ModuleSymTable, Sym};
 use crate::language::ty::{SourceType, SourceTypeArray};
 use crate::language::typeparamck::{self, ErrorReporting};
 use crate::language::{always_returns, expr_always_returns, read_type, AllowSelf};
+use crate::language::{expr_block_unreachable_code, report_lint};
 use crate::language::{report_sym_shadow, TypeParamContext};
 
 use dora_parser::ast;
@@ -31,7 +35,6 @@ use dora_parser::ast::visit::Visitor;
 use dora_parser::interner::Name;
 use dora_parser::lexer::position::Position;
 use dora_parser::lexer::token::{FloatSuffix, IntBase, IntSuffix};
-use fixedbitset::FixedBitSet;
 
 pub struct TypeCheck<'a> {
     pub sa: &'a mut SemAnalysis,
@@ -62,6 +65,8 @@ impl<'a> TypeCheck<'a> {
         let block = self.ast.block.as_ref().expect("missing block");
         let mut returns = false;
 
+        self.check_unreachable_code(block);
+
         for stmt in &block.stmts {
             self.visit_stmt(stmt);
 
@@ -88,9 +93,55 @@ impl<'a> TypeCheck<'a> {
         self.symtable.pop_level();
         assert_eq!(self.symtable.levels(), start_level);
 
+        self.check_unused_vars();
+
         self.prepare_local_and_context_vars();
     }
 
+    fn check_unreachable_code(&mut self, block: &ast::ExprBlockType) {
+        if let Some(span) = expr_block_unreachable_code(block) {
+            // report at the start of the statement, the position of a call
+            // is its opening parenthesis
+            let content = &self.sa.source_file(self.file_id).content;
+            let pos = position_of(content, span.start() as usize);
+
+            report_lint(
+                self.sa,
+                &self.fct.allowed_lints,
+                Lint::UnreachableCode,
+                self.file_id,
+                pos,
+                ErrorMessage::UnreachableCode,
+            );
+        }
+    }
+
+    fn check_unused_vars(&mut self) {
+        let start_idx = self.vars.current_function().start_idx;
+
+        for var in &self.vars.vars[start_idx..] {
+            let pos = match var.local_pos {
+                Some(pos) if !var.used => pos,
+                _ => continue,
+            };
+
+            let name = self.sa.interner.str(var.name).to_string();
+
+            if name.starts_with('_') {
+                continue;
+            }
+
+            report_lint(
+                self.sa,
+                &self.fct.allowed_lints,
+                Lint::UnusedVariable,
+                self.file_id,
+                pos,
+                ErrorMessage::UnusedVariable(name),
+            );
+        }
+    }
+
     fn prepare_local_and_context_vars(&mut self) {
         if self.needs_context() {
             self.setup_context_class();
@@ -261,8 +312,21 @@ impl<'a> TypeCheck<'a> {
 
     fn add_local(&mut self, id: NestedVarId, pos: Position) {
         let name = self.vars.get_var(id).name;
+        self.vars.vars[id.0].local_pos = Some(pos);
+
         match self.symtable.insert(name, Sym::Var(id)) {
-            Some(Sym::Var(_)) | None => {}
+            Some(Sym::Var(_)) => {
+                let name = self.sa.interner.str(name).to_string();
+                report_lint(
+                    self.sa,
+                    &self.fct.allowed_lints,
+                    Lint::ShadowedBinding,
+                    self.file_id,
+                    pos,
+                    ErrorMessage::ShadowedBinding(name),
+                );
+            }
+            None => {}
             Some(sym) => report_sym_shadow(self.sa, name, self.fct.file_id, pos, sym),
         }
     }
@@ -303,7 +367,7 @@ impl<'a> TypeCheck<'a> {
         // update type of variable, necessary when stmt has initializer expression but no type
         self.check_stmt_let_pattern(&s.pattern, defined_type.clone());
 
-        if s.expr.is_some() {
+        if let Some(ref expr) = s.expr {
             if !expr_type.is_error()
                 && !defined_type.is_error()
                 && !defined_type.allows(self.sa, expr_type.clone())
@@ -316,7 +380,10 @@ impl<'a> TypeCheck<'a> {
                 let defined_type = defined_type.name_fct(self.sa, self.fct);
                 let expr_type = expr_type.name_fct(self.sa, self.fct);
                 let msg = ErrorMessage::AssignType(name, defined_type, expr_type);
-                self.sa.diag.lock().report(self.file_id, s.pos, msg);
+                self.sa
+                    .diag
+                    .lock()
+                    .report_with_span(self.file_id, s.pos, expr.span(), msg);
             }
 
         // let variable binding needs to be assigned
@@ -540,17 +607,38 @@ impl<'a> TypeCheck<'a> {
     }
 
     fn check_stmt_while(&mut self, stmt: &ast::StmtWhileType) {
+        if let Some(ref pattern) = stmt.pattern {
+            self.symtable.push_level();
+            self.check_let_pattern_condition(pattern, &stmt.cond);
+            self.check_loop_body(&stmt.block);
+            self.symtable.pop_level();
+            return;
+        }
+
         let expr_type = self.check_expr(&stmt.cond, SourceType::Any);
 
         if !expr_type.is_error() && !expr_type.is_bool() {
             let expr_type = expr_type.name_fct(self.sa, self.fct);
             let msg = ErrorMessage::WhileCondType(expr_type);
-            self.sa.diag.lock().report(self.file_id, stmt.pos, msg);
+            self.sa
+                .diag
+                .lock()
+                .report_with_span(self.file_id, stmt.pos, stmt.cond.span(), msg);
         }
 
         self.check_loop_body(&stmt.block);
     }
 
+    // Checks the value and pattern of `if let` and `while let`, the bindings
+    // are added to the current level of the symbol table.
+    fn check_let_pattern_condition(&mut self, pattern: &ast::MatchPattern, cond: &ast::Expr) {
+        let expr_type = self.check_expr(cond, SourceType::Any);
+
+        let mut bindings = Vec::new();
+        self.check_match_pattern(pattern, expr_type, &mut bindings);
+        self.add_pattern_bindings(&bindings);
+    }
+
     fn check_stmt_return(&mut self, s: &ast::StmtReturnType) {
         let expected_ty = self.fct.return_type.clone();
 
@@ -582,6 +670,7 @@ impl<'a> TypeCheck<'a> {
         _expected_ty: SourceType,
     ) -> SourceType {
         self.symtable.push_level();
+        self.check_unreachable_code(block);
 
         for stmt in &block.stmts {
             self.visit_stmt(stmt);
@@ -640,141 +729,15 @@ impl<'a> TypeCheck<'a> {
     ) -> SourceType {
         let expr_type = self.check_expr(&node.expr, SourceType::Any);
         let mut result_type = SourceType::Error;
-
-        if !expr_type.is_enum() {
-            self.sa
-                .diag
-                .lock()
-                .report(self.file_id, node.pos, ErrorMessage::EnumExpected);
-        }
-
-        let expr_enum_id = expr_type.enum_id();
-        let expr_type_params = expr_type.type_params();
-
-        let enum_variants = if let Some(expr_enum_id) = expr_enum_id {
-            let enum_ = self.sa.enums[expr_enum_id].read();
-            enum_.variants.len()
-        } else {
-            0
-        };
-
-        let mut used_variants = FixedBitSet::with_capacity(enum_variants);
+        let mut patterns_valid = !expr_type.is_error();
 
         for case in &node.cases {
             self.symtable.push_level();
 
-            debug_assert_eq!(case.patterns.len(), 1);
-            let pattern = case.patterns.first().expect("no pattern");
-
-            match pattern.data {
-                ast::MatchPatternData::Underscore => {
-                    let mut negated_used_variants = used_variants.clone();
-                    negated_used_variants.toggle_range(..);
-
-                    if negated_used_variants.count_ones(..) == 0 {
-                        let msg = ErrorMessage::MatchUnreachablePattern;
-                        self.sa.diag.lock().report(self.file_id, case.pos, msg);
-                    }
-
-                    used_variants.insert_range(..);
-                }
-
-                ast::MatchPatternData::Ident(ref ident) => {
-                    let sym = self.read_path(&ident.path);
-
-                    let mut used_idents: HashSet<Name> = HashSet::new();
-
-                    match sym {
-                        Ok(Sym::EnumVariant(enum_id, variant_idx)) => {
-                            if Some(enum_id) == expr_enum_id {
-                                if used_variants.contains(variant_idx) {
-                                    let msg = ErrorMessage::MatchUnreachablePattern;
-                                    self.sa.diag.lock().report(self.file_id, case.pos, msg);
-                                }
-
-                                used_variants.insert(variant_idx);
-                                self.analysis.map_idents.insert(
-                                    pattern.id,
-                                    IdentType::EnumValue(
-                                        enum_id,
-                                        expr_type_params.clone(),
-                                        variant_idx,
-                                    ),
-                                );
-
-                                let enum_ = self.sa.enums.idx(enum_id);
-                                let enum_ = enum_.read();
-                                let variant = &enum_.variants[variant_idx];
-
-                                let given_params = if let Some(ref params) = ident.params {
-                                    params.len()
-                                } else {
-                                    0
-                                };
-
-                                if given_params == 0 && ident.params.is_some() {
-                                    let msg = ErrorMessage::MatchPatternNoParens;
-                                    self.sa.diag.lock().report(self.file_id, case.pos, msg);
-                                }
-
-                                let expected_params = variant.types.len();
-
-                                if given_params != expected_params {
-                                    let msg = ErrorMessage::MatchPatternWrongNumberOfParams(
-                                        given_params,
-                                        expected_params,
-                                    );
-                                    self.sa.diag.lock().report(self.file_id, case.pos, msg);
-                                }
-
-                                if let Some(ref params) = ident.params {
-                                    for (idx, param) in params.iter().enumerate() {
-                                        if let Some(name) = param.name {
-                                            let ty = if idx < variant.types.len() {
-                                                variant.types[idx].clone()
-                                            } else {
-                                                SourceType::Error
-                                            };
-
-                                            let ty = replace_type_param(
-                                                self.sa,
-                                                ty,
-                                                &expr_type_params,
-                                                None,
-                                            );
-
-                                            if used_idents.insert(name) == false {
-                                                let msg = ErrorMessage::VarAlreadyInPattern;
-                                                self.sa.diag.lock().report(
-                                                    self.file_id,
-                                                    param.pos,
-                                                    msg,
-                                                );
-                                            }
-
-                                            let var_id = self.vars.add_var(name, ty, param.mutable);
-                                            self.add_local(var_id, param.pos);
-                                            self.analysis
-                                                .map_vars
-                                                .insert(param.id, self.vars.local_var_id(var_id));
-                                        }
-                                    }
-                                }
-                            } else {
-                                let msg = ErrorMessage::EnumVariantExpected;
-                                self.sa.diag.lock().report(self.file_id, node.pos, msg);
-                            }
-                        }
-
-                        Ok(_) => {
-                            let msg = ErrorMessage::EnumVariantExpected;
-                            self.sa.diag.lock().report(self.file_id, node.pos, msg);
-                        }
-
-                        Err(()) => {}
-                    }
-                }
-            }
+            let mut bindings = Vec::new();
+            patterns_valid &=
+                self.check_match_alternatives(&case.patterns, expr_type.clone(), &mut bindings);
+            self.add_pattern_bindings(&bindings);
 
             let case_ty = self.check_expr(&case.value, expected_ty.clone());
 
@@ -796,11 +759,10 @@ impl<'a> TypeCheck<'a> {
             self.symtable.pop_level();
         }
 
-        used_variants.toggle_range(..);
-
-        if used_variants.count_ones(..) != 0 {
-            let msg = ErrorMessage::MatchUncoveredVariant;
-            self.sa.diag.lock().report(self.file_id, node.pos, msg);
+        // Exhaustiveness is only checked for well-typed patterns to avoid
+        // follow-up errors.
+        if patterns_valid {
+            self.check_match_arms(node, expr_type);
         }
 
         self.analysis.set_ty(node.id, result_type.clone());
@@ -808,16 +770,382 @@ impl<'a> TypeCheck<'a> {
         result_type
     }
 
-    fn check_expr_if(&mut self, expr: &ast::ExprIfType, expected_ty: SourceType) -> SourceType {
-        let expr_type = self.check_expr(&expr.cond, SourceType::Any);
+    fn check_match_arms(&mut self, node: &ast::ExprMatchType, expr_type: SourceType) {
+        let types = [expr_type.clone()];
+        let mut rows = Vec::new();
+
+        for case in &node.cases {
+            let mut unreachable = Vec::new();
+
+            for pattern in &case.patterns {
+                let pattern_pos = pattern.pos;
+                let pattern = Pattern::lower(self.analysis, pattern);
+
+                if !is_useful(self.sa, &rows, std::slice::from_ref(&pattern), &types) {
+                    unreachable.push(pattern_pos);
+                }
 
-        if !expr_type.is_bool() && !expr_type.is_error() {
+                rows.push(vec![pattern]);
+            }
+
+            if unreachable.len() == case.patterns.len() {
+                let msg = ErrorMessage::MatchUnreachablePattern;
+                self.sa.diag.lock().report(self.file_id, case.pos, msg);
+            } else {
+                for pos in unreachable {
+                    let msg = ErrorMessage::MatchUnreachablePattern;
+                    self.sa.diag.lock().report(self.file_id, pos, msg);
+                }
+            }
+        }
+
+        if let Some(missing) = find_missing(self.sa, &rows, &types) {
+            let pattern = pattern_name(self.sa, &missing[0], &expr_type);
+            let msg = ErrorMessage::MatchUncoveredPattern(pattern);
+            self.sa.diag.lock().report(self.file_id, node.pos, msg);
+        }
+    }
+
+    fn check_match_alternatives(
+        &mut self,
+        patterns: &[ast::MatchPattern],
+        ty: SourceType,
+        bindings: &mut Vec<PatternBinding>,
+    ) -> bool {
+        if patterns.len() == 1 {
+            return self.check_match_pattern(&patterns[0], ty, bindings);
+        }
+
+        // Every alternative needs to bind the same variables with the same
+        // types, they all refer to the same variable in the match arm.
+        let mut valid = true;
+        let mut first_bindings: Option<Vec<PatternBinding>> = None;
+        let outer_bindings = bindings.len();
+
+        for pattern in patterns {
+            let mut alternative_bindings = Vec::new();
+            valid &= self.check_match_pattern(pattern, ty.clone(), &mut alternative_bindings);
+
+            if let Some(ref first_bindings) = first_bindings {
+                for binding in &alternative_bindings {
+                    if !first_bindings
+                        .iter()
+                        .any(|first| first.name == binding.name && first.ty == binding.ty)
+                    {
+                        let name = self.sa.interner.str(binding.name).to_string();
+                        let msg = ErrorMessage::PatternAlternativesBindingMismatch(name);
+                        self.sa.diag.lock().report(self.file_id, binding.pos, msg);
+                        valid = false;
+                    }
+                }
+
+                for first in first_bindings {
+                    if !alternative_bindings
+                        .iter()
+                        .any(|binding| binding.name == first.name)
+                    {
+                        let name = self.sa.interner.str(first.name).to_string();
+                        let msg = ErrorMessage::PatternAlternativesBindingMismatch(name);
+                        self.sa.diag.lock().report(self.file_id, pattern.pos, msg);
+                        valid = false;
+                    }
+                }
+            } else {
+                first_bindings = Some(alternative_bindings.clone());
+            }
+
+            for binding in alternative_bindings {
+                if bindings[..outer_bindings]
+                    .iter()
+                    .any(|outer| outer.name == binding.name)
+                {
+                    let msg = ErrorMessage::VarAlreadyInPattern;
+                    self.sa.diag.lock().report(self.file_id, binding.pos, msg);
+                    valid = false;
+                }
+
+                bindings.push(binding);
+            }
+        }
+
+        valid
+    }
+
+    fn check_match_pattern(
+        &mut self,
+        pattern: &ast::MatchPattern,
+        ty: SourceType,
+        bindings: &mut Vec<PatternBinding>,
+    ) -> bool {
+        match pattern.data {
+            ast::MatchPatternData::Underscore => true,
+
+            ast::MatchPatternData::Lit(ref expr) => {
+                let lit_ty = self.check_expr(expr, ty.clone());
+
+                if ty.is_error() || lit_ty.is_error() {
+                    false
+                } else if lit_ty != ty {
+                    let ty = ty.name_fct(self.sa, self.fct);
+                    let msg = ErrorMessage::PatternTypeMismatch(ty);
+                    self.sa.diag.lock().report(self.file_id, pattern.pos, msg);
+                    false
+                } else {
+                    true
+                }
+            }
+
+            ast::MatchPatternData::Tuple(ref parts) => {
+                let subtypes = match ty {
+                    SourceType::Tuple(ref subtypes) => subtypes.types().to_vec(),
+                    SourceType::Unit => Vec::new(),
+                    _ => Vec::new(),
+                };
+
+                if (ty.is_tuple() || ty.is_unit()) && subtypes.len() == parts.len() {
+                    let mut valid = true;
+
+                    for (part, subtype) in parts.iter().zip(subtypes) {
+                        valid &= self.check_match_pattern(part, subtype, bindings);
+                    }
+
+                    valid
+                } else {
+                    if !ty.is_error() {
+                        let ty = ty.name_fct(self.sa, self.fct);
+                        let msg = ErrorMessage::PatternTypeMismatch(ty);
+                        self.sa.diag.lock().report(self.file_id, pattern.pos, msg);
+                    }
+
+                    self.check_match_patterns_invalid(parts, bindings);
+                    false
+                }
+            }
+
+            ast::MatchPatternData::Alt(ref alternatives) => {
+                self.check_match_alternatives(alternatives, ty, bindings)
+            }
+
+            ast::MatchPatternData::Ident(ref ident) => {
+                self.check_match_pattern_ident(pattern, ident, ty, bindings)
+            }
+        }
+    }
+
+    fn check_match_pattern_ident(
+        &mut self,
+        pattern: &ast::MatchPattern,
+        ident: &ast::MatchPatternIdent,
+        ty: SourceType,
+        bindings: &mut Vec<PatternBinding>,
+    ) -> bool {
+        let params = ident.params.as_deref().unwrap_or(&[]);
+
+        // A single name is a binding unless it refers to an enum variant.
+        let sym = if ident.path.names.len() == 1 && ident.params.is_none() {
+            match self.symtable.get(ident.path.names[0]) {
+                Some(Sym::EnumVariant(enum_id, variant_idx)) if !ident.mutable => {
+                    Ok(Sym::EnumVariant(enum_id, variant_idx))
+                }
+
+                _ => {
+                    let name = ident.path.names[0];
+
+                    if bindings.iter().any(|binding| binding.name == name) {
+                        let msg = ErrorMessage::VarAlreadyInPattern;
+                        self.sa.diag.lock().report(self.file_id, pattern.pos, msg);
+                    }
+
+                    bindings.push(PatternBinding {
+                        id: pattern.id,
+                        pos: pattern.pos,
+                        name,
+                        ty: ty.clone(),
+                        mutable: ident.mutable,
+                    });
+
+                    return !ty.is_error();
+                }
+            }
+        } else {
+            self.read_path(&ident.path)
+        };
+
+        let (enum_id, variant_idx) = match sym {
+            Ok(Sym::EnumVariant(enum_id, variant_idx)) => (enum_id, variant_idx),
+
+            Ok(_) => {
+                let msg = ErrorMessage::EnumVariantExpected;
+                self.sa.diag.lock().report(self.file_id, pattern.pos, msg);
+                self.check_match_patterns_invalid(params, bindings);
+                return false;
+            }
+
+            Err(()) => {
+                self.check_match_patterns_invalid(params, bindings);
+                return false;
+            }
+        };
+
+        if !ty.is_enum_id(enum_id) {
+            if !ty.is_error() {
+                let ty = ty.name_fct(self.sa, self.fct);
+                let msg = ErrorMessage::PatternTypeMismatch(ty);
+                self.sa.diag.lock().report(self.file_id, pattern.pos, msg);
+            }
+
+            self.check_match_patterns_invalid(params, bindings);
+            return false;
+        }
+
+        let type_params = ty.type_params();
+
+        self.analysis.map_idents.insert(
+            pattern.id,
+            IdentType::EnumValue(enum_id, type_params.clone(), variant_idx),
+        );
+
+        let variant_types = {
+            let enum_ = self.sa.enums[enum_id].read();
+            enum_.variants[variant_idx].types.clone()
+        };
+
+        let mut valid = true;
+
+        if params.is_empty() && ident.params.is_some() {
+            let msg = ErrorMessage::MatchPatternNoParens;
+            self.sa.diag.lock().report(self.file_id, pattern.pos, msg);
+            valid = false;
+        }
+
+        if params.len() != variant_types.len() {
+            let msg =
+                ErrorMessage::MatchPatternWrongNumberOfParams(params.len(), variant_types.len());
+            self.sa.diag.lock().report(self.file_id, pattern.pos, msg);
+            valid = false;
+        }
+
+        for (idx, param) in params.iter().enumerate() {
+            let ty = if idx < variant_types.len() {
+                replace_type_param(self.sa, variant_types[idx].clone(), &type_params, None)
+            } else {
+                SourceType::Error
+            };
+
+            valid &= self.check_match_pattern(param, ty, bindings);
+        }
+
+        valid
+    }
+
+    // Still declares the bindings in patterns that can't match, otherwise
+    // the arm would report unknown identifiers.
+    fn check_match_patterns_invalid(
+        &mut self,
+        patterns: &[ast::MatchPattern],
+        bindings: &mut Vec<PatternBinding>,
+    ) {
+        for pattern in patterns {
+            self.check_match_pattern(pattern, SourceType::Error, bindings);
+        }
+    }
+
+    fn add_pattern_bindings(&mut self, bindings: &[PatternBinding]) {
+        let mut vars: HashMap<Name, NestedVarId> = HashMap::new();
+
+        for binding in bindings {
+            let var_id = match vars.get(&binding.name) {
+                Some(&var_id) => var_id,
+                None => {
+                    let var_id =
+                        self.vars
+                            .add_var(binding.name, binding.ty.clone(), binding.mutable);
+                    self.add_local(var_id, binding.pos);
+                    vars.insert(binding.name, var_id);
+                    var_id
+                }
+            };
+
+            self.analysis
+                .map_vars
+                .insert(binding.id, self.vars.local_var_id(var_id));
+        }
+    }
+
+    fn check_expr_try(&mut self, node: &ast::ExprTryType, _expected_ty: SourceType) -> SourceType {
+        let expr_type = self.check_expr(&node.expr, SourceType::Any);
+
+        if expr_type.is_error() {
+            self.analysis.set_ty(node.id, SourceType::Error);
+            return SourceType::Error;
+        }
+
+        let option_id = self.sa.known.enums.option();
+        let result_id = self.sa.known.enums.result();
+
+        let (enum_id, type_params) = match expr_type {
+            SourceType::Enum(enum_id, ref type_params)
+                if enum_id == option_id || enum_id == result_id =>
+            {
+                (enum_id, type_params.clone())
+            }
+
+            _ => {
+                let expr_type = expr_type.name_fct(self.sa, self.fct);
+                let msg = ErrorMessage::TryExpectsOptionOrResult(expr_type);
+                self.sa.diag.lock().report(self.file_id, node.pos, msg);
+
+                self.analysis.set_ty(node.id, SourceType::Error);
+                return SourceType::Error;
+            }
+        };
+
+        // `None` can be returned as any `Option`, while `Err` needs to keep
+        // the exact error type.
+        let compatible = match self.fct.return_type {
+            SourceType::Enum(fct_enum_id, ref fct_type_params) if fct_enum_id == enum_id => {
+                enum_id == option_id || fct_type_params[1] == type_params[1]
+            }
+
+            SourceType::Error => true,
+            _ => false,
+        };
+
+        if !compatible {
             let expr_type = expr_type.name_fct(self.sa, self.fct);
-            let msg = ErrorMessage::IfCondType(expr_type);
-            self.sa.diag.lock().report(self.file_id, expr.pos, msg);
+            let fct_type = self.fct.return_type.name_fct(self.sa, self.fct);
+            let msg = ErrorMessage::TryIncompatibleReturnType(expr_type, fct_type);
+            self.sa.diag.lock().report(self.file_id, node.pos, msg);
         }
 
-        let then_type = self.check_expr(&expr.then_block, expected_ty.clone());
+        let value_type = type_params[0].clone();
+        self.analysis.set_ty(node.id, value_type.clone());
+
+        value_type
+    }
+
+    fn check_expr_if(&mut self, expr: &ast::ExprIfType, expected_ty: SourceType) -> SourceType {
+        let then_type = if let Some(ref pattern) = expr.pattern {
+            self.symtable.push_level();
+            self.check_let_pattern_condition(pattern, &expr.cond);
+            let then_type = self.check_expr(&expr.then_block, expected_ty.clone());
+            self.symtable.pop_level();
+
+            then_type
+        } else {
+            let expr_type = self.check_expr(&expr.cond, SourceType::Any);
+
+            if !expr_type.is_bool() && !expr_type.is_error() {
+                let expr_type = expr_type.name_fct(self.sa, self.fct);
+                let msg = ErrorMessage::IfCondType(expr_type);
+                self.sa
+                    .diag
+                    .lock()
+                    .report_with_span(self.file_id, expr.pos, expr.cond.span(), msg);
+            }
+
+            self.check_expr(&expr.then_block, expected_ty.clone())
+        };
 
         let merged_type = if let Some(ref else_block) = expr.else_block {
             let else_type = self.check_expr(else_block, expected_ty);
@@ -855,6 +1183,7 @@ impl<'a> TypeCheck<'a> {
             Some(Sym::Var(var_id)) => {
                 let ty = self.vars.get_var(var_id).ty.clone();
                 self.analysis.set_ty(e.id, ty.clone());
+                self.vars.vars[var_id.0].used = true;
 
                 // Variable may have to be context-allocated.
                 let ident = self
@@ -1013,7 +1342,10 @@ impl<'a> TypeCheck<'a> {
             self.analysis.set_ty(e.id, SourceType::Unit);
 
             let msg = ErrorMessage::AssignType(name, lhs_type, rhs_type);
-            self.sa.diag.lock().report(self.file_id, e.pos, msg);
+            self.sa
+                .diag
+                .lock()
+                .report_with_span(self.file_id, e.pos, e.rhs.span(), msg);
         }
     }
 
@@ -3064,6 +3396,7 @@ impl<'a> TypeCheck<'a> {
         lambda.param_types = params_with_ctxt;
         lambda.return_type = ret;
         lambda.type_params = self.fct.type_params.clone();
+        lambda.allowed_lints = self.fct.allowed_lints.clone();
         let lambda_fct_id = self.sa.add_fct(lambda);
         self.analysis.map_lambdas.insert(node.id, lambda_fct_id);
 
@@ -3222,18 +3555,14 @@ impl<'a> TypeCheck<'a> {
                     continue;
                 }
 
-                let implements_stringable = if let SourceType::TypeParam(id) = part_expr {
-                    self.fct
-                        .type_params
-                        .implements_trait(id, stringable_trait_ty.clone())
-                } else {
-                    implements_trait(
-                        self.sa,
-                        part_expr.clone(),
-                        &self.fct.type_params,
-                        stringable_trait_ty.clone(),
-                    )
-                };
+                if let Some(ref spec) = e.specs[idx] {
+                    if self.check_template_format_spec(part, part_expr.clone(), spec) {
+                        continue;
+                    }
+                }
+
+                let implements_stringable =
+                    self.type_implements_trait(part_expr.clone(), stringable_trait_ty.clone());
 
                 if implements_stringable {
                     continue;
@@ -3256,6 +3585,63 @@ impl<'a> TypeCheck<'a> {
         str_ty
     }
 
+    // Returns false if the part should be checked like one without format
+    // spec, which reports the missing Stringable implementation.
+    fn check_template_format_spec(
+        &mut self,
+        part: &ast::Expr,
+        ty: SourceType,
+        spec: &ast::FormatSpec,
+    ) -> bool {
+        let formattable_trait_ty = SourceType::new_trait(self.sa.known.traits.formattable());
+
+        if self.type_implements_trait(ty.clone(), formattable_trait_ty) {
+            let is_integer = matches!(
+                ty,
+                SourceType::UInt8
+                    | SourceType::Int32
+                    | SourceType::Int64
+                    | SourceType::TypeParam(_)
+            );
+
+            if spec.kind != ast::FormatKind::Display && !is_integer {
+                let ty = ty.name_fct(self.sa, self.fct);
+                self.sa.diag.lock().report(
+                    self.file_id,
+                    spec.pos,
+                    ErrorMessage::FormatSpecNotForType(spec.value.clone(), ty),
+                );
+            }
+
+            return true;
+        }
+
+        let stringable_trait_ty = SourceType::new_trait(self.sa.known.traits.stringable());
+
+        if !self.type_implements_trait(ty.clone(), stringable_trait_ty) {
+            return false;
+        }
+
+        if !spec.is_padding_only() {
+            let ty = ty.name_fct(self.sa, self.fct);
+            self.sa.diag.lock().report(
+                self.file_id,
+                part.pos(),
+                ErrorMessage::ExpectedFormattable(ty, spec.value.clone()),
+            );
+        }
+
+        true
+    }
+
+    fn type_implements_trait(&self, ty: SourceType, trait_ty: SourceType) -> bool {
+        if let SourceType::TypeParam(id) = ty {
+            self.fct.type_params.implements_trait(id, trait_ty)
+        } else {
+            implements_trait(self.sa, ty, &self.fct.type_params, trait_ty)
+        }
+    }
+
     fn check_expr(&mut self, e: &ast::Expr, expected_ty: SourceType) -> SourceType {
         match *e {
             ast::Expr::LitChar(ref expr) => self.check_expr_lit_char(expr, expected_ty),
@@ -3279,6 +3665,7 @@ impl<'a> TypeCheck<'a> {
             ast::Expr::Tuple(ref expr) => self.check_expr_tuple(expr, expected_ty),
             ast::Expr::Paren(ref expr) => self.check_expr_paren(expr, expected_ty),
             ast::Expr::Match(ref expr) => self.check_expr_match(expr, expected_ty),
+            ast::Expr::Try(ref expr) => self.check_expr_try(expr, expected_ty),
         }
     }
 
@@ -3586,6 +3973,15 @@ pub fn check_lit_float(
     (ty, value)
 }
 
+#[derive(Clone)]
+struct PatternBinding {
+    id: ast::NodeId,
+    pos: Position,
+    name: Name,
+    ty: SourceType,
+    mutable: bool,
+}
+
 struct MethodDescriptor {
     fct_id: FctDefinitionId,
     type_params: SourceTypeArray,
@@ -3735,6 +4131,8 @@ impl VarManager {
             ty,
             mutable,
             location: VarLocation::Stack,
+            local_pos: None,
+            used: false,
         };
 
         self.vars.push(var);
@@ -3777,4 +4175,7 @@ pub struct VarDefinition {
     pub ty: SourceType,
     pub mutable: bool,
     pub location: VarLocation,
+    // position of the declaration for let and pattern bindings
+    pub local_pos: Option<Position>,
+    pub used: bool,
 }
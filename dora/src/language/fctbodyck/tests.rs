@@ -1,5 +1,8 @@
+use crate::driver::cmd::Args;
+use crate::language::error::lint::Lint;
 use crate::language::error::msg::ErrorMessage;
 use crate::language::sem_analysis::ConstValue;
+use crate::language::test;
 use crate::language::tests::*;
 
 #[test]
@@ -1430,6 +1433,45 @@ fn test_template() {
     ok("fn f[T: std::Stringable](x: T): String { return \"${x}\"; }");
 }
 
+#[test]
+fn test_template_format_spec() {
+    ok("fn f(x: Int32, y: Float64, z: String): String { \"${x:08x}${y:>8.2}${z:.3}\" }");
+    ok("fn f[T: std::Formattable](x: T): String { \"${x:+08.2x}\" }");
+    ok("fn f[T: std::Stringable](x: T): String { \"${x:*^8}\" }");
+    ok("
+        class Foo
+        impl std::Stringable for Foo { fn toString(): String { \"foo\" } }
+        fn f(x: Foo): String { \"${x:>8}\" }
+    ");
+    err(
+        "
+            class Foo
+            impl std::Stringable for Foo { fn toString(): String { \"foo\" } }
+            fn f(x: Foo): String { \"${x:.3}\" }
+        ",
+        pos(4, 39),
+        ErrorMessage::ExpectedFormattable("Foo".into(), ".3".into()),
+    );
+    err(
+        "fn f[T: std::Stringable](x: T): String { \"${x:08}\" }",
+        pos(1, 45),
+        ErrorMessage::ExpectedFormattable("T".into(), "08".into()),
+    );
+    err(
+        "fn f(x: Float64): String { \"${x:x}\" }",
+        pos(1, 33),
+        ErrorMessage::FormatSpecNotForType("x".into(), "Float64".into()),
+    );
+    err(
+        "
+            class Foo
+            fn f(x: Foo): String { \"${x:>8}\" }
+        ",
+        pos(3, 39),
+        ErrorMessage::ExpectedStringable("Foo".into()),
+    );
+}
+
 #[test]
 fn test_trait_object_as_argument() {
     ok("trait Foo { fn bar(): Int32; }
@@ -1946,7 +1988,7 @@ fn test_enum_match_missing_variants() {
         }
     ",
         pos(4, 13),
-        ErrorMessage::MatchUncoveredVariant,
+        ErrorMessage::MatchUncoveredPattern("A::V3".into()),
     );
 
     err(
@@ -1993,6 +2035,220 @@ fn test_enum_match_underscore() {
     );
 }
 
+#[test]
+fn test_match_nested_patterns() {
+    ok("
+        fn f(x: Option[Result[Int32, String]]): Int32 {
+            match x {
+                Some(Ok(value)) => value,
+                Some(Err(_)) => 1i32,
+                None => 0i32,
+            }
+        }
+    ");
+
+    err(
+        "
+        fn f(x: Option[Result[Int32, String]]): Int32 {
+            match x {
+                Some(Ok(value)) => value,
+                None => 0i32,
+            }
+        }
+    ",
+        pos(3, 13),
+        ErrorMessage::MatchUncoveredPattern("Option::Some(Result::Err(_))".into()),
+    );
+}
+
+#[test]
+fn test_match_literal_patterns() {
+    ok("
+        fn f(x: Int32): String {
+            match x {
+                0i32 => \"zero\",
+                -1i32 => \"minus one\",
+                _ => \"other\",
+            }
+        }
+        fn g(x: String): Bool {
+            match x {
+                \"yes\" | \"y\" => true,
+                _ => false,
+            }
+        }
+        fn h(x: Bool): Int32 {
+            match x {
+                true => 1i32,
+                false => 0i32,
+            }
+        }
+    ");
+
+    err(
+        "
+        fn f(x: Char): Int32 {
+            match x {
+                'a' => 1i32,
+            }
+        }
+    ",
+        pos(3, 13),
+        ErrorMessage::MatchUncoveredPattern("_".into()),
+    );
+
+    err(
+        "
+        fn f(x: Int32): Int32 {
+            match x {
+                \"a\" => 1i32,
+                _ => 0i32,
+            }
+        }
+    ",
+        pos(4, 17),
+        ErrorMessage::PatternTypeMismatch("Int32".into()),
+    );
+}
+
+#[test]
+fn test_match_tuple_patterns() {
+    ok("
+        fn f(x: (Bool, Bool)): Int32 {
+            match x {
+                (true, true) => 3i32,
+                (true, false) => 2i32,
+                (false, value) => if value { 1i32 } else { 0i32 },
+            }
+        }
+    ");
+
+    err(
+        "
+        fn f(x: (Bool, Int32)): Int32 {
+            match x {
+                (false, _) => 0i32,
+                (true, 1i32) => 1i32,
+            }
+        }
+    ",
+        pos(3, 13),
+        ErrorMessage::MatchUncoveredPattern("(true, _)".into()),
+    );
+}
+
+#[test]
+fn test_match_alternatives() {
+    ok("
+        enum A { V1(Int32), V2(Int32), V3 }
+        fn f(x: A): Int32 {
+            match x {
+                A::V1(value) | A::V2(value) => value,
+                A::V3 => 0i32,
+            }
+        }
+    ");
+
+    err(
+        "
+        enum A { V1(Int32), V2(Bool), V3 }
+        fn f(x: A): Int32 {
+            match x {
+                A::V1(value) | A::V2(value) => 1i32,
+                A::V3 => 0i32,
+            }
+        }
+    ",
+        pos(5, 38),
+        ErrorMessage::PatternAlternativesBindingMismatch("value".into()),
+    );
+
+    err(
+        "
+        enum A { V1, V2, V3 }
+        fn f(x: A): Int32 {
+            match x {
+                A::V1 | A::V2 => 1i32,
+                A::V2 | A::V3 => 0i32,
+            }
+        }
+    ",
+        pos(6, 17),
+        ErrorMessage::MatchUnreachablePattern,
+    );
+}
+
+#[test]
+fn test_if_let() {
+    ok("
+        fn f(x: Option[Int32]): Int32 {
+            if let Some(value) = x { value } else { 0i32 }
+        }
+    ");
+
+    ok("
+        fn f(x: Option[(Int32, Bool)]) {
+            if let Some((_, true)) | None = x {}
+        }
+    ");
+
+    err(
+        "
+        fn f(x: Option[Int32]): Int32 {
+            if let Some(value) = x { value } else { value }
+        }
+    ",
+        pos(3, 53),
+        ErrorMessage::UnknownIdentifier("value".into()),
+    );
+
+    err(
+        "
+        fn f(x: Option[Int32]): Int32 {
+            if let Some(value) = x { value } else { \"a\" }
+        }
+    ",
+        pos(3, 13),
+        ErrorMessage::IfBranchTypesIncompatible("Int32".into(), "String".into()),
+    );
+
+    err(
+        "
+        fn f(x: Int32) {
+            if let Some(value) = x {}
+        }
+    ",
+        pos(3, 20),
+        ErrorMessage::PatternTypeMismatch("Int32".into()),
+    );
+}
+
+#[test]
+fn test_while_let() {
+    ok("
+        fn f(x: Array[Int32]): Int32 {
+            let it = x.makeIterator();
+            let mut sum = 0i32;
+            while let Some(value) = it.next() {
+                if value == 0i32 { break; }
+                sum = sum + value;
+            }
+            sum
+        }
+    ");
+
+    err(
+        "
+        fn f(x: Option[Int32]): Int32 {
+            while let Some(value) = x { break; }
+            value
+        }
+    ",
+        pos(4, 13),
+        ErrorMessage::UnknownIdentifier("value".into()),
+    );
+}
+
 #[test]
 fn test_enum_equals() {
     ok("
@@ -3727,3 +3983,167 @@ fn self_unavailable_in_lambda() {
         ErrorMessage::ThisUnavailable,
     );
 }
+
+#[test]
+fn try_operator() {
+    ok("fn f(x: Option[Int32]): Option[Int64] { Some[Int64](x?.toInt64()) }");
+    ok("fn f(x: Result[Int32, String]): Result[(), String] { x?; Ok[(), String](()) }");
+    ok("fn f[T](x: Result[T, Int32]): Result[T, Int32] { Ok[T, Int32](x?) }");
+
+    err(
+        "fn f(x: Int32): Option[Int32] { x?; None[Int32] }",
+        pos(1, 34),
+        ErrorMessage::TryExpectsOptionOrResult("Int32".into()),
+    );
+
+    err(
+        "fn f(x: Option[Int32]): Int32 { x? }",
+        pos(1, 34),
+        ErrorMessage::TryIncompatibleReturnType("Option[Int32]".into(), "Int32".into()),
+    );
+
+    err(
+        "fn f(x: Option[Int32]): Result[Int32, ()] { Ok[Int32, ()](x?) }",
+        pos(1, 60),
+        ErrorMessage::TryIncompatibleReturnType("Option[Int32]".into(), "Result[Int32, ()]".into()),
+    );
+
+    err(
+        "fn f(x: Result[Int32, Int32]): Result[Int32, String] { Ok[Int32, String](x?) }",
+        pos(1, 75),
+        ErrorMessage::TryIncompatibleReturnType(
+            "Result[Int32, Int32]".into(),
+            "Result[Int32, String]".into(),
+        ),
+    );
+}
+
+#[test]
+fn test_serial_annotation() {
+    ok("@Test fn f() {}");
+    ok("@serial @Test fn f() {}");
+    ok("@Test @serial fn f() {}");
+    err(
+        "@serial fn f() {}",
+        pos(1, 9),
+        ErrorMessage::InvalidSerialAnnotationUsage,
+    );
+}
+
+#[test]
+fn test_unused_variable() {
+    ok_without_warnings("fn main() { let x = 1; x; }");
+    ok_without_warnings("fn main() { let _x = 1; }");
+    ok_without_warnings("fn main() { let mut x = 1; x = 2; x; }");
+    ok_without_warnings("@allow(unusedVariable) fn main() { let x = 1; }");
+    ok_without_warnings("fn main() { let x = 1; let f = || { x; }; f(); }");
+    warn(
+        "fn main() { let x = 1; }",
+        pos(1, 17),
+        ErrorMessage::UnusedVariable("x".into()),
+    );
+    warn(
+        "fn main() { for x in std::range(0i32, 2i32) {} }",
+        pos(1, 17),
+        ErrorMessage::UnusedVariable("x".into()),
+    );
+}
+
+#[test]
+fn test_unreachable_code() {
+    ok_without_warnings("fn main() { if true { return; } 1; }");
+    ok_without_warnings("@allow(unreachableCode) fn main() { return; 1; }");
+    warn(
+        "fn main() { return; 1; }",
+        pos(1, 21),
+        ErrorMessage::UnreachableCode,
+    );
+    warn(
+        "fn main() {\n    return;\n    foo();\n}\nfn foo() {}",
+        pos(3, 5),
+        ErrorMessage::UnreachableCode,
+    );
+    warn(
+        "fn main() { return; foo().size() }\nfn foo(): Array[Int64] { Array[Int64]::new() }",
+        pos(1, 21),
+        ErrorMessage::UnreachableCode,
+    );
+    warn(
+        "@pub fn f(): Int64 { return 1; 2 }",
+        pos(1, 32),
+        ErrorMessage::UnreachableCode,
+    );
+}
+
+#[test]
+fn test_shadowed_binding() {
+    ok_without_warnings("fn main() { let x = 1; let x = x + 1; x; }");
+
+    let mut args: Args = Default::default();
+    args.flag_warn = vec![Lint::ShadowedBinding];
+
+    test::check_with_args("fn main() { let x = 1; let x = x + 1; x; }", args, |sa| {
+        let diag = sa.diag.lock();
+        let warnings = diag.warnings();
+
+        assert_eq!(1, warnings.len());
+        assert_eq!(Some(pos(1, 28)), warnings[0].pos);
+        assert_eq!(ErrorMessage::ShadowedBinding("x".into()), warnings[0].msg);
+    });
+}
+
+#[test]
+fn test_deny_lint() {
+    let mut args: Args = Default::default();
+    args.flag_deny = vec![Lint::UnusedVariable];
+
+    test::check_with_args("fn main() { let x = 1; }", args, |sa| {
+        let diag = sa.diag.lock();
+
+        assert!(!diag.has_warnings());
+        assert_eq!(1, diag.errors().len());
+        assert_eq!(
+            ErrorMessage::UnusedVariable("x".into()),
+            diag.errors()[0].msg
+        );
+    });
+}
+
+#[test]
+fn test_check_after_syntax_errors() {
+    errors(
+        "fn f() { let x = ; }\nfn g() { let y: Int64 = true; y; }\nfn h( {}",
+        &[
+            (
+                pos(1, 18),
+                ErrorMessage::Custom("factor expected but got ;.".into()),
+            ),
+            (
+                pos(3, 7),
+                ErrorMessage::Custom("identifier expected but got {.".into()),
+            ),
+            (
+                pos(2, 10),
+                ErrorMessage::AssignType("y".into(), "Int64".into(), "Bool".into()),
+            ),
+        ],
+    );
+}
+
+#[test]
+fn test_call_function_with_syntax_error() {
+    errors(
+        "fn main() { f(1); }\nfn f(x: Int64) { let y = ; x + y; }",
+        &[(
+            pos(2, 26),
+            ErrorMessage::Custom("factor expected but got ;.".into()),
+        )],
+    );
+    errors(
+        "class Foo\nimpl Foo { fn g(): Int64 { 1 + ; } }\nfn main() { Foo().g(); }",
+        &[(
+            pos(2, 32),
+            ErrorMessage::Custom("factor expected but got ;.".into()),
+        )],
+    );
+}
@@ -157,6 +157,7 @@ pub fn check(sa: &SemAnalysis) {
         }
 
         fct.initialized = true;
+        fct.allowed_lints = language::read_allowed_lints(sa, fct.file_id, fct.pos, &ast.allow);
 
         check_test(sa, &*fct);
 
@@ -180,6 +181,11 @@ fn check_test(sa: &SemAnalysis, fct: &FctDefinition) {
     debug_assert!(fct.initialized);
 
     if !fct.is_test {
+        if fct.is_serial {
+            let msg = ErrorMessage::InvalidSerialAnnotationUsage;
+            sa.diag.lock().report(fct.file_id, fct.pos, msg);
+        }
+
         return;
     }
 
@@ -7,10 +7,11 @@ use dora_parser::ast;
 use crate::bytecode::{
     BytecodeBuilder, BytecodeFunction, BytecodeType, ConstPoolIdx, Label, Register,
 };
+use crate::language::patterns::{constructor_subtypes, Constructor, Pattern};
 use crate::language::sem_analysis::{
     find_impl, AnalysisData, CallType, ClassDefinitionId, ConstDefinitionId, ContextIdx,
     EnumDefinitionId, FctDefinition, FctDefinitionId, FieldId, GlobalDefinitionId, IdentType,
-    Intrinsic, SemAnalysis, StructDefinitionId, TypeParamId, VarId,
+    Intrinsic, SemAnalysis, StructDefinitionId, TraitDefinitionId, TypeParamId, VarId,
 };
 use crate::language::specialize::specialize_type;
 use crate::language::ty::{SourceType, SourceTypeArray};
@@ -569,6 +570,11 @@ impl<'a> AstBytecodeGen<'a> {
     }
 
     fn visit_stmt_while(&mut self, stmt: &ast::StmtWhileType) {
+        if let Some(ref pattern) = stmt.pattern {
+            self.visit_stmt_while_let(stmt, pattern);
+            return;
+        }
+
         let cond_lbl = self.builder.define_label();
         let end_lbl = self.builder.create_label();
         self.builder.emit_loop_start();
@@ -582,6 +588,60 @@ impl<'a> AstBytecodeGen<'a> {
         self.builder.bind_label(end_lbl);
     }
 
+    fn visit_stmt_while_let(&mut self, stmt: &ast::StmtWhileType, pattern: &ast::MatchPattern) {
+        let cond_lbl = self.builder.define_label();
+        let body_lbl = self.builder.create_label();
+        let end_lbl = self.builder.create_label();
+        self.builder.emit_loop_start();
+        self.push_scope();
+        let cond_reg = self.visit_expr(&stmt.cond, DataDest::Alloc);
+        let cond_ty = self.ty(stmt.cond.id());
+        self.emit_let_pattern_condition(pattern, cond_reg, cond_ty, body_lbl, end_lbl, stmt.pos);
+        self.builder.bind_label(body_lbl);
+        self.free_if_temp(cond_reg);
+        self.loops.push(LoopLabels::new(cond_lbl, end_lbl));
+        self.visit_stmt(&stmt.block);
+        self.loops.pop().unwrap();
+        self.builder.emit_jump_loop(cond_lbl);
+        self.pop_scope();
+        self.builder.bind_label(end_lbl);
+    }
+
+    // Jumps to `match_lbl` with the pattern's variables assigned if the value
+    // in `value_reg` matches the pattern, otherwise to `fail_lbl`.
+    fn emit_let_pattern_condition(
+        &mut self,
+        pattern: &ast::MatchPattern,
+        value_reg: Register,
+        value_ty: SourceType,
+        match_lbl: Label,
+        fail_lbl: Label,
+        pos: Position,
+    ) {
+        let pattern = Pattern::lower(self.analysis, pattern);
+        self.alloc_pattern_vars(&pattern);
+
+        let rows = vec![
+            MatchRow {
+                patterns: vec![pattern],
+                arm: 0,
+                bindings: Vec::new(),
+            },
+            MatchRow {
+                patterns: vec![Pattern::Wildcard],
+                arm: 1,
+                bindings: Vec::new(),
+            },
+        ];
+
+        self.emit_decision_tree(
+            rows,
+            vec![(value_reg, value_ty)],
+            &[match_lbl, fail_lbl],
+            pos,
+        );
+    }
+
     fn visit_stmt_expr(&mut self, stmt: &ast::StmtExprType) {
         let reg = self.visit_expr(&stmt.expr, DataDest::Effect);
         self.free_if_temp(reg);
@@ -642,6 +702,7 @@ impl<'a> AstBytecodeGen<'a> {
             ast::Expr::Tuple(ref tuple) => self.visit_expr_tuple(tuple, dest),
             ast::Expr::Paren(ref paren) => self.visit_expr(&paren.expr, dest),
             ast::Expr::Match(ref expr) => self.visit_expr_match(expr, dest),
+            ast::Expr::Try(ref expr) => self.visit_expr_try(expr, dest),
             ast::Expr::Lambda(ref node) => self.visit_expr_lambda(node, dest),
         }
     }
@@ -674,65 +735,28 @@ impl<'a> AstBytecodeGen<'a> {
 
         let part_register = self.alloc_temp(BytecodeType::Ptr);
 
-        for part in &expr.parts {
-            if let Some(ref lit_str) = part.to_lit_str() {
-                let value = lit_str.value.clone();
+        for (idx, (part, spec)) in expr.parts.iter().zip(&expr.specs).enumerate() {
+            if idx % 2 == 0 {
+                let value = part
+                    .to_lit_str()
+                    .expect("string literal expected")
+                    .value
+                    .clone();
                 self.builder.emit_const_string(part_register, value);
+            } else if let Some(ref spec) = spec {
+                self.emit_template_part_formatted(part, spec, part_register);
             } else {
                 let ty = self.ty(part.id());
 
                 if ty.cls_id() == Some(self.sa.known.classes.string()) {
                     self.visit_expr(part, DataDest::Reg(part_register));
-                } else if ty.is_type_param() {
-                    let type_list_id = match ty {
-                        SourceType::TypeParam(id) => id,
-                        _ => unreachable!(),
-                    };
-
-                    let expr_register = self.visit_expr(part, DataDest::Alloc);
-                    self.builder.emit_push_register(expr_register);
-
-                    // build toString() call
-                    let name = self.sa.interner.intern("toString");
-                    let trait_id = self.sa.known.traits.stringable();
-                    let trait_ = self.sa.traits[trait_id].read();
-                    let to_string_id = trait_
-                        .find_method(self.sa, name, false)
-                        .expect("Stringable::toString() not found");
-
-                    let fct_idx = self.builder.add_const_generic(
-                        type_list_id,
-                        to_string_id,
-                        SourceTypeArray::empty(),
-                    );
-
-                    self.builder
-                        .emit_invoke_generic_direct(part_register, fct_idx, part.pos());
-
-                    self.free_if_temp(expr_register);
                 } else {
                     let expr_register = self.visit_expr(part, DataDest::Alloc);
                     self.builder.emit_push_register(expr_register);
 
                     // build toString() call
-                    let name = self.sa.interner.intern("toString");
-                    let stringable_impl_id = find_impl(
-                        self.sa,
-                        ty,
-                        &self.fct.type_params,
-                        SourceType::new_trait(self.sa.known.traits.stringable()),
-                    )
-                    .expect("impl of Stringable not found");
-                    let impl_ = self.sa.impls[stringable_impl_id].read();
-                    let to_string_id = impl_
-                        .instance_names
-                        .get(&name)
-                        .cloned()
-                        .expect("method toString() not found");
-
-                    let fct_idx = self.builder.add_const_fct(to_string_id);
-                    self.builder
-                        .emit_invoke_direct(part_register, fct_idx, part.pos());
+                    let trait_id = self.sa.known.traits.stringable();
+                    self.emit_template_trait_call(part, ty, trait_id, "toString", part_register);
 
                     self.free_if_temp(expr_register);
                 }
@@ -759,6 +783,112 @@ impl<'a> AstBytecodeGen<'a> {
         buffer_register
     }
 
+    // Formats a template part with Formattable::format(), types that only
+    // implement Stringable are padded with Formatter::pad().
+    fn emit_template_part_formatted(
+        &mut self,
+        part: &ast::Expr,
+        spec: &ast::FormatSpec,
+        dest: Register,
+    ) {
+        let ty = self.ty(part.id());
+
+        // build Formatter::fromSpec() call
+        let formatter_register = self.alloc_temp(BytecodeType::Ptr);
+        self.builder
+            .emit_const_string(formatter_register, spec.value.clone());
+        self.builder.emit_push_register(formatter_register);
+        let fct_id = self.sa.known.functions.formatter_from_spec();
+        let fct_idx = self.builder.add_const_fct(fct_id);
+        self.builder
+            .emit_invoke_static(formatter_register, fct_idx, spec.pos);
+
+        let formattable_trait_id = self.sa.known.traits.formattable();
+        let formattable_trait_ty = SourceType::new_trait(formattable_trait_id);
+
+        let is_formattable = if let SourceType::TypeParam(id) = ty {
+            self.fct
+                .type_params
+                .implements_trait(id, formattable_trait_ty)
+        } else {
+            find_impl(
+                self.sa,
+                ty.clone(),
+                &self.fct.type_params,
+                formattable_trait_ty,
+            )
+            .is_some()
+        };
+
+        let expr_register = self.visit_expr(part, DataDest::Alloc);
+
+        if is_formattable {
+            // build format() call
+            self.builder.emit_push_register(expr_register);
+            self.builder.emit_push_register(formatter_register);
+            self.emit_template_trait_call(part, ty, formattable_trait_id, "format", dest);
+        } else {
+            // build toString() call
+            self.builder.emit_push_register(expr_register);
+            let stringable_trait_id = self.sa.known.traits.stringable();
+            self.emit_template_trait_call(part, ty, stringable_trait_id, "toString", dest);
+
+            // build Formatter::pad() call
+            let fct_id = self.sa.known.functions.formatter_pad();
+            let fct_idx = self.builder.add_const_fct(fct_id);
+            self.builder.emit_push_register(formatter_register);
+            self.builder.emit_push_register(dest);
+            self.builder.emit_invoke_direct(dest, fct_idx, part.pos());
+        }
+
+        self.free_if_temp(expr_register);
+        self.free_temp(formatter_register);
+    }
+
+    // Calls the method of a trait implemented by the type of a template part,
+    // the arguments need to be pushed already.
+    fn emit_template_trait_call(
+        &mut self,
+        part: &ast::Expr,
+        ty: SourceType,
+        trait_id: TraitDefinitionId,
+        name: &str,
+        dest: Register,
+    ) {
+        let name = self.sa.interner.intern(name);
+
+        if let SourceType::TypeParam(type_list_id) = ty {
+            let trait_ = self.sa.traits[trait_id].read();
+            let method_id = trait_
+                .find_method(self.sa, name, false)
+                .expect("trait method not found");
+
+            let fct_idx =
+                self.builder
+                    .add_const_generic(type_list_id, method_id, SourceTypeArray::empty());
+
+            self.builder
+                .emit_invoke_generic_direct(dest, fct_idx, part.pos());
+        } else {
+            let impl_id = find_impl(
+                self.sa,
+                ty,
+                &self.fct.type_params,
+                SourceType::new_trait(trait_id),
+            )
+            .expect("impl of trait not found");
+            let impl_ = self.sa.impls[impl_id].read();
+            let method_id = impl_
+                .instance_names
+                .get(&name)
+                .cloned()
+                .expect("trait method not found");
+
+            let fct_idx = self.builder.add_const_fct(method_id);
+            self.builder.emit_invoke_direct(dest, fct_idx, part.pos());
+        }
+    }
+
     fn visit_expr_path(&mut self, expr: &ast::ExprPathType, dest: DataDest) -> Register {
         let ident_type = self.analysis.map_idents.get(expr.id).cloned().unwrap();
 
@@ -815,8 +945,7 @@ impl<'a> AstBytecodeGen<'a> {
 
     fn visit_expr_match(&mut self, node: &ast::ExprMatchType, dest: DataDest) -> Register {
         let result_ty = self.ty(node.id);
-        let enum_ty = self.ty(node.expr.id());
-        let enum_id = enum_ty.enum_id().expect("enum expected");
+        let expr_ty = self.ty(node.expr.id());
 
         let dest = if result_ty.is_unit() {
             None
@@ -830,101 +959,477 @@ impl<'a> AstBytecodeGen<'a> {
 
         let expr_reg = self.visit_expr(&node.expr, DataDest::Alloc);
 
-        let variant_reg = self.alloc_temp(BytecodeType::Int32);
-        let idx = self.builder.add_const_enum(enum_id, enum_ty.type_params());
-        self.builder
-            .emit_load_enum_variant(variant_reg, expr_reg, idx, node.pos);
+        self.push_scope();
 
-        let mut next_lbl = self.builder.create_label();
+        let mut rows = Vec::with_capacity(node.cases.len());
+        let mut arm_lbls = Vec::with_capacity(node.cases.len());
 
-        for (idx, case) in node.cases.iter().enumerate() {
-            debug_assert_eq!(case.patterns.len(), 1);
-            let pattern = case.patterns.first().expect("pattern missing");
-            match pattern.data {
-                ast::MatchPatternData::Underscore => {
-                    self.builder.bind_label(next_lbl);
+        for (arm, case) in node.cases.iter().enumerate() {
+            let pattern = Pattern::lower_alternatives(self.analysis, &case.patterns);
+            self.alloc_pattern_vars(&pattern);
 
-                    if let Some(dest) = dest {
-                        self.visit_expr(&case.value, DataDest::Reg(dest));
-                    } else {
-                        self.visit_expr(&case.value, DataDest::Effect);
+            rows.push(MatchRow {
+                patterns: vec![pattern],
+                arm,
+                bindings: Vec::new(),
+            });
+            arm_lbls.push(self.builder.create_label());
+        }
+
+        self.emit_decision_tree(rows, vec![(expr_reg, expr_ty)], &arm_lbls, node.pos);
+
+        for (case, arm_lbl) in node.cases.iter().zip(arm_lbls) {
+            self.builder.bind_label(arm_lbl);
+
+            if let Some(dest) = dest {
+                self.visit_expr(&case.value, DataDest::Reg(dest));
+            } else {
+                self.visit_expr(&case.value, DataDest::Effect);
+            }
+
+            self.builder.emit_jump(end_lbl);
+        }
+
+        self.pop_scope();
+
+        self.builder.bind_label(end_lbl);
+        self.free_if_temp(expr_reg);
+
+        dest.unwrap_or(Register::invalid())
+    }
+
+    // Registers for the variables of a match arm are allocated up-front,
+    // the decision tree has to assign them before jumping to the arm.
+    fn alloc_pattern_vars(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Wildcard => {}
+
+            Pattern::Binding(id) => {
+                let var_id = *self.analysis.map_vars.get(*id).unwrap();
+                let ty = self.var_ty(var_id);
+
+                if !ty.is_unit() && !self.var_registers.contains_key(&var_id) {
+                    let var_reg = self.alloc_var(register_bty_from_ty(ty));
+                    self.var_registers.insert(var_id, var_reg);
+                }
+            }
+
+            Pattern::Constructor(_, params) | Pattern::Alt(params) => {
+                for param in params {
+                    self.alloc_pattern_vars(param);
+                }
+            }
+        }
+    }
+
+    // Compiles the match into a decision tree: Each value is only tested
+    // once, at the leaves the bindings are assigned and control jumps to
+    // the body of the match arm. `occurrences` holds the registers and
+    // types of the values the columns of `rows` are matched against.
+    fn emit_decision_tree(
+        &mut self,
+        mut rows: Vec<MatchRow>,
+        mut occurrences: Vec<(Register, SourceType)>,
+        arm_lbls: &[Label],
+        pos: Position,
+    ) {
+        if rows.is_empty() {
+            // The type checker guarantees that the match is exhaustive.
+            return;
+        }
+
+        let col = match rows[0]
+            .patterns
+            .iter()
+            .position(|pattern| !pattern.is_wildcard())
+        {
+            Some(col) => col,
+            None => {
+                let row = &rows[0];
+
+                for (id, reg) in row.bindings.iter().cloned().chain(
+                    row.patterns.iter().zip(occurrences.iter()).filter_map(
+                        |(pattern, &(reg, _))| match pattern {
+                            Pattern::Binding(id) => Some((*id, reg)),
+                            _ => None,
+                        },
+                    ),
+                ) {
+                    let var_id = *self.analysis.map_vars.get(id).unwrap();
+
+                    if let Some(&var_reg) = self.var_registers.get(&var_id) {
+                        self.builder.emit_mov(var_reg, reg);
                     }
+                }
+
+                self.builder.emit_jump(arm_lbls[row.arm]);
+                return;
+            }
+        };
+
+        for row in &mut rows {
+            row.patterns.swap(0, col);
+        }
+
+        occurrences.swap(0, col);
+
+        if rows
+            .iter()
+            .any(|row| matches!(row.patterns[0], Pattern::Alt(_)))
+        {
+            let rows = rows.into_iter().flat_map(MatchRow::expand).collect();
+            self.emit_decision_tree(rows, occurrences, arm_lbls, pos);
+            return;
+        }
+
+        let mut constructors: Vec<Constructor> = Vec::new();
 
-                    self.builder.emit_jump(end_lbl);
+        for row in &rows {
+            if let Pattern::Constructor(ref constructor, _) = row.patterns[0] {
+                if !constructors.contains(constructor) {
+                    constructors.push(constructor.clone());
                 }
+            }
+        }
 
-                ast::MatchPatternData::Ident(ref ident) => {
-                    let variant_idx: i32 = {
-                        let ident_type = self.analysis.map_idents.get(pattern.id).unwrap();
+        let (reg, ty) = occurrences[0].clone();
 
-                        match ident_type {
-                            IdentType::EnumValue(_, _, variant_idx) => {
-                                (*variant_idx).try_into().unwrap()
-                            }
-                            _ => unreachable!(),
-                        }
+        match ty {
+            SourceType::Tuple(_) | SourceType::Unit => {
+                let subtypes = constructor_subtypes(self.sa, &ty, &Constructor::Tuple);
+                let mut subregs = Vec::with_capacity(subtypes.len());
+
+                for (idx, subtype) in subtypes.into_iter().enumerate() {
+                    let subreg = if subtype.is_unit() {
+                        self.ensure_unit_register()
+                    } else {
+                        let subreg = self.alloc_temp(register_bty_from_ty(subtype.clone()));
+                        let idx = self.builder.add_const_tuple_element(ty.clone(), idx);
+                        self.builder.emit_load_tuple_element(subreg, reg, idx);
+                        subreg
                     };
 
-                    self.builder.bind_label(next_lbl);
-                    next_lbl = self.builder.create_label();
+                    subregs.push((subreg, subtype));
+                }
+
+                self.emit_decision_tree_branch(
+                    &rows,
+                    &Constructor::Tuple,
+                    subregs,
+                    &occurrences,
+                    arm_lbls,
+                    pos,
+                );
+            }
+
+            SourceType::Enum(enum_id, ref type_params) => {
+                let variants = self.sa.enums[enum_id].read().variants.len();
+                let complete = constructors.len() == variants;
+
+                let variant_reg = self.alloc_temp(BytecodeType::Int32);
+                let idx = self.builder.add_const_enum(enum_id, type_params.clone());
+                self.builder
+                    .emit_load_enum_variant(variant_reg, reg, idx, pos);
 
-                    if idx != node.cases.len() - 1 {
+                for (idx, constructor) in constructors.iter().enumerate() {
+                    let variant_idx = match constructor {
+                        Constructor::Variant(variant_idx) => *variant_idx,
+                        _ => unreachable!(),
+                    };
+
+                    // The last variant doesn't need to be tested when all
+                    // variants are handled.
+                    let next_lbl = if complete && idx == constructors.len() - 1 {
+                        None
+                    } else {
+                        let next_lbl = self.builder.create_label();
                         let tmp_reg = self.alloc_temp(BytecodeType::Int32);
                         let cmp_reg = self.alloc_temp(BytecodeType::Bool);
-                        self.builder.emit_const_int32(tmp_reg, variant_idx);
+                        self.builder
+                            .emit_const_int32(tmp_reg, variant_idx.try_into().unwrap());
                         self.builder.emit_test_eq(cmp_reg, variant_reg, tmp_reg);
                         self.builder.emit_jump_if_false(cmp_reg, next_lbl);
                         self.free_temp(tmp_reg);
                         self.free_temp(cmp_reg);
+                        Some(next_lbl)
+                    };
+
+                    let subtypes = constructor_subtypes(self.sa, &ty, constructor);
+                    let mut subregs = Vec::with_capacity(subtypes.len());
+
+                    for (element_idx, subtype) in subtypes.into_iter().enumerate() {
+                        let subreg = if subtype.is_unit() {
+                            self.ensure_unit_register()
+                        } else {
+                            let subreg = self.alloc_temp(register_bty_from_ty(subtype.clone()));
+                            let idx = self.builder.add_const_enum_element(
+                                enum_id,
+                                type_params.clone(),
+                                variant_idx,
+                                element_idx,
+                            );
+                            self.builder.emit_load_enum_element(subreg, reg, idx, pos);
+                            subreg
+                        };
+
+                        subregs.push((subreg, subtype));
                     }
 
-                    self.push_scope();
+                    self.emit_decision_tree_branch(
+                        &rows,
+                        constructor,
+                        subregs,
+                        &occurrences,
+                        arm_lbls,
+                        pos,
+                    );
 
-                    if let Some(ref params) = ident.params {
-                        for (subtype_idx, param) in params.iter().enumerate() {
-                            if let Some(_) = param.name {
-                                let idx = self.builder.add_const_enum_element(
-                                    enum_id,
-                                    enum_ty.type_params(),
-                                    variant_idx as usize,
-                                    subtype_idx,
-                                );
+                    if let Some(next_lbl) = next_lbl {
+                        self.builder.bind_label(next_lbl);
+                    }
+                }
 
-                                let var_id = *self.analysis.map_vars.get(param.id).unwrap();
+                if !complete {
+                    self.emit_decision_tree_default(&rows, &occurrences, arm_lbls, pos);
+                }
 
-                                let ty = self.var_ty(var_id);
+                self.free_temp(variant_reg);
+            }
 
-                                if !ty.is_unit() {
-                                    let ty: BytecodeType = register_bty_from_ty(ty);
-                                    let var_reg = self.alloc_var(ty);
+            SourceType::Bool => {
+                let other_lbl = self.builder.create_label();
+                let first = constructors[0].clone();
 
-                                    self.var_registers.insert(var_id, var_reg);
+                if first == Constructor::Bool(true) {
+                    self.builder.emit_jump_if_false(reg, other_lbl);
+                } else {
+                    self.builder.emit_jump_if_true(reg, other_lbl);
+                }
 
-                                    self.builder
-                                        .emit_load_enum_element(var_reg, expr_reg, idx, param.pos);
-                                }
-                            }
-                        }
-                    }
+                self.emit_decision_tree_branch(
+                    &rows,
+                    &first,
+                    Vec::new(),
+                    &occurrences,
+                    arm_lbls,
+                    pos,
+                );
 
-                    if let Some(dest) = dest {
-                        self.visit_expr(&case.value, DataDest::Reg(dest));
-                    } else {
-                        self.visit_expr(&case.value, DataDest::Effect);
-                    }
+                self.builder.bind_label(other_lbl);
+
+                if constructors.len() == 2 {
+                    self.emit_decision_tree_branch(
+                        &rows,
+                        &constructors[1],
+                        Vec::new(),
+                        &occurrences,
+                        arm_lbls,
+                        pos,
+                    );
+                } else {
+                    self.emit_decision_tree_default(&rows, &occurrences, arm_lbls, pos);
+                }
+            }
 
-                    self.pop_scope();
+            _ => {
+                for constructor in &constructors {
+                    let next_lbl = self.builder.create_label();
+                    let cmp_reg = self.emit_test_literal(reg, &ty, constructor, pos);
+                    self.builder.emit_jump_if_false(cmp_reg, next_lbl);
+                    self.free_temp(cmp_reg);
+
+                    self.emit_decision_tree_branch(
+                        &rows,
+                        constructor,
+                        Vec::new(),
+                        &occurrences,
+                        arm_lbls,
+                        pos,
+                    );
 
-                    self.builder.emit_jump(end_lbl);
+                    self.builder.bind_label(next_lbl);
                 }
+
+                self.emit_decision_tree_default(&rows, &occurrences, arm_lbls, pos);
             }
         }
+    }
 
-        self.builder.bind_label(end_lbl);
+    // Continues with the rows matching the constructor, the first column is
+    // replaced with the constructor's subvalues in `subregs`.
+    fn emit_decision_tree_branch(
+        &mut self,
+        rows: &[MatchRow],
+        constructor: &Constructor,
+        subregs: Vec<(Register, SourceType)>,
+        occurrences: &[(Register, SourceType)],
+        arm_lbls: &[Label],
+        pos: Position,
+    ) {
+        let reg = occurrences[0].0;
+        let arity = subregs.len();
+        let mut specialized = Vec::new();
+
+        for row in rows {
+            let mut patterns = match row.patterns[0] {
+                Pattern::Constructor(ref other, ref params) if other == constructor => {
+                    params.clone()
+                }
+                Pattern::Constructor(_, _) => continue,
+                _ => vec![Pattern::Wildcard; arity],
+            };
+
+            patterns.extend_from_slice(&row.patterns[1..]);
+            specialized.push(row.consume_column(patterns, reg));
+        }
+
+        let temps: Vec<Register> = subregs.iter().map(|&(reg, _)| reg).collect();
+        let mut subregs = subregs;
+        subregs.extend_from_slice(&occurrences[1..]);
+
+        self.emit_decision_tree(specialized, subregs, arm_lbls, pos);
+
+        for reg in temps {
+            self.free_if_temp(reg);
+        }
+    }
+
+    // Continues with the rows matching any value in the first column.
+    fn emit_decision_tree_default(
+        &mut self,
+        rows: &[MatchRow],
+        occurrences: &[(Register, SourceType)],
+        arm_lbls: &[Label],
+        pos: Position,
+    ) {
+        let reg = occurrences[0].0;
+        let rows = rows
+            .iter()
+            .filter(|row| row.patterns[0].is_wildcard())
+            .map(|row| row.consume_column(row.patterns[1..].to_vec(), reg))
+            .collect();
+
+        self.emit_decision_tree(rows, occurrences[1..].to_vec(), arm_lbls, pos);
+    }
+
+    fn emit_test_literal(
+        &mut self,
+        reg: Register,
+        ty: &SourceType,
+        constructor: &Constructor,
+        pos: Position,
+    ) -> Register {
+        let cmp_reg = self.alloc_temp(BytecodeType::Bool);
+        let tmp_reg = self.alloc_temp(register_bty_from_ty(ty.clone()));
+
+        match (constructor, ty) {
+            (Constructor::Int(value), SourceType::UInt8) => {
+                self.builder.emit_const_uint8(tmp_reg, *value as u8)
+            }
+            (Constructor::Int(value), SourceType::Int32) => {
+                self.builder.emit_const_int32(tmp_reg, *value as i32)
+            }
+            (Constructor::Int(value), SourceType::Int64) => {
+                self.builder.emit_const_int64(tmp_reg, *value)
+            }
+            (Constructor::Char(value), _) => self.builder.emit_const_char(tmp_reg, *value),
+            (Constructor::Str(value), _) => self.builder.emit_const_string(tmp_reg, value.clone()),
+            _ => unreachable!(),
+        }
+
+        if let Constructor::Str(_) = constructor {
+            let fct_id = self.sa.known.functions.string_equals();
+            let fct_idx = self.builder.add_const_fct(fct_id);
+            self.builder.emit_push_register(reg);
+            self.builder.emit_push_register(tmp_reg);
+            self.builder.emit_invoke_direct(cmp_reg, fct_idx, pos);
+        } else {
+            self.builder.emit_test_eq(cmp_reg, reg, tmp_reg);
+        }
+
+        self.free_temp(tmp_reg);
+
+        cmp_reg
+    }
+
+    fn visit_expr_try(&mut self, node: &ast::ExprTryType, dest: DataDest) -> Register {
+        let value_ty = self.ty(node.id);
+        let enum_ty = self.ty(node.expr.id());
+        let enum_id = enum_ty.enum_id().expect("enum expected");
+        let type_params = enum_ty.type_params();
+
+        // Some/Ok is always the first variant, None/Err the second one.
+        let success_variant_idx = 0;
+        let failure_variant_idx = 1;
+
+        let success_lbl = self.builder.create_label();
+
+        let expr_reg = self.visit_expr(&node.expr, DataDest::Alloc);
+
+        let variant_reg = self.alloc_temp(BytecodeType::Int32);
+        let idx = self.builder.add_const_enum(enum_id, type_params.clone());
+        self.builder
+            .emit_load_enum_variant(variant_reg, expr_reg, idx, node.pos);
+
+        let tmp_reg = self.alloc_temp(BytecodeType::Int32);
+        let cmp_reg = self.alloc_temp(BytecodeType::Bool);
+        self.builder
+            .emit_const_int32(tmp_reg, success_variant_idx as i32);
+        self.builder.emit_test_eq(cmp_reg, variant_reg, tmp_reg);
+        self.builder.emit_jump_if_true(cmp_reg, success_lbl);
+        self.free_temp(tmp_reg);
+        self.free_temp(cmp_reg);
         self.free_temp(variant_reg);
+
+        // Early return of None or Err with the function's return type.
+        let return_type = self.fct.return_type.clone();
+        let return_type_params = return_type.type_params();
+
+        if enum_id == self.sa.known.enums.result() {
+            let error_ty = type_params[1].clone();
+
+            let error_reg = if error_ty.is_unit() {
+                self.ensure_unit_register()
+            } else {
+                let error_reg = self.alloc_temp(register_bty_from_ty(error_ty));
+                let idx = self.builder.add_const_enum_element(
+                    enum_id,
+                    type_params.clone(),
+                    failure_variant_idx,
+                    0,
+                );
+                self.builder
+                    .emit_load_enum_element(error_reg, expr_reg, idx, node.pos);
+                error_reg
+            };
+
+            self.builder.emit_push_register(error_reg);
+            self.free_if_temp(error_reg);
+        }
+
+        let return_reg = self.alloc_temp(register_bty_from_ty(return_type));
+        let idx =
+            self.builder
+                .add_const_enum_variant(enum_id, return_type_params, failure_variant_idx);
+        self.builder.emit_new_enum(return_reg, idx, node.pos);
+        self.emit_ret_value(return_reg);
+        self.free_temp(return_reg);
+
+        self.builder.bind_label(success_lbl);
+
+        let dest = self.ensure_register(dest, register_bty_from_ty(value_ty.clone()));
+
+        if !value_ty.is_unit() {
+            let idx =
+                self.builder
+                    .add_const_enum_element(enum_id, type_params, success_variant_idx, 0);
+            self.builder
+                .emit_load_enum_element(dest, expr_reg, idx, node.pos);
+        }
+
         self.free_if_temp(expr_reg);
 
-        dest.unwrap_or(Register::invalid())
+        dest
     }
 
     fn visit_expr_lambda(&mut self, node: &ast::Function, dest: DataDest) -> Register {
@@ -954,6 +1459,10 @@ impl<'a> AstBytecodeGen<'a> {
     }
 
     fn visit_expr_if(&mut self, expr: &ast::ExprIfType, dest: DataDest) -> Register {
+        if let Some(ref pattern) = expr.pattern {
+            return self.visit_expr_if_let(expr, pattern, dest);
+        }
+
         let ty = self.ty(expr.id);
 
         if let Some(ref else_block) = expr.else_block {
@@ -997,6 +1506,54 @@ impl<'a> AstBytecodeGen<'a> {
         }
     }
 
+    fn visit_expr_if_let(
+        &mut self,
+        expr: &ast::ExprIfType,
+        pattern: &ast::MatchPattern,
+        dest: DataDest,
+    ) -> Register {
+        let ty = self.ty(expr.id);
+
+        let dest = if ty.is_unit() {
+            Register::invalid()
+        } else {
+            self.ensure_register(dest, register_bty_from_ty(ty))
+        };
+
+        let then_lbl = self.builder.create_label();
+        let else_lbl = self.builder.create_label();
+        let end_lbl = self.builder.create_label();
+
+        self.push_scope();
+
+        let cond_reg = self.visit_expr(&expr.cond, DataDest::Alloc);
+        let cond_ty = self.ty(expr.cond.id());
+        self.emit_let_pattern_condition(pattern, cond_reg, cond_ty, then_lbl, else_lbl, expr.pos);
+        self.free_if_temp(cond_reg);
+
+        self.builder.bind_label(then_lbl);
+
+        if let Some(ref else_block) = expr.else_block {
+            self.visit_expr(&expr.then_block, DataDest::Reg(dest));
+            self.pop_scope();
+
+            if !expr_always_returns(&expr.then_block) {
+                self.builder.emit_jump(end_lbl);
+            }
+
+            self.builder.bind_label(else_lbl);
+            self.visit_expr(else_block, DataDest::Reg(dest));
+        } else {
+            self.emit_expr_for_effect(&expr.then_block);
+            self.pop_scope();
+            self.builder.bind_label(else_lbl);
+        }
+
+        self.builder.bind_label(end_lbl);
+
+        dest
+    }
+
     fn visit_expr_block(&mut self, block: &ast::ExprBlockType, dest: DataDest) -> Register {
         self.push_scope();
 
@@ -3166,6 +3723,50 @@ impl<'a> AstBytecodeGen<'a> {
     }
 }
 
+struct MatchRow {
+    patterns: Vec<Pattern>,
+    arm: usize,
+    bindings: Vec<(ast::NodeId, Register)>,
+}
+
+impl MatchRow {
+    fn expand(self) -> Vec<MatchRow> {
+        match self.patterns[0] {
+            Pattern::Alt(ref alternatives) => alternatives
+                .iter()
+                .map(|alternative| {
+                    let mut patterns = self.patterns.clone();
+                    patterns[0] = alternative.clone();
+
+                    MatchRow {
+                        patterns,
+                        arm: self.arm,
+                        bindings: self.bindings.clone(),
+                    }
+                })
+                .collect(),
+
+            _ => vec![self],
+        }
+    }
+
+    // Creates the row for the next step of the decision tree, a binding in
+    // the consumed column is assigned the value in `reg`.
+    fn consume_column(&self, patterns: Vec<Pattern>, reg: Register) -> MatchRow {
+        let mut bindings = self.bindings.clone();
+
+        if let Pattern::Binding(id) = self.patterns[0] {
+            bindings.push((id, reg));
+        }
+
+        MatchRow {
+            patterns,
+            arm: self.arm,
+            bindings,
+        }
+    }
+}
+
 struct IntrinsicInfo {
     intrinsic: Intrinsic,
     fct_id: Option<FctDefinitionId>,
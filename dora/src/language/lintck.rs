@@ -0,0 +1,192 @@
+use std::collections::HashSet;
+
+use crate::language::error::lint::Lint;
+use crate::language::error::msg::ErrorMessage;
+use crate::language::sem_analysis::{FctDefinition, SemAnalysis, UseDefinition};
+use crate::language::{read_allowed_lints, report_lint};
+
+use dora_parser::ast::{self, UsePathComponentValue, UseTargetDescriptor};
+use dora_parser::interner::Name;
+use dora_parser::lexer::position::Position;
+
+// Lints that need the whole program analyzed first. Lints about function
+// bodies are reported while type checking them.
+pub fn check(sa: &SemAnalysis) {
+    // elements with syntax errors are missing, so their uses are unknown
+    if sa.diag.lock().has_errors() {
+        return;
+    }
+
+    check_unused_functions(sa);
+    check_unused_imports(sa);
+}
+
+fn check_unused_functions(sa: &SemAnalysis) {
+    let mut used = HashSet::new();
+
+    for fct in sa.fcts.iter() {
+        let fct = fct.read();
+
+        if let Some(ref analysis) = fct.analysis {
+            for (_, call_type) in analysis.map_calls.iter() {
+                match call_type.fct_id() {
+                    // recursive calls don't make a function used
+                    Some(callee_id) if callee_id != fct.id() => {
+                        used.insert(callee_id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    for fct in sa.fcts.iter() {
+        let fct = fct.read();
+
+        if is_private_function(sa, &fct) && !used.contains(&fct.id()) {
+            let name = sa.interner.str(fct.name).to_string();
+            report_lint(
+                sa,
+                &fct.allowed_lints,
+                Lint::UnusedFunction,
+                fct.file_id,
+                fct.pos,
+                ErrorMessage::UnusedFunction(name),
+            );
+        }
+    }
+}
+
+fn is_private_function(sa: &SemAnalysis, fct: &FctDefinition) -> bool {
+    if !fct.parent.is_none()
+        || fct.is_lambda()
+        || fct.visibility.is_public()
+        || fct.is_test
+        || fct.internal
+        || !fct.has_body()
+    {
+        return false;
+    }
+
+    // main is called by the runtime
+    !(fct.module_id == sa.program_module_id() && &*sa.interner.str(fct.name) == "main")
+}
+
+fn check_unused_imports(sa: &SemAnalysis) {
+    for use_elem in &sa.uses {
+        if use_elem.package_id != sa.program_package_id() {
+            continue;
+        }
+
+        let allowed_lints =
+            read_allowed_lints(sa, use_elem.file_id, use_elem.ast.pos, &use_elem.ast.allow);
+        let mut targets = Vec::new();
+        use_targets(&use_elem.ast, &mut targets);
+
+        for (name, pos) in targets {
+            check_unused_import(sa, use_elem, &allowed_lints, name, pos);
+        }
+    }
+}
+
+fn check_unused_import(
+    sa: &SemAnalysis,
+    use_elem: &UseDefinition,
+    allowed_lints: &[Lint],
+    name: Name,
+    pos: Position,
+) {
+    let module = sa.modules.idx(use_elem.module_id);
+    let module = module.read();
+
+    if module.table.read().is_import_used(name) {
+        return;
+    }
+
+    let name = sa.interner.str(name).to_string();
+    report_lint(
+        sa,
+        allowed_lints,
+        Lint::UnusedImport,
+        use_elem.file_id,
+        pos,
+        ErrorMessage::UnusedImport(name),
+    );
+}
+
+// Collects the names defined by the use declaration.
+fn use_targets(use_declaration: &ast::Use, targets: &mut Vec<(Name, Position)>) {
+    match use_declaration.target {
+        UseTargetDescriptor::Default => {
+            if let Some(component) = use_declaration.common_path.last() {
+                if let UsePathComponentValue::Name(name) = component.value {
+                    targets.push((name, component.pos));
+                }
+            }
+        }
+
+        UseTargetDescriptor::As(ref target) => {
+            if let Some(name) = target.name {
+                targets.push((name, target.pos));
+            }
+        }
+
+        UseTargetDescriptor::Group(ref group) => {
+            for nested_use in &group.targets {
+                use_targets(nested_use, targets);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::error::msg::ErrorMessage;
+    use crate::language::tests::*;
+
+    #[test]
+    fn unused_function() {
+        ok_without_warnings("fn main() {}");
+        ok_without_warnings("fn main() { f(); } fn f() {}");
+        ok_without_warnings("@pub fn f() {}");
+        ok_without_warnings("@Test fn f() {}");
+        ok_without_warnings("@allow(unusedFunction) fn f() {}");
+        warn(
+            "fn f() {}",
+            pos(1, 1),
+            ErrorMessage::UnusedFunction("f".into()),
+        );
+        warn(
+            "fn f(x: Int64): Int64 { f(x - 1) }",
+            pos(1, 1),
+            ErrorMessage::UnusedFunction("f".into()),
+        );
+    }
+
+    #[test]
+    fn unused_import() {
+        ok_without_warnings("use std::HashMap; fn main() { HashMap[Int64, Int64]::new(); }");
+        ok_without_warnings("use std::HashMap; @pub fn f(x: HashMap[Int64, Int64]) {}");
+        ok_without_warnings("@allow(unusedImport) use std::HashMap; fn main() {}");
+        warn(
+            "use std::HashMap; fn main() {}",
+            pos(1, 10),
+            ErrorMessage::UnusedImport("HashMap".into()),
+        );
+        warn(
+            "use std::{HashMap, HashSet}; fn main() { HashSet[Int64]::new(); }",
+            pos(1, 11),
+            ErrorMessage::UnusedImport("HashMap".into()),
+        );
+        warn(
+            "use std::HashMap as Map; fn main() {}",
+            pos(1, 21),
+            ErrorMessage::UnusedImport("Map".into()),
+        );
+        err(
+            "@allow(unusedStuff) use std::HashMap; fn main() {}",
+            pos(1, 25),
+            ErrorMessage::UnknownLint("unusedStuff".into()),
+        );
+    }
+}
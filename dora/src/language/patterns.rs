@@ -0,0 +1,393 @@
+use dora_parser::ast;
+
+use crate::language::sem_analysis::{AnalysisData, IdentType, SemAnalysis};
+use crate::language::specialize::replace_type_param;
+use crate::language::ty::SourceType;
+
+// Simplified representation of match patterns. Used both for checking
+// exhaustiveness and reachability of match arms in the type checker and
+// for compiling matches into decision trees in the bytecode generator.
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    Wildcard,
+    Binding(ast::NodeId),
+    Constructor(Constructor, Vec<Pattern>),
+    Alt(Vec<Pattern>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constructor {
+    Variant(usize),
+    Tuple,
+    Bool(bool),
+    Int(i64),
+    Char(char),
+    Str(String),
+}
+
+impl Pattern {
+    pub fn lower(analysis: &AnalysisData, pattern: &ast::MatchPattern) -> Pattern {
+        match pattern.data {
+            ast::MatchPatternData::Underscore => Pattern::Wildcard,
+
+            ast::MatchPatternData::Lit(ref expr) => {
+                Pattern::Constructor(lower_literal(analysis, expr), Vec::new())
+            }
+
+            ast::MatchPatternData::Ident(ref ident) => match analysis.map_idents.get(pattern.id) {
+                Some(IdentType::EnumValue(_, _, variant_idx)) => {
+                    let params = ident
+                        .params
+                        .iter()
+                        .flatten()
+                        .map(|param| Pattern::lower(analysis, param))
+                        .collect();
+
+                    Pattern::Constructor(Constructor::Variant(*variant_idx), params)
+                }
+
+                _ => Pattern::Binding(pattern.id),
+            },
+
+            ast::MatchPatternData::Tuple(ref parts) => {
+                let parts = parts
+                    .iter()
+                    .map(|part| Pattern::lower(analysis, part))
+                    .collect();
+
+                Pattern::Constructor(Constructor::Tuple, parts)
+            }
+
+            ast::MatchPatternData::Alt(ref alternatives) => {
+                Pattern::lower_alternatives(analysis, alternatives)
+            }
+        }
+    }
+
+    pub fn lower_alternatives(analysis: &AnalysisData, patterns: &[ast::MatchPattern]) -> Pattern {
+        if patterns.len() == 1 {
+            Pattern::lower(analysis, &patterns[0])
+        } else {
+            let alternatives = patterns
+                .iter()
+                .map(|pattern| Pattern::lower(analysis, pattern))
+                .collect();
+
+            Pattern::Alt(alternatives)
+        }
+    }
+
+    pub fn is_wildcard(&self) -> bool {
+        matches!(self, Pattern::Wildcard | Pattern::Binding(_))
+    }
+}
+
+fn lower_literal(analysis: &AnalysisData, expr: &ast::Expr) -> Constructor {
+    match expr {
+        ast::Expr::LitBool(ref lit) => Constructor::Bool(lit.value),
+        ast::Expr::LitChar(ref lit) => Constructor::Char(lit.value),
+        ast::Expr::LitStr(ref lit) => Constructor::Str(lit.value.clone()),
+        ast::Expr::LitInt(ref lit) => {
+            Constructor::Int(int_value(analysis.ty(lit.id), lit.value, false))
+        }
+        ast::Expr::Un(ref un) => {
+            let lit = un.opnd.to_lit_int().expect("int literal expected");
+            Constructor::Int(int_value(analysis.ty(lit.id), lit.value, true))
+        }
+        _ => unreachable!(),
+    }
+}
+
+// Normalizes the literal to the value it has at runtime, e.g. 0xFFFFFFFFi32 and
+// -1i32 are the same pattern.
+fn int_value(ty: SourceType, value: u64, negate: bool) -> i64 {
+    let value = if negate {
+        (value as i64).wrapping_neg()
+    } else {
+        value as i64
+    };
+
+    match ty {
+        SourceType::UInt8 => value as u8 as i64,
+        SourceType::Int32 => value as i32 as i64,
+        _ => value,
+    }
+}
+
+// Returns all constructors for types with a finite number of them, None
+// for types like Int64 or String.
+pub fn all_constructors(sa: &SemAnalysis, ty: &SourceType) -> Option<Vec<Constructor>> {
+    match ty {
+        SourceType::Bool => Some(vec![Constructor::Bool(true), Constructor::Bool(false)]),
+        SourceType::Enum(enum_id, _) => {
+            let enum_ = sa.enums[*enum_id].read();
+            Some(
+                (0..enum_.variants.len())
+                    .map(Constructor::Variant)
+                    .collect(),
+            )
+        }
+        SourceType::Tuple(_) | SourceType::Unit => Some(vec![Constructor::Tuple]),
+        _ => None,
+    }
+}
+
+// Types of the values a constructor consists of.
+pub fn constructor_subtypes(
+    sa: &SemAnalysis,
+    ty: &SourceType,
+    constructor: &Constructor,
+) -> Vec<SourceType> {
+    match (constructor, ty) {
+        (Constructor::Variant(variant_idx), SourceType::Enum(enum_id, type_params)) => {
+            let enum_ = sa.enums[*enum_id].read();
+            enum_.variants[*variant_idx]
+                .types
+                .iter()
+                .map(|ty| replace_type_param(sa, ty.clone(), type_params, None))
+                .collect()
+        }
+        (Constructor::Tuple, SourceType::Tuple(subtypes)) => subtypes.types().to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+// Replaces rows starting with alternatives with one row per alternative.
+fn expand_alternatives(rows: &[Vec<Pattern>]) -> Vec<Vec<Pattern>> {
+    let mut result = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        expand_row(row, &mut result);
+    }
+
+    result
+}
+
+fn expand_row(row: &[Pattern], result: &mut Vec<Vec<Pattern>>) {
+    match row.first() {
+        Some(Pattern::Alt(alternatives)) => {
+            for alternative in alternatives {
+                let mut expanded = Vec::with_capacity(row.len());
+                expanded.push(alternative.clone());
+                expanded.extend_from_slice(&row[1..]);
+                expand_row(&expanded, result);
+            }
+        }
+
+        _ => result.push(row.to_vec()),
+    }
+}
+
+// Constructors used in the first column, in order of first use.
+pub fn head_constructors(rows: &[Vec<Pattern>]) -> Vec<Constructor> {
+    let mut constructors = Vec::new();
+
+    for row in rows {
+        if let Pattern::Constructor(ref constructor, _) = row[0] {
+            if !constructors.contains(constructor) {
+                constructors.push(constructor.clone());
+            }
+        }
+    }
+
+    constructors
+}
+
+// Rows matching the constructor with the first column replaced by the
+// constructor's subpatterns. Expects alternatives to be expanded.
+fn specialize(rows: &[Vec<Pattern>], constructor: &Constructor, arity: usize) -> Vec<Vec<Pattern>> {
+    let mut result = Vec::new();
+
+    for row in rows {
+        match row[0] {
+            Pattern::Constructor(ref other, ref params) => {
+                if other == constructor {
+                    let mut specialized = params.clone();
+                    specialized.extend_from_slice(&row[1..]);
+                    result.push(specialized);
+                }
+            }
+
+            Pattern::Wildcard | Pattern::Binding(_) => {
+                let mut specialized = vec![Pattern::Wildcard; arity];
+                specialized.extend_from_slice(&row[1..]);
+                result.push(specialized);
+            }
+
+            Pattern::Alt(_) => unreachable!(),
+        }
+    }
+
+    result
+}
+
+// Rows matching any constructor not mentioned in the first column.
+fn default_rows(rows: &[Vec<Pattern>]) -> Vec<Vec<Pattern>> {
+    rows.iter()
+        .filter(|row| row[0].is_wildcard())
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+// Checks whether `row` matches any value not already matched by `rows`.
+pub fn is_useful(
+    sa: &SemAnalysis,
+    rows: &[Vec<Pattern>],
+    row: &[Pattern],
+    types: &[SourceType],
+) -> bool {
+    if row.is_empty() {
+        return rows.is_empty();
+    }
+
+    let rows = expand_alternatives(rows);
+    let ty = &types[0];
+
+    match row[0] {
+        Pattern::Alt(ref alternatives) => alternatives.iter().any(|alternative| {
+            let mut expanded = vec![alternative.clone()];
+            expanded.extend_from_slice(&row[1..]);
+            is_useful(sa, &rows, &expanded, types)
+        }),
+
+        Pattern::Constructor(ref constructor, _) => {
+            is_useful_constructor(sa, &rows, row, types, constructor)
+        }
+
+        Pattern::Wildcard | Pattern::Binding(_) => {
+            let used = head_constructors(&rows);
+
+            match all_constructors(sa, ty) {
+                Some(all) if all.iter().all(|constructor| used.contains(constructor)) => all
+                    .iter()
+                    .any(|constructor| is_useful_constructor(sa, &rows, row, types, constructor)),
+
+                _ => is_useful(sa, &default_rows(&rows), &row[1..], &types[1..]),
+            }
+        }
+    }
+}
+
+fn is_useful_constructor(
+    sa: &SemAnalysis,
+    rows: &[Vec<Pattern>],
+    row: &[Pattern],
+    types: &[SourceType],
+    constructor: &Constructor,
+) -> bool {
+    let mut subtypes = constructor_subtypes(sa, &types[0], constructor);
+    let arity = subtypes.len();
+    subtypes.extend_from_slice(&types[1..]);
+
+    let rows = specialize(rows, constructor, arity);
+    let row = specialize(&[row.to_vec()], constructor, arity);
+
+    row.iter().any(|row| is_useful(sa, &rows, row, &subtypes))
+}
+
+// Returns values (one pattern per column) not matched by any of the rows.
+pub fn find_missing(
+    sa: &SemAnalysis,
+    rows: &[Vec<Pattern>],
+    types: &[SourceType],
+) -> Option<Vec<Pattern>> {
+    if types.is_empty() {
+        return if rows.is_empty() {
+            Some(Vec::new())
+        } else {
+            None
+        };
+    }
+
+    let rows = expand_alternatives(rows);
+    let ty = &types[0];
+    let used = head_constructors(&rows);
+    let all = all_constructors(sa, ty);
+
+    match all {
+        Some(ref all) if all.iter().all(|constructor| used.contains(constructor)) => {
+            for constructor in all {
+                let mut subtypes = constructor_subtypes(sa, ty, constructor);
+                let arity = subtypes.len();
+                subtypes.extend_from_slice(&types[1..]);
+
+                let specialized = specialize(&rows, constructor, arity);
+
+                if let Some(mut missing) = find_missing(sa, &specialized, &subtypes) {
+                    let rest = missing.split_off(arity);
+                    let mut result = vec![Pattern::Constructor(constructor.clone(), missing)];
+                    result.extend(rest);
+                    return Some(result);
+                }
+            }
+
+            None
+        }
+
+        _ => {
+            let missing = find_missing(sa, &default_rows(&rows), &types[1..])?;
+
+            let head = match all {
+                Some(all) if !used.is_empty() => {
+                    let constructor = all
+                        .into_iter()
+                        .find(|constructor| !used.contains(constructor))
+                        .expect("missing constructor");
+                    let arity = constructor_subtypes(sa, ty, &constructor).len();
+                    Pattern::Constructor(constructor, vec![Pattern::Wildcard; arity])
+                }
+
+                _ => Pattern::Wildcard,
+            };
+
+            let mut result = vec![head];
+            result.extend(missing);
+            Some(result)
+        }
+    }
+}
+
+pub fn pattern_name(sa: &SemAnalysis, pattern: &Pattern, ty: &SourceType) -> String {
+    match pattern {
+        Pattern::Wildcard | Pattern::Binding(_) => "_".into(),
+
+        Pattern::Alt(alternatives) => alternatives
+            .iter()
+            .map(|alternative| pattern_name(sa, alternative, ty))
+            .collect::<Vec<_>>()
+            .join(" | "),
+
+        Pattern::Constructor(constructor, params) => {
+            let subtypes = constructor_subtypes(sa, ty, constructor);
+            let params = params
+                .iter()
+                .zip(subtypes.iter())
+                .map(|(param, ty)| pattern_name(sa, param, ty))
+                .collect::<Vec<_>>();
+
+            match constructor {
+                Constructor::Variant(variant_idx) => {
+                    let enum_ = sa.enums[ty.enum_id().expect("enum expected")].read();
+                    let variant = &enum_.variants[*variant_idx];
+                    let name = format!(
+                        "{}::{}",
+                        sa.interner.str(enum_.name),
+                        sa.interner.str(variant.name)
+                    );
+
+                    if params.is_empty() {
+                        name
+                    } else {
+                        format!("{}({})", name, params.join(", "))
+                    }
+                }
+
+                Constructor::Tuple => format!("({})", params.join(", ")),
+                Constructor::Bool(value) => value.to_string(),
+                Constructor::Int(value) => value.to_string(),
+                Constructor::Char(value) => format!("{:?}", value),
+                Constructor::Str(value) => format!("{:?}", value),
+            }
+        }
+    }
+}
@@ -354,26 +354,24 @@ impl<'a> ProgramParser<'a> {
 
         let parser = Parser::from_shared_string(content, &mut self.sa.interner);
 
-        match parser.parse() {
-            Ok(ast) => {
-                self.scan_file(
-                    package_id,
-                    module_id,
-                    file_id,
-                    module_path,
-                    file_lookup,
-                    &ast,
-                );
-            }
+        let (ast, errors) = parser.parse_with_errors();
 
-            Err(error) => {
-                self.sa.diag.lock().report(
-                    file_id,
-                    error.pos,
-                    ErrorMessage::Custom(error.error.message()),
-                );
-            }
+        for error in errors {
+            self.sa.diag.lock().report_syntax_error(
+                file_id,
+                error.pos,
+                ErrorMessage::Custom(error.error.message()),
+            );
         }
+
+        self.scan_file(
+            package_id,
+            module_id,
+            file_id,
+            module_path,
+            file_lookup,
+            &ast,
+        );
     }
 }
 
@@ -1,5 +1,5 @@
 use dora_parser::ast::*;
-use dora_parser::lexer::position::Position;
+use dora_parser::lexer::position::{Position, Span};
 
 pub fn returns_value(s: &Stmt) -> Result<(), Position> {
     match *s {
@@ -38,6 +38,21 @@ pub fn expr_block_returns_value(e: &ExprBlockType) -> Result<(), Position> {
     }
 }
 
+// Returns the span of the first statement or expression in the block
+// that is never executed because an earlier statement always returns.
+pub fn expr_block_unreachable_code(e: &ExprBlockType) -> Option<Span> {
+    let idx = e
+        .stmts
+        .iter()
+        .position(|stmt| returns_value(stmt).is_ok())?;
+
+    if let Some(stmt) = e.stmts.get(idx + 1) {
+        Some(stmt.span())
+    } else {
+        e.expr.as_ref().map(|expr| expr.span())
+    }
+}
+
 fn expr_if_returns_value(e: &ExprIfType) -> Result<(), Position> {
     expr_returns_value(&e.then_block)?;
 
@@ -102,6 +102,15 @@ impl AnnotationDefinition {
         annotation_usages.contains(name)
     }
 
+    pub fn is_serial(annotation_usages: &AnnotationUsages, sa: &SemAnalysis) -> bool {
+        let name = sa
+            .annotations
+            .idx(sa.known.annotations.serial())
+            .read()
+            .name;
+        annotation_usages.contains(name)
+    }
+
     pub fn is_cannon(annotation_usages: &AnnotationUsages, sa: &SemAnalysis) -> bool {
         let name = sa
             .annotations
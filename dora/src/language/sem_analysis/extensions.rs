@@ -258,11 +258,9 @@ mod matching {
             | SourceType::Int64
             | SourceType::Float32
             | SourceType::Float64
-            | SourceType::TypeParam(_) => check_ty == ext_ty,
-
-            SourceType::Lambda(_, _) | SourceType::Trait(_, _) => {
-                unimplemented!()
-            }
+            | SourceType::TypeParam(_)
+            | SourceType::Lambda(_, _)
+            | SourceType::Trait(_, _) => check_ty == ext_ty,
 
             SourceType::Tuple(check_subtypes) => {
                 if !ext_ty.is_tuple() {
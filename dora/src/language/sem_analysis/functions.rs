@@ -8,6 +8,7 @@ use dora_parser::lexer::position::Position;
 
 use crate::bytecode::{BytecodeFunction, BytecodeType};
 use crate::gc::Address;
+use crate::language::error::lint::Lint;
 use crate::language::sem_analysis::{
     module_path, AnalysisData, ExtensionDefinitionId, ImplDefinitionId, ModuleDefinitionId,
     PackageDefinitionId, SemAnalysis, SourceFileId, TraitDefinitionId, TypeParamDefinition,
@@ -50,6 +51,8 @@ pub struct FctDefinition {
     pub is_static: bool,
     pub visibility: Visibility,
     pub is_test: bool,
+    pub is_serial: bool,
+    pub allowed_lints: Vec<Lint>,
     pub internal: bool,
     pub internal_resolved: bool,
     pub param_types: Vec<SourceType>,
@@ -92,6 +95,8 @@ impl FctDefinition {
             visibility: Visibility::from_ast(ast.visibility),
             is_static: ast.is_static,
             is_test: ast.is_test,
+            is_serial: ast.is_serial,
+            allowed_lints: Vec::new(),
             internal: ast.internal,
             internal_resolved: false,
             is_constructor: ast.is_constructor,
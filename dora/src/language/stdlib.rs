@@ -42,6 +42,20 @@ pub fn resolve_internal_annotations(sa: &mut SemAnalysis) {
         Modifier::Test,
     ));
 
+    sa.known.annotations.serial = Some(internal_annotation(
+        sa,
+        stdlib_id,
+        "annotations::serial",
+        Modifier::Serial,
+    ));
+
+    sa.known.annotations.allow = Some(internal_annotation(
+        sa,
+        stdlib_id,
+        "annotations::allow",
+        Modifier::Allow,
+    ));
+
     sa.known.annotations.optimize_immediately = Some(internal_annotation(
         sa,
         stdlib_id,
@@ -120,10 +134,12 @@ pub fn resolve_internal_classes(sa: &mut SemAnalysis) {
     sa.known.classes.thread = Some(find_class(sa, stdlib_id, "thread::Thread"));
 
     sa.known.traits.stringable = Some(find_trait(sa, stdlib_id, "string::Stringable"));
+    sa.known.traits.formattable = Some(find_trait(sa, stdlib_id, "string::Formattable"));
     sa.known.traits.zero = Some(find_trait(sa, stdlib_id, "traits::Zero"));
     sa.known.traits.iterator = Some(find_trait(sa, stdlib_id, "traits::Iterator"));
 
     sa.known.enums.option = Some(find_enum(sa, stdlib_id, "primitives::Option"));
+    sa.known.enums.result = Some(find_enum(sa, stdlib_id, "primitives::Result"));
 }
 
 pub fn fill_prelude(sa: &mut SemAnalysis) {
@@ -142,6 +158,8 @@ pub fn fill_prelude(sa: &mut SemAnalysis) {
         "collections::Vec",
         "print",
         "println",
+        "eprint",
+        "eprintln",
         "primitives::Option",
         "unimplemented",
         "unreachable",
@@ -223,6 +241,24 @@ pub fn discover_known_methods(sa: &mut SemAnalysis) {
         "string::StringBuffer",
         "toString",
     ));
+    sa.known.functions.formatter_from_spec = Some(find_static_method(
+        sa,
+        stdlib_id,
+        "string::Formatter",
+        "fromSpec",
+    ));
+    sa.known.functions.formatter_pad = Some(find_instance_method(
+        sa,
+        stdlib_id,
+        "string::Formatter",
+        "pad",
+    ));
+    sa.known.functions.string_equals = Some(find_instance_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "equals",
+    ));
     sa.known.functions.stacktrace_retrieve = Some(find_instance_method(
         sa,
         stdlib_id,
@@ -372,6 +408,8 @@ pub fn resolve_internal_functions(sa: &mut SemAnalysis) {
 
     native_fct(sa, stdlib_id, "print", stdlib::print as *const u8);
     native_fct(sa, stdlib_id, "println", stdlib::println as *const u8);
+    native_fct(sa, stdlib_id, "eprint", stdlib::eprint as *const u8);
+    native_fct(sa, stdlib_id, "eprintln", stdlib::eprintln as *const u8);
     let fid = intrinsic_fct(sa, stdlib_id, "assert", Intrinsic::Assert);
     sa.known.functions.assert = Some(fid);
     intrinsic_fct(sa, stdlib_id, "debug", Intrinsic::Debug);
@@ -383,6 +421,7 @@ pub fn resolve_internal_functions(sa: &mut SemAnalysis) {
         "forceCollect",
         stdlib::gc_collect as *const u8,
     );
+    native_fct(sa, stdlib_id, "dumpHeap", stdlib::dump_heap as *const u8);
     native_fct(sa, stdlib_id, "timestamp", stdlib::timestamp as *const u8);
     native_fct(
         sa,
@@ -1083,6 +1122,89 @@ pub fn resolve_internal_functions(sa: &mut SemAnalysis) {
         "clone",
         stdlib::str_clone as *const u8,
     );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "find",
+        stdlib::str_find as *const u8,
+    );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "findLast",
+        stdlib::str_find_last as *const u8,
+    );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "trim",
+        stdlib::str_trim as *const u8,
+    );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "trimStart",
+        stdlib::str_trim_start as *const u8,
+    );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "trimEnd",
+        stdlib::str_trim_end as *const u8,
+    );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "replace",
+        stdlib::str_replace as *const u8,
+    );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "toUpperCase",
+        stdlib::str_to_upper_case as *const u8,
+    );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "toLowerCase",
+        stdlib::str_to_lower_case as *const u8,
+    );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "repeatUnchecked",
+        stdlib::str_repeat as *const u8,
+    );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "charSlice",
+        stdlib::str_char_slice as *const u8,
+    );
+    native_method(
+        sa,
+        stdlib_id,
+        "string::String",
+        "charCount",
+        stdlib::str_char_count as *const u8,
+    );
+    native_fct(
+        sa,
+        stdlib_id,
+        "collections::joinStrings",
+        stdlib::str_join as *const u8,
+    );
 
     native_method(
         sa,
@@ -1239,6 +1361,18 @@ pub fn resolve_internal_functions(sa: &mut SemAnalysis) {
         "toString",
         stdlib::float64_to_string as *const u8,
     );
+    native_fct(
+        sa,
+        stdlib_id,
+        "string::formatFloat32",
+        stdlib::float32_format as *const u8,
+    );
+    native_fct(
+        sa,
+        stdlib_id,
+        "string::formatFloat64",
+        stdlib::float64_format as *const u8,
+    );
     intrinsic_method(
         sa,
         stdlib_id,
@@ -1518,6 +1652,27 @@ pub fn resolve_internal_functions(sa: &mut SemAnalysis) {
         stdlib::condition_wakeup_all as *const u8,
     );
 
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::lastErrorKind",
+        stdlib::io::last_error_kind as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::lastErrorCode",
+        stdlib::io::last_error_code as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::lastErrorMessage",
+        stdlib::io::last_error_message as *const u8,
+    );
+
     native_fct(
         sa,
         stdlib_id,
@@ -1546,6 +1701,97 @@ pub fn resolve_internal_functions(sa: &mut SemAnalysis) {
         stdlib::io::write_file_as_bytes as *const u8,
     );
 
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::fileOpen",
+        stdlib::io::file_open as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::fileRead",
+        stdlib::io::file_read as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::fileWrite",
+        stdlib::io::file_write as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::fileSeek",
+        stdlib::io::file_seek as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::fileClose",
+        stdlib::io::file_close as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::fileRemove",
+        stdlib::io::file_remove as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::pathMetadata",
+        stdlib::io::path_metadata as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::pathRename",
+        stdlib::io::path_rename as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::directoryCreate",
+        stdlib::io::directory_create as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::directoryRemove",
+        stdlib::io::directory_remove as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::directoryOpen",
+        stdlib::io::directory_open as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::directoryNext",
+        stdlib::io::directory_next as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::directoryClose",
+        stdlib::io::directory_close as *const u8,
+    );
+
     native_fct(
         sa,
         stdlib_id,
@@ -1588,6 +1834,227 @@ pub fn resolve_internal_functions(sa: &mut SemAnalysis) {
         stdlib::io::socket_accept as *const u8,
     );
 
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::socketLocalAddr",
+        stdlib::io::socket_local_addr as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::socketSetNonBlocking",
+        stdlib::io::socket_set_non_blocking as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::selectorCreate",
+        stdlib::io::selector_create as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::selectorCtl",
+        stdlib::io::selector_ctl as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::selectorWait",
+        stdlib::io::selector_wait as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::selectorClose",
+        stdlib::io::selector_close as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::socketPeerAddr",
+        stdlib::io::socket_peer_addr as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::socketShutdown",
+        stdlib::io::socket_shutdown as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::socketSetReadTimeout",
+        stdlib::io::socket_set_read_timeout as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::udpBind",
+        stdlib::io::udp_bind as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::udpConnect",
+        stdlib::io::udp_connect as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::udpSendTo",
+        stdlib::io::udp_send_to as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::udpRecvFrom",
+        stdlib::io::udp_recv_from as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::unixConnect",
+        stdlib::io::unix_connect as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::unixBind",
+        stdlib::io::unix_bind as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::unixAccept",
+        stdlib::io::unix_accept as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::stdinRead",
+        stdlib::io::stdin_read as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::stdinReadLine",
+        stdlib::io::stdin_read_line as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::stdinReadToString",
+        stdlib::io::stdin_read_to_string as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::stdoutWrite",
+        stdlib::io::stdout_write as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "io::stdoutFlush",
+        stdlib::io::stdout_flush as *const u8,
+    );
+
+    native_fct(sa, stdlib_id, "env::envVar", stdlib::env_var as *const u8);
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "env::envSetVar",
+        stdlib::env_set_var as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "env::envRemoveVar",
+        stdlib::env_remove_var as *const u8,
+    );
+
+    native_fct(sa, stdlib_id, "env::envVars", stdlib::env_vars as *const u8);
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "env::envCurrentDir",
+        stdlib::env_current_dir as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "env::envSetCurrentDir",
+        stdlib::env_set_current_dir as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "process::processSpawn",
+        stdlib::process_spawn as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "process::processWait",
+        stdlib::process_wait as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "process::processKill",
+        stdlib::process_kill as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "process::processClose",
+        stdlib::process_close as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "process::processReadOutput",
+        stdlib::process_read_output as *const u8,
+    );
+
+    native_fct(
+        sa,
+        stdlib_id,
+        "process::id",
+        stdlib::process_id as *const u8,
+    );
+
     let fct_id = intrinsic_method(sa, stdlib_id, "Option", "isNone", Intrinsic::OptionIsNone);
     sa.known.functions.option_is_none = Some(fct_id);
     let fct_id = intrinsic_method(sa, stdlib_id, "Option", "isSome", Intrinsic::OptionIsSome);
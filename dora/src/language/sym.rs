@@ -1,6 +1,7 @@
 use parking_lot::RwLock;
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use self::Sym::*;
@@ -124,6 +125,8 @@ impl ModuleSymTable {
 #[derive(Debug)]
 pub struct SymTable {
     table: HashMap<Name, Sym>,
+    // names defined by use declarations and whether they were looked up
+    imports: HashMap<Name, AtomicBool>,
 }
 
 impl SymTable {
@@ -131,10 +134,15 @@ impl SymTable {
     pub fn new() -> SymTable {
         SymTable {
             table: HashMap::new(),
+            imports: HashMap::new(),
         }
     }
 
     pub fn get(&self, name: Name) -> Option<Sym> {
+        if let Some(used) = self.imports.get(&name) {
+            used.store(true, Ordering::Relaxed);
+        }
+
         self.table.get(&name).cloned()
     }
 
@@ -142,6 +150,18 @@ impl SymTable {
         self.table.insert(name, sym)
     }
 
+    pub fn insert_import(&mut self, name: Name, sym: Sym) -> Option<Sym> {
+        self.imports.insert(name, AtomicBool::new(false));
+        self.table.insert(name, sym)
+    }
+
+    pub fn is_import_used(&self, name: Name) -> bool {
+        self.imports
+            .get(&name)
+            .map(|used| used.load(Ordering::Relaxed))
+            .unwrap_or(true)
+    }
+
     pub fn get_fct(&self, name: Name) -> Option<FctDefinitionId> {
         self.get(name).and_then(|n| n.to_fct())
     }
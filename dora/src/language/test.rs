@@ -21,7 +21,13 @@ pub fn check<F, T>(code: &'static str, f: F) -> T
 where
     F: FnOnce(&SemAnalysis) -> T,
 {
-    let args: Args = Default::default();
+    check_with_args(code, Default::default(), f)
+}
+
+pub fn check_with_args<F, T>(code: &'static str, args: Args, f: F) -> T
+where
+    F: FnOnce(&SemAnalysis) -> T,
+{
     let mut sa = SemAnalysis::new(args);
     sa.test_file_as_string = Some(code);
 
@@ -315,7 +315,7 @@ fn define_use_target(
     let table = module.table.clone();
     let mut table = table.write();
 
-    if let Some(old_sym) = table.insert(name, sym) {
+    if let Some(old_sym) = table.insert_import(name, sym) {
         report_sym_shadow(sa, name, use_file_id, use_pos, old_sym);
         Err(UseError::Fatal)
     } else {
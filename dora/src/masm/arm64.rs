@@ -61,6 +61,16 @@ impl MacroAssembler {
             .ldp_post(REG_FP.into(), REG_LR.into(), REG_SP.into(), 2);
     }
 
+    // Resets the stack pointer to `sp` and returns to the address stored
+    // right below it by the callee's frame record. This returns from the
+    // call that was made with that stack pointer, skipping all frames in
+    // between.
+    pub fn unwind_to(&mut self, sp: Reg) {
+        self.load_mem(MachineMode::Ptr, REG_LR.into(), Mem::Base(sp, -ptr_width()));
+        self.set_sp(sp);
+        self.asm.ret(REG_LR.into());
+    }
+
     pub fn increase_stack_frame(&mut self, size: i32) {
         if size > 0 {
             self.load_int_const(MachineMode::Ptr, REG_TMP1, size as i64);
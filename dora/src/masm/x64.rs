@@ -69,6 +69,14 @@ impl MacroAssembler {
         self.asm.popq_r(RBP.into());
     }
 
+    // Resets the stack pointer to `sp` and returns to the address stored
+    // right below it. This returns from the call that was made with that
+    // stack pointer, skipping all frames in between.
+    pub fn unwind_to(&mut self, sp: Reg) {
+        self.lea(RSP, Mem::Base(sp, -ptr_width()));
+        self.asm.retq();
+    }
+
     pub fn increase_stack_frame(&mut self, size: i32) {
         debug_assert!(size as usize % STACK_FRAME_ALIGNMENT == 0);
 
@@ -197,12 +205,12 @@ impl MacroAssembler {
             MachineMode::Int8 | MachineMode::Int32 => self.asm.cmpl_rr(lhs.into(), rhs.into()),
             _ => unreachable!(),
         }
-        self.asm.setcc_r(Condition::Above, dest.into());
+        self.asm.setcc_r(Condition::Greater, dest.into());
 
         let scratch = self.get_scratch();
         self.asm.movl_ri((*scratch).into(), Immediate(-1));
         self.asm
-            .cmovl(Condition::Below, dest.into(), (*scratch).into());
+            .cmovl(Condition::Less, dest.into(), (*scratch).into());
     }
 
     pub fn float_cmp_int(&mut self, mode: MachineMode, dest: Reg, lhs: FReg, rhs: FReg) {
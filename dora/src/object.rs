@@ -803,6 +803,7 @@ pub fn offset_of_array_data() -> i32 {
 
 pub type UInt8Array = Array<u8>;
 pub type Int32Array = Array<i32>;
+pub type Int64Array = Array<i64>;
 pub type StrArray = Array<Ref<Str>>;
 
 pub fn alloc(vm: &VM, clsid: ClassInstanceId) -> Ref<Obj> {
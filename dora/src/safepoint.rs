@@ -12,11 +12,13 @@ where
     parked_scope(|| {
         let threads = vm.threads.threads.lock();
         if threads.len() == 1 {
+            resolve_allocation_samples(vm);
             let ret = operation(&*threads);
             return ret;
         }
 
         stop_threads(vm, &*threads);
+        resolve_allocation_samples(vm);
         let ret = operation(&*threads);
         resume_threads(vm, &*threads);
 
@@ -24,6 +26,14 @@ where
     })
 }
 
+// Sampled objects are initialized once all threads are stopped. Their class
+// needs to be determined before the collector moves them.
+fn resolve_allocation_samples(vm: &VM) {
+    if let Some(alloc_profiler) = vm.gc.alloc_profiler() {
+        alloc_profiler.resolve_pending();
+    }
+}
+
 fn stop_threads(vm: &VM, threads: &[Arc<DoraThread>]) {
     vm.threads.barrier.arm();
 
@@ -18,6 +18,10 @@ impl NativeStacktrace {
         self.elems.len()
     }
 
+    pub fn entries(&self) -> impl Iterator<Item = (CodeId, u32)> + '_ {
+        self.elems.iter().map(|elem| (elem.fct_id, elem.lineno))
+    }
+
     pub fn push_entry(&mut self, fct_id: CodeId, lineno: u32) {
         self.elems.push(StackElem { fct_id, lineno });
     }
@@ -124,13 +128,17 @@ fn determine_stack_entry(stacktrace: &mut NativeStacktrace, vm: &VM, pc: usize)
     if let Some(code_id) = code_id {
         let code = vm.code_objects.get(code_id);
         match code.descriptor() {
-            CodeKind::DoraFct(_) => {
+            CodeKind::DoraFct(fct_id) => {
                 let offset = pc - code.instruction_start().to_usize();
-                let position = code
-                    .position_for_offset(offset as u32)
-                    .expect("position not found for program point");
 
-                stacktrace.push_entry(code_id, position.line);
+                // Code generated by Boots has no position table yet,
+                // use the function's position instead.
+                let line = match code.position_for_offset(offset as u32) {
+                    Some(position) => position.line,
+                    None => vm.fcts.idx(fct_id).read().pos.line,
+                };
+
+                stacktrace.push_entry(code_id, line);
 
                 true
             }
@@ -150,7 +158,9 @@ fn determine_stack_entry(stacktrace: &mut NativeStacktrace, vm: &VM, pc: usize)
             CodeKind::AllocStub => true,
             CodeKind::DoraStub => false,
 
-            CodeKind::VerifyStub | CodeKind::SafepointStub => unreachable!(),
+            CodeKind::VerifyStub | CodeKind::SafepointStub | CodeKind::PreWriteBarrierStub => {
+                unreachable!()
+            }
         }
     } else {
         println!("no code found at pc = {:x}", pc);
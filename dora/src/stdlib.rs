@@ -1,21 +1,29 @@
 use libc;
 
 use std::char;
-use std::io::Write;
+use std::env;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Write};
 use std::mem;
+use std::os::unix::prelude::{ExitStatusExt, FromRawFd, IntoRawFd};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
 use std::str;
 use std::thread;
 use std::time::Duration;
 
-use crate::gc::{Address, GcReason};
+use crate::gc::{self, Address, GcReason};
 use crate::handle::{handle, handle_scope, Handle};
-use crate::object::{Obj, Ref, Str, UInt8Array};
+use crate::object::{
+    byte_array_from_buffer, Int32Array, Int64Array, Obj, Ref, Str, StrArray, UInt8Array,
+};
+use crate::safepoint;
 use crate::stack::stacktrace_from_last_dtn;
 use crate::threads::{
-    current_thread, deinit_current_thread, init_current_thread, DoraThread, ManagedThread,
-    ThreadState, STACK_SIZE,
+    current_thread, deinit_current_thread, init_current_thread, parked_scope, CaughtFailure,
+    DoraThread, ManagedThread, ThreadState, STACK_SIZE,
 };
-use crate::vm::{get_vm, stack_pointer, ManagedCondition, ManagedMutex, ShapeKind, Trap};
+use crate::vm::{get_vm, stack_pointer, ManagedCondition, ManagedMutex, ShapeKind, Trap, VM};
 
 pub mod io;
 
@@ -73,22 +81,78 @@ pub extern "C" fn float64_to_string(val: f64) -> Ref<Str> {
     })
 }
 
+pub extern "C" fn float32_format(val: f32, precision: i64) -> Ref<Str> {
+    let precision = format_precision(precision);
+
+    handle_scope(|| {
+        let buffer = format!("{:.*}", precision, val);
+        let vm = get_vm();
+
+        Str::from_buffer(vm, buffer.as_bytes())
+    })
+}
+
+pub extern "C" fn float64_format(val: f64, precision: i64) -> Ref<Str> {
+    let precision = format_precision(precision);
+
+    handle_scope(|| {
+        let buffer = format!("{:.*}", precision, val);
+        let vm = get_vm();
+
+        Str::from_buffer(vm, buffer.as_bytes())
+    })
+}
+
+// Rust only supports precisions up to u16::MAX and panics otherwise.
+fn format_precision(precision: i64) -> usize {
+    match u16::try_from(precision) {
+        Ok(precision) => precision as usize,
+        Err(_) => native_fatal_error(format!("precision {} out of range", precision)),
+    }
+}
+
 pub extern "C" fn print(val: Handle<Str>) {
     std::io::stdout().write(val.content()).unwrap();
 }
 
+pub extern "C" fn eprint(val: Handle<Str>) {
+    std::io::stderr().write_all(val.content()).unwrap();
+}
+
 pub extern "C" fn fatal_error(msg: Handle<Str>) {
-    eprint!("fatal error: ");
-    std::io::stderr().write(msg.content()).unwrap();
-    eprintln!("");
+    native_fatal_error(String::from_utf8_lossy(msg.content()).into_owned());
+}
 
+// Fails the same way as `fatalError` in Dora code, natives use this for
+// invalid arguments instead of panicking.
+fn native_fatal_error(message: String) -> ! {
     let vm = get_vm();
+    report_failure(vm, format!("fatal error: {}", message));
+
+    std::process::exit(1);
+}
+
+// Prints the message and the current stack trace. When a test runner is
+// catching failures on this thread, the failure is handed to it instead
+// and this function doesn't return.
+fn report_failure(vm: &VM, message: String) {
     let stacktrace = stacktrace_from_last_dtn(vm);
+    let thread = current_thread();
+
+    if thread.is_catching_failures() {
+        thread.unwind_failure(
+            vm,
+            CaughtFailure {
+                message,
+                stacktrace,
+            },
+        );
+    }
+
+    eprintln!("{}", message);
     let stderr = std::io::stderr();
     let mut stderr = stderr.lock();
     stacktrace.dump(vm, &mut stderr).expect("output broken");
-
-    std::process::exit(1);
 }
 
 pub extern "C" fn abort() {
@@ -97,18 +161,21 @@ pub extern "C" fn abort() {
 }
 
 pub extern "C" fn exit(status: i32) {
+    let vm = get_vm();
+
+    if let Some(alloc_profiler) = vm.gc.alloc_profiler() {
+        // Other threads might still be running and need to be stopped
+        // before their samples can be resolved.
+        safepoint::stop_the_world(vm, |_threads| alloc_profiler.resolve_pending());
+        alloc_profiler.write(vm);
+    }
+
     std::process::exit(status);
 }
 
 pub extern "C" fn unreachable() {
     let vm = get_vm();
-
-    eprintln!("unreachable code executed.");
-
-    let stacktrace = stacktrace_from_last_dtn(vm);
-    let stderr = std::io::stderr();
-    let mut stderr = stderr.lock();
-    stacktrace.dump(vm, &mut stderr).expect("output broken");
+    report_failure(vm, "unreachable code executed.".into());
 
     std::process::exit(1);
 }
@@ -126,6 +193,13 @@ pub extern "C" fn println(val: Handle<Str>) {
     handle.write(b"\n").unwrap();
 }
 
+pub extern "C" fn eprintln(val: Handle<Str>) {
+    let stderr = std::io::stderr();
+    let mut handle = stderr.lock();
+    handle.write_all(val.content()).unwrap();
+    handle.write_all(b"\n").unwrap();
+}
+
 pub extern "C" fn sleep(seconds: i32) {
     assert!(seconds >= 0);
     thread::sleep(Duration::from_secs(seconds as u64));
@@ -164,6 +238,123 @@ pub extern "C" fn str_from_bytes(val: Handle<UInt8Array>, offset: usize, len: us
     })
 }
 
+// Returns the byte offset of the first occurrence of `needle` at or after
+// `start`, -1 if there is none.
+pub extern "C" fn str_find(val: Handle<Str>, needle: Handle<Str>, start: i64) -> i64 {
+    let value = val.content_utf8();
+
+    match value.get(start.max(0) as usize..) {
+        Some(rest) => rest
+            .find(needle.content_utf8())
+            .map_or(-1, |idx| idx as i64 + start.max(0)),
+        None => -1,
+    }
+}
+
+pub extern "C" fn str_find_last(val: Handle<Str>, needle: Handle<Str>) -> i64 {
+    val.content_utf8()
+        .rfind(needle.content_utf8())
+        .map_or(-1, |idx| idx as i64)
+}
+
+// The results are copied out of the string before allocating, since the
+// allocation may move the string.
+pub extern "C" fn str_trim(val: Handle<Str>) -> Ref<Str> {
+    let result = String::from(val.content_utf8().trim());
+    Str::from_buffer(get_vm(), result.as_bytes())
+}
+
+pub extern "C" fn str_trim_start(val: Handle<Str>) -> Ref<Str> {
+    let result = String::from(val.content_utf8().trim_start());
+    Str::from_buffer(get_vm(), result.as_bytes())
+}
+
+pub extern "C" fn str_trim_end(val: Handle<Str>) -> Ref<Str> {
+    let result = String::from(val.content_utf8().trim_end());
+    Str::from_buffer(get_vm(), result.as_bytes())
+}
+
+pub extern "C" fn str_replace(val: Handle<Str>, from: Handle<Str>, to: Handle<Str>) -> Ref<Str> {
+    let result = val
+        .content_utf8()
+        .replace(from.content_utf8(), to.content_utf8());
+    Str::from_buffer(get_vm(), result.as_bytes())
+}
+
+pub extern "C" fn str_to_upper_case(val: Handle<Str>) -> Ref<Str> {
+    let result = val.content_utf8().to_uppercase();
+    Str::from_buffer(get_vm(), result.as_bytes())
+}
+
+pub extern "C" fn str_to_lower_case(val: Handle<Str>) -> Ref<Str> {
+    let result = val.content_utf8().to_lowercase();
+    Str::from_buffer(get_vm(), result.as_bytes())
+}
+
+pub extern "C" fn str_repeat(val: Handle<Str>, count: i64) -> Ref<Str> {
+    // The result has to fit into the heap, Rust would otherwise panic or
+    // abort while building it.
+    let vm = get_vm();
+    let size = usize::try_from(count)
+        .ok()
+        .and_then(|count| val.content().len().checked_mul(count));
+
+    if !matches!(size, Some(size) if size <= vm.args.max_heap_size()) {
+        native_fatal_error(format!("repeat count {} too large", count));
+    }
+
+    let result = val.content().repeat(count as usize);
+    Str::from_buffer(vm, &result)
+}
+
+// Returns the characters from `start` up to `end` (exclusive), null if the
+// range is out of bounds.
+pub extern "C" fn str_char_slice(val: Handle<Str>, start: i64, end: i64) -> Ref<Str> {
+    if start < 0 || start > end {
+        return Ref::null();
+    }
+
+    let value = val.content_utf8();
+    let mut offsets = value
+        .char_indices()
+        .map(|(idx, _)| idx)
+        .chain(std::iter::once(value.len()));
+
+    let start_offset = offsets.nth(start as usize);
+    let end_offset = if start == end {
+        start_offset
+    } else {
+        offsets.nth((end - start - 1) as usize)
+    };
+
+    match (start_offset, end_offset) {
+        (Some(start), Some(end)) => {
+            let result = String::from(&value[start..end]);
+            Str::from_buffer(get_vm(), result.as_bytes())
+        }
+        _ => Ref::null(),
+    }
+}
+
+pub extern "C" fn str_char_count(val: Handle<Str>) -> i64 {
+    val.content_utf8().chars().count() as i64
+}
+
+// Joins the first `len` strings of the array.
+pub extern "C" fn str_join(parts: Handle<StrArray>, len: i64, separator: Handle<Str>) -> Ref<Str> {
+    let mut result = Vec::new();
+
+    for (idx, part) in parts.slice()[..len as usize].iter().enumerate() {
+        if idx > 0 {
+            result.extend_from_slice(separator.content());
+        }
+
+        result.extend_from_slice(part.content());
+    }
+
+    Str::from_buffer(get_vm(), &result)
+}
+
 pub extern "C" fn gc_alloc(size: usize, array_ref: bool) -> *mut Obj {
     let vm = get_vm();
     vm.gc.alloc(vm, size, array_ref).to_mut_ptr()
@@ -179,6 +370,12 @@ pub extern "C" fn gc_minor_collect() {
     vm.gc.minor_collect(vm, GcReason::ForceMinorCollect);
 }
 
+pub extern "C" fn dump_heap(path: Handle<Str>) -> bool {
+    let vm = get_vm();
+    let path = PathBuf::from(path.content_utf8());
+    gc::dump_heap(vm, &path).is_ok()
+}
+
 pub extern "C" fn argc() -> i32 {
     let vm = get_vm();
 
@@ -203,6 +400,244 @@ pub extern "C" fn argv(ind: i32) -> Ref<Str> {
     panic!("argument does not exist");
 }
 
+// Returns null if the variable isn't set or isn't valid unicode.
+pub extern "C" fn env_var(name: Handle<Str>) -> Ref<Str> {
+    match env::var(name.content_utf8()) {
+        Ok(value) => Str::from_buffer(get_vm(), value.as_bytes()),
+        Err(_) => Ref::null(),
+    }
+}
+
+fn is_valid_env_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains('=') && !name.contains('\0')
+}
+
+pub extern "C" fn env_set_var(name: Handle<Str>, value: Handle<Str>) -> bool {
+    let name = name.content_utf8();
+    let value = value.content_utf8();
+
+    if !is_valid_env_name(name) || value.contains('\0') {
+        io::set_last_error(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            "invalid environment variable",
+        ));
+        return false;
+    }
+
+    env::set_var(name, value);
+    true
+}
+
+pub extern "C" fn env_remove_var(name: Handle<Str>) -> bool {
+    let name = name.content_utf8();
+
+    if !is_valid_env_name(name) {
+        io::set_last_error(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            "invalid environment variable",
+        ));
+        return false;
+    }
+
+    env::remove_var(name);
+    true
+}
+
+// Returns all variables as "NAME=VALUE" entries, each terminated by a NUL
+// byte. Entries that aren't valid unicode are skipped.
+pub extern "C" fn env_vars() -> Ref<Str> {
+    let mut buffer = String::new();
+
+    for (name, value) in env::vars_os() {
+        if let (Some(name), Some(value)) = (name.to_str(), value.to_str()) {
+            buffer.push_str(name);
+            buffer.push('=');
+            buffer.push_str(value);
+            buffer.push('\0');
+        }
+    }
+
+    Str::from_buffer(get_vm(), buffer.as_bytes())
+}
+
+pub extern "C" fn env_current_dir() -> Ref<Str> {
+    match env::current_dir() {
+        Ok(path) => Str::from_buffer(get_vm(), path.to_string_lossy().as_bytes()),
+        Err(err) => {
+            io::set_last_error(err);
+            Ref::null()
+        }
+    }
+}
+
+pub extern "C" fn env_set_current_dir(path: Handle<Str>) -> bool {
+    match env::set_current_dir(path.content_utf8()) {
+        Ok(()) => true,
+        Err(err) => {
+            io::set_last_error(err);
+            false
+        }
+    }
+}
+
+// Modes for the standard streams of a spawned process, need to be kept in
+// sync with process.dora.
+const STDIO_INHERIT: i32 = 0;
+const STDIO_PIPED: i32 = 1;
+const STDIO_NULL: i32 = 2;
+
+fn stdio_for_mode(mode: i32) -> Stdio {
+    match mode {
+        STDIO_PIPED => Stdio::piped(),
+        STDIO_NULL => Stdio::null(),
+        _ => {
+            assert_eq!(mode, STDIO_INHERIT);
+            Stdio::inherit()
+        }
+    }
+}
+
+// Spawns the program and returns a handle for process_wait and process_kill,
+// 0 if the process couldn't be started. `env` holds alternating names and
+// values, an empty `cwd` keeps the current directory. On success `info` holds
+// the file descriptors of the stdin, stdout and stderr pipes (-1 if not piped)
+// and the process id.
+pub extern "C" fn process_spawn(
+    program: Handle<Str>,
+    args: Handle<StrArray>,
+    env: Handle<StrArray>,
+    clear_env: bool,
+    cwd: Handle<Str>,
+    stdio: Handle<Int32Array>,
+    mut info: Handle<Int32Array>,
+) -> i64 {
+    let mut command = Command::new(program.content_utf8());
+
+    for arg in args.slice() {
+        command.arg(arg.content_utf8());
+    }
+
+    if clear_env {
+        command.env_clear();
+    }
+
+    for pair in env.slice().chunks(2) {
+        command.env(pair[0].content_utf8(), pair[1].content_utf8());
+    }
+
+    let cwd = cwd.content_utf8();
+
+    if !cwd.is_empty() {
+        command.current_dir(cwd);
+    }
+
+    let stdio = stdio.slice();
+    command
+        .stdin(stdio_for_mode(stdio[0]))
+        .stdout(stdio_for_mode(stdio[1]))
+        .stderr(stdio_for_mode(stdio[2]));
+
+    let mut child = match parked_scope(|| command.spawn()) {
+        Ok(child) => child,
+        Err(err) => {
+            io::set_last_error(err);
+            return 0;
+        }
+    };
+
+    info.set_at(0, child.stdin.take().map_or(-1, |pipe| pipe.into_raw_fd()));
+    info.set_at(1, child.stdout.take().map_or(-1, |pipe| pipe.into_raw_fd()));
+    info.set_at(2, child.stderr.take().map_or(-1, |pipe| pipe.into_raw_fd()));
+    info.set_at(3, child.id() as i32);
+
+    Box::into_raw(Box::new(child)) as i64
+}
+
+// Waits for the process to exit. `status` receives 1 and the exit code if
+// the process exited normally, 0 and the signal number if it was killed.
+pub extern "C" fn process_wait(handle: i64, mut status: Handle<Int32Array>) -> bool {
+    let child = unsafe { &mut *(handle as *mut Child) };
+
+    let exit_status = match parked_scope(|| child.wait()) {
+        Ok(exit_status) => exit_status,
+        Err(err) => {
+            io::set_last_error(err);
+            return false;
+        }
+    };
+
+    if let Some(code) = exit_status.code() {
+        status.set_at(0, 1);
+        status.set_at(1, code);
+    } else {
+        status.set_at(0, 0);
+        status.set_at(1, exit_status.signal().unwrap_or(0));
+    }
+
+    true
+}
+
+pub extern "C" fn process_kill(handle: i64) -> bool {
+    let child = unsafe { &mut *(handle as *mut Child) };
+
+    match child.kill() {
+        Ok(()) => true,
+        Err(err) => {
+            io::set_last_error(err);
+            false
+        }
+    }
+}
+
+pub extern "C" fn process_id() -> i32 {
+    std::process::id() as i32
+}
+
+pub extern "C" fn process_close(handle: i64) {
+    let child = unsafe { Box::from_raw(handle as *mut Child) };
+    std::mem::drop(child);
+}
+
+// Reads stdout and stderr of a process until both are closed and closes the
+// file descriptors afterwards. Both pipes are drained concurrently, so the
+// process can't block on a full pipe. Returns the stdout data followed by the
+// stderr data, `stdout_len` receives the length of the stdout data.
+pub extern "C" fn process_read_output(
+    stdout: i32,
+    stderr: i32,
+    mut stdout_len: Handle<Int64Array>,
+) -> Ref<UInt8Array> {
+    let result = parked_scope(|| {
+        let mut stdout = unsafe { File::from_raw_fd(stdout) };
+        let mut stderr = unsafe { File::from_raw_fd(stderr) };
+
+        thread::scope(|scope| {
+            let stderr_thread = scope.spawn(move || {
+                let mut data = Vec::new();
+                stderr.read_to_end(&mut data).map(|_| data)
+            });
+
+            let mut data = Vec::new();
+            let stdout_result = stdout.read_to_end(&mut data);
+            let stderr_result = stderr_thread.join().expect("reading stderr failed");
+
+            stdout_result.and_then(|_| stderr_result.map(|stderr_data| (data, stderr_data)))
+        })
+    });
+
+    match result {
+        Ok((mut data, stderr_data)) => {
+            stdout_len.set_at(0, data.len() as i64);
+            data.extend_from_slice(&stderr_data);
+            byte_array_from_buffer(get_vm(), &data)
+        }
+        Err(err) => {
+            io::set_last_error(err);
+            Ref::null()
+        }
+    }
+}
+
 pub extern "C" fn str_to_int32_success(val: Handle<Str>) -> bool {
     let slice = val.content();
     let val = str::from_utf8(slice).unwrap();
@@ -275,11 +710,8 @@ pub extern "C" fn trap(trap_id: u32) {
         Trap::OVERFLOW => "overflow",
     };
 
-    eprintln!("{}", msg);
-    let stacktrace = stacktrace_from_last_dtn(vm);
-    let stderr = std::io::stderr();
-    let mut stderr = stderr.lock();
-    stacktrace.dump(vm, &mut stderr).expect("output broken");
+    report_failure(vm, msg.into());
+
     unsafe {
         libc::_exit(100 + trap_id as i32);
     }
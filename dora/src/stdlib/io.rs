@@ -1,15 +1,88 @@
-use std::fs::File;
-use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions, ReadDir};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::os::unix::net::{UnixListener, UnixStream};
 use std::os::unix::prelude::{FromRawFd, IntoRawFd};
 use std::str::FromStr;
+use std::time::{Duration, UNIX_EPOCH};
 use std::{fs, path::PathBuf};
 
 use crate::handle::{handle_scope, Handle};
-use crate::object::{byte_array_from_buffer, Ref, Str, UInt8Array};
+use crate::object::{byte_array_from_buffer, Int32Array, Int64Array, Ref, Str, UInt8Array};
 use crate::threads::parked_scope;
 use crate::vm::get_vm;
 
+thread_local! {
+    // Error of the last failed I/O operation on this thread, the natives only
+    // signal failure in their return value.
+    static LAST_ERROR: RefCell<Option<io::Error>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn set_last_error(err: io::Error) {
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(err));
+}
+
+fn invalid_range() -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, "range out of bounds")
+}
+
+// Converts offset and length of a range in an array, None if the range is
+// negative or doesn't fit into the array.
+fn checked_range(array_len: usize, offset: i64, len: i64) -> Option<(usize, usize)> {
+    let offset = usize::try_from(offset).ok()?;
+    let len = usize::try_from(len).ok()?;
+
+    if offset.checked_add(len)? > array_len {
+        return None;
+    }
+
+    Some((offset, len))
+}
+
+// Kinds of IoError, need to be kept in sync with io.dora.
+const IO_ERROR_NOT_FOUND: i32 = 0;
+const IO_ERROR_PERMISSION_DENIED: i32 = 1;
+const IO_ERROR_ADDR_IN_USE: i32 = 2;
+const IO_ERROR_CONNECTION_REFUSED: i32 = 3;
+const IO_ERROR_INTERRUPTED: i32 = 4;
+const IO_ERROR_WOULD_BLOCK: i32 = 5;
+const IO_ERROR_OTHER: i32 = 6;
+
+pub extern "C" fn last_error_kind() -> i32 {
+    LAST_ERROR.with(
+        |last_error| match last_error.borrow().as_ref().map(|err| err.kind()) {
+            Some(ErrorKind::NotFound) => IO_ERROR_NOT_FOUND,
+            Some(ErrorKind::PermissionDenied) => IO_ERROR_PERMISSION_DENIED,
+            Some(ErrorKind::AddrInUse) => IO_ERROR_ADDR_IN_USE,
+            Some(ErrorKind::ConnectionRefused) => IO_ERROR_CONNECTION_REFUSED,
+            Some(ErrorKind::Interrupted) => IO_ERROR_INTERRUPTED,
+            Some(ErrorKind::WouldBlock) => IO_ERROR_WOULD_BLOCK,
+            _ => IO_ERROR_OTHER,
+        },
+    )
+}
+
+pub extern "C" fn last_error_code() -> i32 {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .and_then(|err| err.raw_os_error())
+            .unwrap_or(0)
+    })
+}
+
+pub extern "C" fn last_error_message() -> Ref<Str> {
+    let message = LAST_ERROR.with(|last_error| match *last_error.borrow() {
+        Some(ref err) => err.to_string(),
+        None => String::from("unknown error"),
+    });
+
+    let vm = get_vm();
+    Str::from_buffer(vm, message.as_bytes())
+}
+
 pub extern "C" fn read_file_as_string(name: Handle<Str>) -> Ref<Str> {
     handle_scope(|| {
         let path = PathBuf::from_str(name.content_utf8());
@@ -17,11 +90,13 @@ pub extern "C" fn read_file_as_string(name: Handle<Str>) -> Ref<Str> {
             return Ref::null();
         }
         let path = path.unwrap();
-        let content = parked_scope(|| fs::read_to_string(path));
-        if content.is_err() {
-            return Ref::null();
-        }
-        let content = content.unwrap();
+        let content = match parked_scope(|| fs::read_to_string(path)) {
+            Ok(content) => content,
+            Err(err) => {
+                set_last_error(err);
+                return Ref::null();
+            }
+        };
 
         let vm = get_vm();
         Str::from_buffer(vm, content.as_bytes())
@@ -35,24 +110,20 @@ pub extern "C" fn read_file_as_bytes(name: Handle<Str>) -> Ref<UInt8Array> {
             return Ref::null();
         }
         let path = path.unwrap();
-        let content: Option<Vec<u8>> = parked_scope(|| {
-            let f = File::open(&path);
-            if f.is_err() {
-                return None;
-            }
-            let mut f = f.unwrap();
+        let content: io::Result<Vec<u8>> = parked_scope(|| {
+            let mut f = File::open(&path)?;
             let mut buffer = Vec::new();
-            match f.read_to_end(&mut buffer) {
-                Ok(_) => Some(buffer),
-                Err(_) => None,
-            }
+            f.read_to_end(&mut buffer)?;
+            Ok(buffer)
         });
 
-        if content.is_none() {
-            return Ref::null();
-        }
-
-        let content = content.unwrap();
+        let content = match content {
+            Ok(content) => content,
+            Err(err) => {
+                set_last_error(err);
+                return Ref::null();
+            }
+        };
 
         let vm = get_vm();
         byte_array_from_buffer(vm, &content)
@@ -80,38 +151,348 @@ fn write_file_common(name: Handle<Str>, content: Vec<u8>) -> bool {
         Ok(())
     });
 
-    if result.is_ok() {
-        true
-    } else {
-        false
+    match result {
+        Ok(()) => true,
+        Err(err) => {
+            set_last_error(err);
+            false
+        }
     }
 }
 
-pub extern "C" fn socket_connect(addr: Handle<Str>) -> i32 {
-    let addr = String::from(addr.content_utf8());
+// Modes for file_open, need to be kept in sync with io.dora.
+const FILE_MODE_READ: i32 = 0;
+const FILE_MODE_WRITE: i32 = 1;
+const FILE_MODE_APPEND: i32 = 2;
+const FILE_MODE_READ_WRITE: i32 = 3;
+
+pub extern "C" fn file_open(name: Handle<Str>, mode: i32) -> i32 {
+    let path = String::from(name.content_utf8());
+    let mut options = OpenOptions::new();
+
+    match mode {
+        FILE_MODE_READ => options.read(true),
+        FILE_MODE_WRITE => options.write(true).create(true).truncate(true),
+        FILE_MODE_APPEND => options.append(true).create(true),
+        FILE_MODE_READ_WRITE => options.read(true).write(true).create(true),
+        _ => {
+            set_last_error(io::Error::new(ErrorKind::InvalidInput, "invalid mode"));
+            return -1;
+        }
+    };
+
+    parked_scope(|| match options.open(&path) {
+        Ok(file) => file.into_raw_fd(),
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    })
+}
+
+pub extern "C" fn file_read(fd: i32, mut array: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    let (offset, len) = match checked_range(array.slice().len(), offset, len) {
+        Some(range) => range,
+        None => {
+            set_last_error(invalid_range());
+            return -1;
+        }
+    };
+
+    let mut buffer = vec![0; len];
+
+    let bytes = parked_scope(|| {
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        let bytes = match file.read(&mut buffer) {
+            Ok(bytes) => bytes as i64,
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
+        };
+        std::mem::forget(file);
+        bytes
+    });
+
+    for (i, &byte) in buffer.iter().take(bytes.max(0) as usize).enumerate() {
+        array.set_at(offset + i, byte);
+    }
+
+    bytes
+}
+
+pub extern "C" fn file_write(fd: i32, array: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    let (offset, len) = match checked_range(array.slice().len(), offset, len) {
+        Some(range) => range,
+        None => {
+            set_last_error(invalid_range());
+            return -1;
+        }
+    };
+
+    let buffer = Vec::from(&array.slice()[offset..offset + len]);
     parked_scope(|| {
-        if let Ok(stream) = TcpStream::connect(&addr) {
-            stream.into_raw_fd() as i32
-        } else {
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        let bytes = match file.write(&buffer) {
+            Ok(bytes) => bytes as i64,
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
+        };
+        std::mem::forget(file);
+        bytes
+    })
+}
+
+pub extern "C" fn file_seek(fd: i32, offset: i64, whence: i32) -> i64 {
+    let pos = match whence {
+        0 if offset >= 0 => SeekFrom::Start(offset as u64),
+        1 => SeekFrom::Current(offset),
+        2 => SeekFrom::End(offset),
+        _ => {
+            set_last_error(io::Error::new(ErrorKind::InvalidInput, "invalid seek"));
+            return -1;
+        }
+    };
+
+    parked_scope(|| {
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        let result = match file.seek(pos) {
+            Ok(position) => position as i64,
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
+        };
+        std::mem::forget(file);
+        result
+    })
+}
+
+pub extern "C" fn file_close(fd: i32) {
+    parked_scope(|| {
+        let file = unsafe { File::from_raw_fd(fd) };
+        std::mem::drop(file)
+    });
+}
+
+pub extern "C" fn stdin_read(mut array: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    let (offset, len) = match checked_range(array.slice().len(), offset, len) {
+        Some(range) => range,
+        None => {
+            set_last_error(invalid_range());
+            return -1;
+        }
+    };
+
+    let mut buffer = vec![0; len];
+
+    let bytes = parked_scope(|| match io::stdin().read(&mut buffer) {
+        Ok(bytes) => bytes as i64,
+        Err(err) => {
+            set_last_error(err);
             -1
         }
+    });
+
+    for (i, &byte) in buffer.iter().take(bytes.max(0) as usize).enumerate() {
+        array.set_at(offset + i, byte);
+    }
+
+    bytes
+}
+
+// Returns the next line including its terminator, an empty string at the end
+// of the input.
+pub extern "C" fn stdin_read_line() -> Ref<Str> {
+    let mut line = String::new();
+
+    match parked_scope(|| io::stdin().read_line(&mut line)) {
+        Ok(_) => Str::from_buffer(get_vm(), line.as_bytes()),
+        Err(err) => {
+            set_last_error(err);
+            Ref::null()
+        }
+    }
+}
+
+pub extern "C" fn stdin_read_to_string() -> Ref<Str> {
+    let mut content = String::new();
+
+    match parked_scope(|| io::stdin().read_to_string(&mut content)) {
+        Ok(_) => Str::from_buffer(get_vm(), content.as_bytes()),
+        Err(err) => {
+            set_last_error(err);
+            Ref::null()
+        }
+    }
+}
+
+// Writes through the same buffer as print and println, so the output of both
+// isn't reordered.
+pub extern "C" fn stdout_write(array: Handle<UInt8Array>, offset: i64, len: i64) -> bool {
+    let (offset, len) = match checked_range(array.slice().len(), offset, len) {
+        Some(range) => range,
+        None => {
+            set_last_error(invalid_range());
+            return false;
+        }
+    };
+
+    let buffer = Vec::from(&array.slice()[offset..offset + len]);
+    parked_scope(|| succeeded(io::stdout().write_all(&buffer)))
+}
+
+pub extern "C" fn stdout_flush() -> bool {
+    parked_scope(|| succeeded(io::stdout().flush()))
+}
+
+pub extern "C" fn file_remove(name: Handle<Str>) -> bool {
+    let path = String::from(name.content_utf8());
+    succeeded(parked_scope(|| fs::remove_file(&path)))
+}
+
+fn succeeded(result: io::Result<()>) -> bool {
+    match result {
+        Ok(()) => true,
+        Err(err) => {
+            set_last_error(err);
+            false
+        }
+    }
+}
+
+// Stores whether the path is a directory, its size and modification time in
+// `data`.
+pub extern "C" fn path_metadata(name: Handle<Str>, mut data: Handle<Int64Array>) -> bool {
+    let path = String::from(name.content_utf8());
+
+    if data.len() < 3 {
+        set_last_error(invalid_range());
+        return false;
+    }
+
+    let metadata = parked_scope(|| fs::metadata(&path));
+
+    let metadata = match metadata {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            set_last_error(err);
+            return false;
+        }
+    };
+
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos() as i64)
+        .unwrap_or(0);
+
+    data.set_at(0, if metadata.is_dir() { 1 } else { 0 });
+    data.set_at(1, metadata.len() as i64);
+    data.set_at(2, modified);
+
+    true
+}
+
+pub extern "C" fn path_rename(name: Handle<Str>, new_name: Handle<Str>) -> bool {
+    let path = String::from(name.content_utf8());
+    let new_path = String::from(new_name.content_utf8());
+    succeeded(parked_scope(|| fs::rename(&path, &new_path)))
+}
+
+pub extern "C" fn directory_create(name: Handle<Str>, recursive: bool) -> bool {
+    let path = String::from(name.content_utf8());
+    succeeded(parked_scope(|| {
+        if recursive {
+            fs::create_dir_all(&path)
+        } else {
+            fs::create_dir(&path)
+        }
+    }))
+}
+
+pub extern "C" fn directory_remove(name: Handle<Str>, recursive: bool) -> bool {
+    let path = String::from(name.content_utf8());
+    succeeded(parked_scope(|| {
+        if recursive {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_dir(&path)
+        }
+    }))
+}
+
+// Returns a handle for iterating the directory entries with directory_next,
+// 0 if the directory can't be read.
+pub extern "C" fn directory_open(name: Handle<Str>) -> i64 {
+    let path = String::from(name.content_utf8());
+    parked_scope(|| match fs::read_dir(&path) {
+        Ok(entries) => Box::into_raw(Box::new(entries)) as i64,
+        Err(err) => {
+            set_last_error(err);
+            0
+        }
     })
 }
 
-pub extern "C" fn socket_write(fd: i32, array: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
-    let offset = offset as usize;
-    let len = len as usize;
+pub extern "C" fn directory_next(handle: i64) -> Ref<Str> {
+    let entries = unsafe { &mut *(handle as *mut ReadDir) };
 
-    if offset + len > array.slice().len() {
-        return -1;
+    let name = parked_scope(|| loop {
+        match entries.next() {
+            Some(Ok(entry)) => break Some(entry.file_name()),
+            // Skip entries that vanished or can't be read.
+            Some(Err(_)) => continue,
+            None => break None,
+        }
+    });
+
+    match name {
+        Some(name) => {
+            let vm = get_vm();
+            Str::from_buffer(vm, name.to_string_lossy().as_bytes())
+        }
+        None => Ref::null(),
     }
+}
+
+pub extern "C" fn directory_close(handle: i64) {
+    let entries = unsafe { Box::from_raw(handle as *mut ReadDir) };
+    std::mem::drop(entries);
+}
+
+pub extern "C" fn socket_connect(addr: Handle<Str>) -> i32 {
+    let addr = String::from(addr.content_utf8());
+    parked_scope(|| match TcpStream::connect(&addr) {
+        Ok(stream) => stream.into_raw_fd(),
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    })
+}
+
+pub extern "C" fn socket_write(fd: i32, array: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    let (offset, len) = match checked_range(array.slice().len(), offset, len) {
+        Some(range) => range,
+        None => {
+            set_last_error(invalid_range());
+            return -1;
+        }
+    };
 
     let buffer = Vec::from(&array.slice()[offset..offset + len]);
     parked_scope(|| {
         let mut stream = unsafe { TcpStream::from_raw_fd(fd) };
         let bytes = match stream.write(&buffer) {
             Ok(bytes) => bytes as i64,
-            Err(_) => -1,
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
         };
         std::mem::forget(stream);
         bytes
@@ -124,12 +505,13 @@ pub extern "C" fn socket_read(
     offset: i64,
     len: i64,
 ) -> i64 {
-    let offset = offset as usize;
-    let len = len as usize;
-
-    if offset + len > array.slice().len() {
-        return -1;
-    }
+    let (offset, len) = match checked_range(array.slice().len(), offset, len) {
+        Some(range) => range,
+        None => {
+            set_last_error(invalid_range());
+            return -1;
+        }
+    };
 
     let mut buffer = vec![0; len];
 
@@ -137,7 +519,10 @@ pub extern "C" fn socket_read(
         let mut stream = unsafe { TcpStream::from_raw_fd(fd) };
         let bytes = match stream.read(&mut buffer) {
             Ok(bytes) => bytes as i64,
-            Err(_) => -1,
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
         };
         std::mem::forget(stream);
         bytes
@@ -163,10 +548,10 @@ pub extern "C" fn socket_close(fd: i32) {
 
 pub extern "C" fn socket_bind(addr: Handle<Str>) -> i32 {
     let addr = String::from(addr.content_utf8());
-    parked_scope(|| {
-        if let Ok(stream) = TcpListener::bind(&addr) {
-            stream.into_raw_fd() as i32
-        } else {
+    parked_scope(|| match TcpListener::bind(&addr) {
+        Ok(listener) => listener.into_raw_fd(),
+        Err(err) => {
+            set_last_error(err);
             -1
         }
     })
@@ -175,10 +560,399 @@ pub extern "C" fn socket_bind(addr: Handle<Str>) -> i32 {
 pub extern "C" fn socket_accept(fd: i32) -> i32 {
     parked_scope(|| {
         let listener = unsafe { TcpListener::from_raw_fd(fd) };
-        let result = if let Ok((stream, _)) = listener.accept() {
-            stream.into_raw_fd() as i32
-        } else {
+        let result = match listener.accept() {
+            Ok((stream, _)) => stream.into_raw_fd(),
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
+        };
+        std::mem::forget(listener);
+        result
+    })
+}
+
+// Works for TCP and UDP sockets.
+pub extern "C" fn socket_local_addr(fd: i32) -> Ref<Str> {
+    let listener = unsafe { TcpListener::from_raw_fd(fd) };
+    let addr = listener.local_addr();
+    std::mem::forget(listener);
+
+    socket_addr_to_str(addr)
+}
+
+fn socket_addr_to_str(addr: io::Result<SocketAddr>) -> Ref<Str> {
+    match addr {
+        Ok(addr) => {
+            let vm = get_vm();
+            Str::from_buffer(vm, addr.to_string().as_bytes())
+        }
+        Err(err) => {
+            set_last_error(err);
+            Ref::null()
+        }
+    }
+}
+
+pub extern "C" fn socket_peer_addr(fd: i32) -> Ref<Str> {
+    let stream = unsafe { TcpStream::from_raw_fd(fd) };
+    let addr = stream.peer_addr();
+    std::mem::forget(stream);
+
+    socket_addr_to_str(addr)
+}
+
+// Shutdown modes, need to be kept in sync with io.dora.
+const SHUTDOWN_READ: i32 = 0;
+const SHUTDOWN_WRITE: i32 = 1;
+const SHUTDOWN_BOTH: i32 = 2;
+
+// Works for both TCP and Unix domain sockets.
+pub extern "C" fn socket_shutdown(fd: i32, how: i32) -> bool {
+    let how = match how {
+        SHUTDOWN_READ => Shutdown::Read,
+        SHUTDOWN_WRITE => Shutdown::Write,
+        SHUTDOWN_BOTH => Shutdown::Both,
+        _ => {
+            set_last_error(io::Error::new(ErrorKind::InvalidInput, "invalid shutdown"));
+            return false;
+        }
+    };
+
+    let stream = unsafe { TcpStream::from_raw_fd(fd) };
+    let result = stream.shutdown(how);
+    std::mem::forget(stream);
+
+    succeeded(result)
+}
+
+// Sets the read timeout of any socket in milliseconds, 0 disables it.
+pub extern "C" fn socket_set_read_timeout(fd: i32, timeout_ms: i64) -> bool {
+    let timeout = if timeout_ms > 0 {
+        Some(Duration::from_millis(timeout_ms as u64))
+    } else {
+        None
+    };
+
+    let stream = unsafe { TcpStream::from_raw_fd(fd) };
+    let result = stream.set_read_timeout(timeout);
+    std::mem::forget(stream);
+
+    succeeded(result)
+}
+
+// Works for both listening and connected sockets.
+pub extern "C" fn socket_set_non_blocking(fd: i32, non_blocking: bool) -> bool {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+
+    if flags == -1 {
+        set_last_error(io::Error::last_os_error());
+        return false;
+    }
+
+    let flags = if non_blocking {
+        flags | libc::O_NONBLOCK
+    } else {
+        flags & !libc::O_NONBLOCK
+    };
+
+    if unsafe { libc::fcntl(fd, libc::F_SETFL, flags) } == -1 {
+        set_last_error(io::Error::last_os_error());
+        return false;
+    }
+
+    true
+}
+
+// Interest and event flags of the selector, need to be kept in sync with
+// io.dora.
+const SELECTOR_READABLE: i32 = 1;
+const SELECTOR_WRITABLE: i32 = 2;
+const SELECTOR_ERROR: i32 = 4;
+const SELECTOR_HANGUP: i32 = 8;
+
+// Operations for selector_ctl.
+const SELECTOR_ADD: i32 = 0;
+const SELECTOR_MODIFY: i32 = 1;
+const SELECTOR_DELETE: i32 = 2;
+
+#[cfg(target_os = "linux")]
+pub extern "C" fn selector_create() -> i32 {
+    let fd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+
+    if fd == -1 {
+        set_last_error(io::Error::last_os_error());
+    }
+
+    fd
+}
+
+#[cfg(target_os = "linux")]
+pub extern "C" fn selector_ctl(selector: i32, op: i32, fd: i32, interest: i32, token: i64) -> bool {
+    let op = match op {
+        SELECTOR_ADD => libc::EPOLL_CTL_ADD,
+        SELECTOR_MODIFY => libc::EPOLL_CTL_MOD,
+        SELECTOR_DELETE => libc::EPOLL_CTL_DEL,
+        _ => {
+            set_last_error(io::Error::new(ErrorKind::InvalidInput, "invalid operation"));
+            return false;
+        }
+    };
+
+    let mut events = libc::EPOLLRDHUP as u32;
+
+    if interest & SELECTOR_READABLE != 0 {
+        events |= libc::EPOLLIN as u32;
+    }
+
+    if interest & SELECTOR_WRITABLE != 0 {
+        events |= libc::EPOLLOUT as u32;
+    }
+
+    let mut event = libc::epoll_event {
+        events,
+        u64: token as u64,
+    };
+
+    if unsafe { libc::epoll_ctl(selector, op, fd, &mut event) } == -1 {
+        set_last_error(io::Error::last_os_error());
+        return false;
+    }
+
+    true
+}
+
+// Waits for events and stores their tokens and flags in the arrays. Returns
+// the number of events or -1 on failure, a negative timeout waits forever.
+#[cfg(target_os = "linux")]
+pub extern "C" fn selector_wait(
+    selector: i32,
+    mut tokens: Handle<Int64Array>,
+    mut flags: Handle<Int32Array>,
+    timeout_ms: i64,
+) -> i32 {
+    let capacity = tokens.len().min(flags.len());
+    let timeout_ms = if timeout_ms < 0 {
+        -1
+    } else {
+        timeout_ms.min(i32::MAX as i64) as i32
+    };
+
+    let mut events = vec![libc::epoll_event { events: 0, u64: 0 }; capacity.max(1)];
+
+    let result = parked_scope(|| unsafe {
+        libc::epoll_wait(
+            selector,
+            events.as_mut_ptr(),
+            capacity.max(1) as i32,
+            timeout_ms,
+        )
+    });
+
+    if result == -1 {
+        set_last_error(io::Error::last_os_error());
+        return -1;
+    }
+
+    for (idx, event) in events.iter().take(result as usize).enumerate() {
+        let events = event.events as i32;
+        let mut event_flags = 0;
+
+        if events & libc::EPOLLIN != 0 {
+            event_flags |= SELECTOR_READABLE;
+        }
+
+        if events & libc::EPOLLOUT != 0 {
+            event_flags |= SELECTOR_WRITABLE;
+        }
+
+        if events & libc::EPOLLERR != 0 {
+            event_flags |= SELECTOR_ERROR;
+        }
+
+        if events & (libc::EPOLLHUP | libc::EPOLLRDHUP) != 0 {
+            event_flags |= SELECTOR_HANGUP;
+        }
+
+        tokens.set_at(idx, event.u64 as i64);
+        flags.set_at(idx, event_flags);
+    }
+
+    result
+}
+
+#[cfg(not(target_os = "linux"))]
+pub extern "C" fn selector_create() -> i32 {
+    set_last_error(io::Error::new(
+        ErrorKind::Unsupported,
+        "selector is only supported on Linux",
+    ));
+    -1
+}
+
+#[cfg(not(target_os = "linux"))]
+pub extern "C" fn selector_ctl(
+    _selector: i32,
+    _op: i32,
+    _fd: i32,
+    _interest: i32,
+    _token: i64,
+) -> bool {
+    set_last_error(io::Error::new(
+        ErrorKind::Unsupported,
+        "selector is only supported on Linux",
+    ));
+    false
+}
+
+#[cfg(not(target_os = "linux"))]
+pub extern "C" fn selector_wait(
+    _selector: i32,
+    _tokens: Handle<Int64Array>,
+    _flags: Handle<Int32Array>,
+    _timeout_ms: i64,
+) -> i32 {
+    set_last_error(io::Error::new(
+        ErrorKind::Unsupported,
+        "selector is only supported on Linux",
+    ));
+    -1
+}
+
+pub extern "C" fn selector_close(selector: i32) {
+    parked_scope(|| unsafe {
+        libc::close(selector);
+    });
+}
+
+pub extern "C" fn udp_bind(addr: Handle<Str>) -> i32 {
+    let addr = String::from(addr.content_utf8());
+    parked_scope(|| match UdpSocket::bind(&addr) {
+        Ok(socket) => socket.into_raw_fd(),
+        Err(err) => {
+            set_last_error(err);
             -1
+        }
+    })
+}
+
+pub extern "C" fn udp_connect(fd: i32, addr: Handle<Str>) -> bool {
+    let addr = String::from(addr.content_utf8());
+    let result = parked_scope(|| {
+        let socket = unsafe { UdpSocket::from_raw_fd(fd) };
+        let result = socket.connect(&addr);
+        std::mem::forget(socket);
+        result
+    });
+
+    succeeded(result)
+}
+
+pub extern "C" fn udp_send_to(
+    fd: i32,
+    array: Handle<UInt8Array>,
+    offset: i64,
+    len: i64,
+    addr: Handle<Str>,
+) -> i64 {
+    let (offset, len) = match checked_range(array.slice().len(), offset, len) {
+        Some(range) => range,
+        None => {
+            set_last_error(invalid_range());
+            return -1;
+        }
+    };
+
+    let buffer = Vec::from(&array.slice()[offset..offset + len]);
+    let addr = String::from(addr.content_utf8());
+
+    parked_scope(|| {
+        let socket = unsafe { UdpSocket::from_raw_fd(fd) };
+        let bytes = match socket.send_to(&buffer, &addr) {
+            Ok(bytes) => bytes as i64,
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
+        };
+        std::mem::forget(socket);
+        bytes
+    })
+}
+
+// Receives a datagram into the array, stores its size in `size` and returns
+// the sender's address.
+pub extern "C" fn udp_recv_from(
+    fd: i32,
+    mut array: Handle<UInt8Array>,
+    offset: i64,
+    len: i64,
+    mut size: Handle<Int64Array>,
+) -> Ref<Str> {
+    let (offset, len) = match checked_range(array.slice().len(), offset, len) {
+        Some(range) if size.len() >= 1 => range,
+        _ => {
+            set_last_error(invalid_range());
+            return Ref::null();
+        }
+    };
+
+    let mut buffer = vec![0; len];
+
+    let result = parked_scope(|| {
+        let socket = unsafe { UdpSocket::from_raw_fd(fd) };
+        let result = socket.recv_from(&mut buffer);
+        std::mem::forget(socket);
+        result
+    });
+
+    let (bytes, addr) = match result {
+        Ok(result) => result,
+        Err(err) => {
+            set_last_error(err);
+            return Ref::null();
+        }
+    };
+
+    for (i, &byte) in buffer.iter().take(bytes).enumerate() {
+        array.set_at(offset + i, byte);
+    }
+
+    size.set_at(0, bytes as i64);
+    socket_addr_to_str(Ok(addr))
+}
+
+pub extern "C" fn unix_connect(path: Handle<Str>) -> i32 {
+    let path = String::from(path.content_utf8());
+    parked_scope(|| match UnixStream::connect(&path) {
+        Ok(stream) => stream.into_raw_fd(),
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    })
+}
+
+pub extern "C" fn unix_bind(path: Handle<Str>) -> i32 {
+    let path = String::from(path.content_utf8());
+    parked_scope(|| match UnixListener::bind(&path) {
+        Ok(listener) => listener.into_raw_fd(),
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    })
+}
+
+pub extern "C" fn unix_accept(fd: i32) -> i32 {
+    parked_scope(|| {
+        let listener = unsafe { UnixListener::from_raw_fd(fd) };
+        let result = match listener.accept() {
+            Ok((stream, _)) => stream.into_raw_fd(),
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
         };
         std::mem::forget(listener);
         result
@@ -1,6 +1,7 @@
 use parking_lot::{Condvar, Mutex};
 use std::cell::RefCell;
 use std::convert::From;
+use std::mem;
 use std::ptr;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -8,7 +9,7 @@ use std::sync::Arc;
 use crate::gc::{tlab, Address, Region, K};
 use crate::handle::HandleMemory;
 use crate::object::{alloc, Header, Ref};
-use crate::stack::DoraToNativeInfo;
+use crate::stack::{DoraToNativeInfo, NativeStacktrace};
 use crate::vm::{get_vm, VM};
 
 pub const STACK_SIZE: usize = 500 * K;
@@ -69,6 +70,10 @@ impl Threads {
         assert!(thread.is_parked());
         parked_scope(|| {
             let mut threads = self.threads.lock();
+            let vm = get_vm();
+            thread
+                .tld
+                .set_concurrent_marking(vm.gc.concurrent_marking_active());
             threads.push(thread);
         });
     }
@@ -137,6 +142,7 @@ pub struct DoraThread {
     pub state: AtomicUsize,
     join_data: JoinData,
     blocking_data: BlockingData,
+    catch_data: CatchData,
 }
 
 unsafe impl Sync for DoraThread {}
@@ -155,6 +161,7 @@ impl DoraThread {
             state: AtomicUsize::new(initial_state as usize),
             join_data: JoinData::new(),
             blocking_data: BlockingData::new(),
+            catch_data: CatchData::new(),
         })
     }
 
@@ -325,6 +332,52 @@ impl DoraThread {
             }
         });
     }
+
+    pub fn is_catching_failures(&self) -> bool {
+        self.catch_data.sp.load(Ordering::Relaxed) != 0
+    }
+
+    // Runs the compiled function `fct` and returns the failure when it is
+    // left through `unwind_failure` instead of returning normally.
+    pub fn catch_failure(&self, vm: &VM, fct: Address) -> Result<(), CaughtFailure> {
+        assert!(!self.is_catching_failures());
+
+        let dtn = self.dtn();
+        let borders = self.handles.borders();
+        self.handles.push_border();
+
+        let stub: extern "C" fn(Address, Address, Address) =
+            unsafe { mem::transmute(vm.stubs.dora_catch_entry()) };
+        stub(
+            self.tld_address(),
+            fct,
+            Address::from_ptr(&self.catch_data.sp),
+        );
+
+        // After unwinding, the DTNs and handles of the skipped native
+        // frames are still registered.
+        self.catch_data.sp.store(0, Ordering::Relaxed);
+        self.set_dtn(dtn);
+        self.handles.pop_borders_to(borders);
+
+        match self.catch_data.failure.lock().take() {
+            Some(failure) => Err(failure),
+            None => Ok(()),
+        }
+    }
+
+    // Leaves the function run by `catch_failure` on this thread and makes
+    // it return the failure. No destructors of the skipped frames are run,
+    // so the caller must not hold any locks.
+    pub fn unwind_failure(&self, vm: &VM, failure: CaughtFailure) -> ! {
+        let sp = self.catch_data.sp.load(Ordering::Relaxed);
+        assert!(sp != 0);
+
+        *self.catch_data.failure.lock() = Some(failure);
+
+        let stub: extern "C" fn(Address) -> ! = unsafe { mem::transmute(vm.stubs.unwind()) };
+        stub(sp.into())
+    }
 }
 
 pub fn parked_scope<F, R>(callback: F) -> R
@@ -355,6 +408,26 @@ impl JoinData {
     }
 }
 
+pub struct CaughtFailure {
+    pub message: String,
+    pub stacktrace: NativeStacktrace,
+}
+
+struct CatchData {
+    // stack pointer saved by the catch entry stub, 0 while not catching
+    sp: AtomicUsize,
+    failure: Mutex<Option<CaughtFailure>>,
+}
+
+impl CatchData {
+    fn new() -> CatchData {
+        CatchData {
+            sp: AtomicUsize::new(0),
+            failure: Mutex::new(None),
+        }
+    }
+}
+
 struct BlockingData {
     blocking: Mutex<(bool, DoraThreadPtr)>,
     cv_blocking: Condvar,
@@ -421,6 +494,7 @@ pub struct ThreadLocalData {
     tlab_top: AtomicUsize,
     tlab_end: AtomicUsize,
     concurrent_marking: AtomicBool,
+    pre_barrier_value: AtomicUsize,
     stack_limit: AtomicUsize,
     safepoint_requested: AtomicBool,
     dtn: AtomicUsize,
@@ -433,6 +507,7 @@ impl ThreadLocalData {
             tlab_top: AtomicUsize::new(0),
             tlab_end: AtomicUsize::new(0),
             concurrent_marking: AtomicBool::new(false),
+            pre_barrier_value: AtomicUsize::new(0),
             stack_limit: AtomicUsize::new(0),
             safepoint_requested: AtomicBool::new(false),
             dtn: AtomicUsize::new(0),
@@ -461,6 +536,10 @@ impl ThreadLocalData {
         Region::new(tlab_top.into(), tlab_end.into())
     }
 
+    pub fn set_concurrent_marking(&self, value: bool) {
+        self.concurrent_marking.store(value, Ordering::Relaxed);
+    }
+
     pub fn set_stack_limit(&self, stack_limit: Address) {
         self.stack_limit
             .store(stack_limit.to_usize(), Ordering::Relaxed);
@@ -483,6 +562,10 @@ impl ThreadLocalData {
         offset_of!(ThreadLocalData, concurrent_marking) as i32
     }
 
+    pub fn pre_barrier_value_offset() -> i32 {
+        offset_of!(ThreadLocalData, pre_barrier_value) as i32
+    }
+
     pub fn safepoint_requested_offset() -> i32 {
         offset_of!(ThreadLocalData, safepoint_requested) as i32
     }
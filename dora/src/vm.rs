@@ -20,8 +20,8 @@ use crate::language::ty::SourceTypeArray;
 use crate::stack::DoraToNativeInfo;
 use crate::threads::ManagedThread;
 use crate::threads::{
-    current_thread, deinit_current_thread, init_current_thread, DoraThread, ThreadState, Threads,
-    STACK_SIZE,
+    current_thread, deinit_current_thread, init_current_thread, CaughtFailure, DoraThread,
+    ThreadState, Threads, STACK_SIZE,
 };
 use crate::utils::GrowableVecNonIter;
 use crate::utils::{GrowableVec, MutableVec};
@@ -37,7 +37,7 @@ pub use self::code::{
     RelocationTable, CODE_ALIGNMENT,
 };
 pub use self::code_map::CodeMap;
-pub use self::compilation::CompilationDatabase;
+pub use self::compilation::{CompilationDatabase, HotnessCounter, TierState};
 pub use self::enums::{EnumInstance, EnumInstanceId, EnumLayout};
 use self::globals::GlobalVariableMemory;
 pub use self::known::{
@@ -368,13 +368,9 @@ impl VM {
         fct(tld, ptr)
     }
 
-    pub fn run_test(&self, fct_id: FctDefinitionId) {
-        let tld = current_thread().tld_address();
+    pub fn run_test(&self, fct_id: FctDefinitionId) -> Result<(), CaughtFailure> {
         let ptr = self.ensure_compiled(fct_id);
-        let dora_stub_address = self.stubs.dora_entry();
-        let fct: extern "C" fn(Address, Address) -> i32 =
-            unsafe { mem::transmute(dora_stub_address) };
-        fct(tld, ptr);
+        current_thread().catch_failure(self, ptr)
     }
 
     pub fn ensure_compiled(&self, fct_id: FctDefinitionId) -> Address {
@@ -430,6 +426,7 @@ impl VM {
 
 impl Drop for VM {
     fn drop(&mut self) {
+        self.gc.shutdown();
         self.gc.drop_all_native_code_objects();
     }
 }
@@ -513,3 +510,48 @@ where
 
     result
 }
+
+// Runs the callback on a new mutator thread which is registered with the
+// VM like threads spawned from Dora code. The returned thread can be used
+// to wait for the callback to finish with `DoraThread::join`.
+pub fn execute_on_thread<F>(vm: &VM, callback: F) -> Arc<DoraThread>
+where
+    F: FnOnce() + Send + 'static,
+{
+    // Create new thread in Parked state, it unparks itself once running.
+    let native_thread = DoraThread::new(vm, ThreadState::Parked);
+    vm.threads.add_thread(native_thread.clone());
+
+    let thread = native_thread.clone();
+
+    std::thread::spawn(move || {
+        let vm = get_vm();
+        let native_thread = thread.clone();
+        let thread = init_current_thread(thread);
+
+        let stack_top = stack_pointer();
+        let stack_limit = stack_top.sub(STACK_SIZE);
+        thread.tld.set_stack_limit(stack_limit);
+
+        thread.unpark(vm);
+
+        let mut managed_thread = ManagedThread::alloc(vm);
+        managed_thread.install_native_thread(&native_thread);
+
+        let managed_thread_handle = thread.handles.handle(managed_thread);
+
+        thread
+            .tld
+            .set_managed_thread_handle(managed_thread_handle.location());
+
+        callback();
+
+        vm.threads.remove_current_thread();
+
+        // notify threads waiting in join() for this thread's end
+        thread.stop();
+        deinit_current_thread();
+    });
+
+    native_thread
+}
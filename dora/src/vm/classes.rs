@@ -9,7 +9,7 @@ use crate::utils::Id;
 use crate::vm::{add_ref_fields, VM};
 use crate::vtable::VTableBox;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ClassInstanceId(usize);
 
 impl ClassInstanceId {
@@ -75,6 +75,22 @@ impl ClassInstance {
             _ => None,
         }
     }
+
+    pub fn name(&self, vm: &VM) -> String {
+        match &self.kind {
+            ShapeKind::Class(cls_id, type_params) => {
+                SourceType::Class(*cls_id, type_params.clone()).name(vm)
+            }
+            ShapeKind::Enum(enum_id, type_params) => {
+                SourceType::Enum(*enum_id, type_params.clone()).name(vm)
+            }
+            ShapeKind::Lambda(..) => "<lambda>".into(),
+            ShapeKind::TraitObject { object_ty, .. } => {
+                format!("<trait object for {}>", object_ty.name(vm))
+            }
+            ShapeKind::Builtin => "<builtin>".into(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +31,7 @@ pub enum CodeKind {
     DoraStub,
     GuardCheckStub,
     SafepointStub,
+    PreWriteBarrierStub,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
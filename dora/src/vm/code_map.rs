@@ -45,6 +45,7 @@ impl CodeMap {
                 CodeKind::DoraStub => println!("dora_stub"),
                 CodeKind::GuardCheckStub => println!("guard_check_stub"),
                 CodeKind::SafepointStub => println!("safepoint_stub"),
+                CodeKind::PreWriteBarrierStub => println!("pre_write_barrier_stub"),
             }
         }
 
@@ -1,10 +1,12 @@
-use parking_lot::{Condvar, Mutex};
+use parking_lot::{Condvar, Mutex, MutexGuard};
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
 
 use crate::gc::Address;
 use crate::language::sem_analysis::FctDefinitionId;
 use crate::language::ty::SourceTypeArray;
+use crate::threads::current_thread;
 use crate::vm::{CodeId, VM};
 
 #[derive(PartialEq, Debug)]
@@ -16,6 +18,7 @@ enum CompilationStatus {
 pub struct CompilationDatabase {
     inner: Mutex<HashMap<(FctDefinitionId, SourceTypeArray), CompilationStatus>>,
     cv_notify: Condvar,
+    hotness_counters: Mutex<HashMap<(FctDefinitionId, SourceTypeArray), Box<HotnessCounter>>>,
 }
 
 impl CompilationDatabase {
@@ -23,6 +26,7 @@ impl CompilationDatabase {
         CompilationDatabase {
             inner: Mutex::new(HashMap::new()),
             cv_notify: Condvar::new(),
+            hotness_counters: Mutex::new(HashMap::new()),
         }
     }
 
@@ -69,7 +73,14 @@ impl CompilationDatabase {
                     }
 
                     CompilationStatus::InProgress => {
+                        // Compilation with Boots runs Dora code and might need
+                        // a safepoint, don't block it while waiting. Unparking
+                        // waits for a running safepoint, the lock must not be
+                        // held in the meantime.
+                        let thread = current_thread();
+                        thread.park(vm);
                         self.cv_notify.wait(&mut inner);
+                        MutexGuard::unlocked(&mut inner, || thread.unpark(vm));
                     }
                 }
             } else {
@@ -92,4 +103,109 @@ impl CompilationDatabase {
 
         self.cv_notify.notify_all();
     }
+
+    // Replaces the code of an already compiled function, e.g. when a hot
+    // function was recompiled with the optimizing compiler.
+    pub fn replace_compilation(
+        &self,
+        id: FctDefinitionId,
+        type_params: SourceTypeArray,
+        code_id: CodeId,
+    ) {
+        let mut inner = self.inner.lock();
+
+        let old_value = inner.insert((id, type_params), CompilationStatus::Compiled(code_id));
+        assert!(matches!(old_value, Some(CompilationStatus::Compiled(_))));
+    }
+
+    // Returns the hotness counter for the function. The counter is boxed, so
+    // its address stays valid and can be embedded into the generated code.
+    pub fn hotness_counter(&self, id: FctDefinitionId, type_params: SourceTypeArray) -> Address {
+        let mut hotness_counters = self.hotness_counters.lock();
+
+        let counter = hotness_counters
+            .entry((id, type_params.clone()))
+            .or_insert_with(|| Box::new(HotnessCounter::new(id, type_params)));
+
+        Address::from_ptr(&**counter as *const HotnessCounter)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TierState {
+    Baseline,
+    Optimizing,
+    Optimized(Address),
+    Failed,
+}
+
+// Counts invocations and loop iterations of a function compiled with the
+// baseline compiler. The generated code increments the counters directly,
+// once their sum reaches the threshold the function gets recompiled.
+#[repr(C)]
+pub struct HotnessCounter {
+    invocations: AtomicU32,
+    back_edges: AtomicU32,
+    pub fct_id: FctDefinitionId,
+    pub type_params: SourceTypeArray,
+    state: Mutex<TierState>,
+}
+
+impl HotnessCounter {
+    fn new(fct_id: FctDefinitionId, type_params: SourceTypeArray) -> HotnessCounter {
+        HotnessCounter {
+            invocations: AtomicU32::new(0),
+            back_edges: AtomicU32::new(0),
+            fct_id,
+            type_params,
+            state: Mutex::new(TierState::Baseline),
+        }
+    }
+
+    pub fn invocations_offset() -> i32 {
+        offset_of!(HotnessCounter, invocations) as i32
+    }
+
+    pub fn back_edges_offset() -> i32 {
+        offset_of!(HotnessCounter, back_edges) as i32
+    }
+
+    pub fn invocations(&self) -> u32 {
+        self.invocations.load(Ordering::Relaxed)
+    }
+
+    pub fn back_edges(&self) -> u32 {
+        self.back_edges.load(Ordering::Relaxed)
+    }
+
+    pub fn reset(&self) {
+        self.invocations.store(0, Ordering::Relaxed);
+        self.back_edges.store(0, Ordering::Relaxed);
+    }
+
+    pub fn state(&self) -> TierState {
+        *self.state.lock()
+    }
+
+    // Returns true if the caller should start the optimizing compilation.
+    pub fn start_optimization(&self) -> bool {
+        let mut state = self.state.lock();
+
+        if *state == TierState::Baseline {
+            *state = TierState::Optimizing;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn finish_optimization(&self, result: Option<Address>) {
+        let mut state = self.state.lock();
+        assert_eq!(*state, TierState::Optimizing);
+
+        *state = match result {
+            Some(address) => TierState::Optimized(address),
+            None => TierState::Failed,
+        };
+    }
 }
@@ -67,16 +67,24 @@ impl KnownElements {
 #[derive(Debug)]
 pub struct KnownEnums {
     pub option: Option<EnumDefinitionId>,
+    pub result: Option<EnumDefinitionId>,
 }
 
 impl KnownEnums {
     pub fn new() -> KnownEnums {
-        KnownEnums { option: None }
+        KnownEnums {
+            option: None,
+            result: None,
+        }
     }
 
     pub fn option(&self) -> EnumDefinitionId {
         self.option.expect("uninitialized")
     }
+
+    pub fn result(&self) -> EnumDefinitionId {
+        self.result.expect("uninitialized")
+    }
 }
 
 #[derive(Debug)]
@@ -206,6 +214,7 @@ pub struct KnownTraits {
     pub equals: Option<TraitDefinitionId>,
     pub comparable: Option<TraitDefinitionId>,
     pub stringable: Option<TraitDefinitionId>,
+    pub formattable: Option<TraitDefinitionId>,
     pub iterator: Option<TraitDefinitionId>,
     pub zero: Option<TraitDefinitionId>,
 }
@@ -216,6 +225,7 @@ impl KnownTraits {
             equals: None,
             comparable: None,
             stringable: None,
+            formattable: None,
             iterator: None,
             zero: None,
         }
@@ -233,6 +243,10 @@ impl KnownTraits {
         self.stringable.expect("uninitialized")
     }
 
+    pub fn formattable(&self) -> TraitDefinitionId {
+        self.formattable.expect("uninitialized")
+    }
+
     pub fn iterator(&self) -> TraitDefinitionId {
         self.iterator.expect("uninitialized")
     }
@@ -248,6 +262,8 @@ pub struct KnownAnnotations {
     pub pub_: Option<AnnotationDefinitionId>,
     pub static_: Option<AnnotationDefinitionId>,
     pub test: Option<AnnotationDefinitionId>,
+    pub serial: Option<AnnotationDefinitionId>,
+    pub allow: Option<AnnotationDefinitionId>,
     pub cannon: Option<AnnotationDefinitionId>,
     pub optimize_immediately: Option<AnnotationDefinitionId>,
 }
@@ -259,6 +275,8 @@ impl KnownAnnotations {
             pub_: None,
             static_: None,
             test: None,
+            serial: None,
+            allow: None,
             cannon: None,
             optimize_immediately: None,
         }
@@ -280,6 +298,14 @@ impl KnownAnnotations {
         self.test.expect("uninitialized")
     }
 
+    pub fn serial(&self) -> AnnotationDefinitionId {
+        self.serial.expect("uninitialized")
+    }
+
+    pub fn allow(&self) -> AnnotationDefinitionId {
+        self.allow.expect("uninitialized")
+    }
+
     pub fn cannon(&self) -> AnnotationDefinitionId {
         self.cannon.expect("uninitialized")
     }
@@ -294,6 +320,9 @@ pub struct KnownFunctions {
     pub string_buffer_empty: Option<FctDefinitionId>,
     pub string_buffer_append: Option<FctDefinitionId>,
     pub string_buffer_to_string: Option<FctDefinitionId>,
+    pub formatter_from_spec: Option<FctDefinitionId>,
+    pub formatter_pad: Option<FctDefinitionId>,
+    pub string_equals: Option<FctDefinitionId>,
     pub assert: Option<FctDefinitionId>,
     pub option_is_some: Option<FctDefinitionId>,
     pub option_is_none: Option<FctDefinitionId>,
@@ -308,6 +337,9 @@ impl KnownFunctions {
             string_buffer_empty: None,
             string_buffer_append: None,
             string_buffer_to_string: None,
+            formatter_from_spec: None,
+            formatter_pad: None,
+            string_equals: None,
             assert: None,
             option_is_none: None,
             option_is_some: None,
@@ -329,6 +361,18 @@ impl KnownFunctions {
         self.string_buffer_to_string.expect("uninitialized")
     }
 
+    pub fn formatter_from_spec(&self) -> FctDefinitionId {
+        self.formatter_from_spec.expect("uninitialized")
+    }
+
+    pub fn formatter_pad(&self) -> FctDefinitionId {
+        self.formatter_pad.expect("uninitialized")
+    }
+
+    pub fn string_equals(&self) -> FctDefinitionId {
+        self.string_equals.expect("uninitialized")
+    }
+
     pub fn assert(&self) -> FctDefinitionId {
         self.assert.expect("uninitialized")
     }
@@ -1,6 +1,9 @@
 use crate::compiler::dora_entry_stub;
 use crate::compiler::dora_exit_stubs::{self, NativeFct, NativeFctKind};
 use crate::compiler::lazy_compilation_stub;
+use crate::compiler::pre_write_barrier_stub;
+use crate::compiler::tier_up_stub;
+use crate::compiler::unwind_stubs;
 use crate::gc::Address;
 use crate::language::ty::SourceType;
 use crate::safepoint;
@@ -9,20 +12,28 @@ use crate::vm::VM;
 
 pub struct Stubs {
     compile: Option<Address>,
+    tier_up: Option<Address>,
     dora_entry: Option<Address>,
+    dora_catch_entry: Option<Address>,
+    unwind: Option<Address>,
     trap: Option<Address>,
     stack_overflow: Option<Address>,
     safepoint: Option<Address>,
+    pre_write_barrier: Option<Address>,
 }
 
 impl Stubs {
     pub fn new() -> Stubs {
         Stubs {
             compile: None,
+            tier_up: None,
             dora_entry: None,
+            dora_catch_entry: None,
+            unwind: None,
             trap: None,
             stack_overflow: None,
             safepoint: None,
+            pre_write_barrier: None,
         }
     }
 
@@ -30,10 +41,22 @@ impl Stubs {
         self.compile.expect("uninitialized field")
     }
 
+    pub fn tier_up(&self) -> Address {
+        self.tier_up.expect("uninitialized field")
+    }
+
     pub fn dora_entry(&self) -> Address {
         self.dora_entry.expect("uninitialized field")
     }
 
+    pub fn dora_catch_entry(&self) -> Address {
+        self.dora_catch_entry.expect("uninitialized field")
+    }
+
+    pub fn unwind(&self) -> Address {
+        self.unwind.expect("uninitialized field")
+    }
+
     pub fn trap(&self) -> Address {
         self.trap.expect("uninitialized field")
     }
@@ -45,10 +68,16 @@ impl Stubs {
     pub fn safepoint(&self) -> Address {
         self.safepoint.expect("uninitialized field")
     }
+
+    pub fn pre_write_barrier(&self) -> Address {
+        self.pre_write_barrier.expect("uninitialized field")
+    }
 }
 
 pub fn setup_stubs(vm: &mut VM) {
     vm.stubs.dora_entry = Some(dora_entry_stub::install(vm).instruction_start());
+    vm.stubs.dora_catch_entry = Some(unwind_stubs::install_catch_entry(vm).instruction_start());
+    vm.stubs.unwind = Some(unwind_stubs::install_unwind(vm).instruction_start());
 
     let ifct = NativeFct {
         fctptr: Address::from_ptr(stdlib::trap as *const u8),
@@ -60,6 +89,7 @@ pub fn setup_stubs(vm: &mut VM) {
     vm.stubs.trap = Some(code.instruction_start());
 
     vm.stubs.compile = Some(lazy_compilation_stub::generate(vm).instruction_start());
+    vm.stubs.tier_up = Some(tier_up_stub::generate(vm).instruction_start());
 
     let ifct = NativeFct {
         fctptr: Address::from_ptr(safepoint::stack_overflow as *const u8),
@@ -78,4 +108,8 @@ pub fn setup_stubs(vm: &mut VM) {
     };
     let code = dora_exit_stubs::generate(vm, ifct, false);
     vm.stubs.safepoint = Some(code.instruction_start());
+
+    if vm.gc.needs_pre_write_barrier() {
+        vm.stubs.pre_write_barrier = Some(pre_write_barrier_stub::generate(vm).instruction_start());
+    }
 }
@@ -126,7 +126,7 @@ def binary_path
 end
 
 class TestUtility
-  def self.spawn_with_timeout(env, cmd, timeout)
+  def self.spawn_with_timeout(env, cmd, timeout, input = nil)
     result = {
       :pid     => nil,
       :status  => nil,
@@ -142,6 +142,7 @@ class TestUtility
       Timeout.timeout(timeout) do
         result[:pid] = wait_thr.pid
 
+        stdin.write(input) if input
         stdin.close
         out_reader = Thread.new { stdout.read }
         err_reader = Thread.new { stderr.read }
@@ -184,6 +185,8 @@ class TestCase
                 :test_file,
                 :vm_args,
                 :args,
+                :stdin,
+                :run_tests,
                 :expectation,
                 :result,
                 :timeout,
@@ -193,6 +196,7 @@ class TestCase
     self.expectation = opts.fetch(:expectation, TestExpectation.new(fail: false))
     self.file = self.test_file = file
     self.args = self.vm_args = ""
+    self.run_tests = false
     self.configs = [:default]
   end
 
@@ -430,6 +434,7 @@ def run_test(test_case, config, mutex)
   end
 
   cmdline = "#{binary_path}"
+  cmdline << " test" if test_case.run_tests
   cmdline << " #{$all_configs[config]}" unless $all_configs[config].empty?
   cmdline << " #{test_case.vm_args}" unless test_case.vm_args.empty?
   cmdline << " #{test_case.test_file}"
@@ -437,7 +442,7 @@ def run_test(test_case, config, mutex)
 
   puts cmdline if $verbose
 
-  process_result = TestUtility.spawn_with_timeout($env, cmdline, test_case.get_timeout)
+  process_result = TestUtility.spawn_with_timeout($env, cmdline, test_case.get_timeout, test_case.stdin)
   result = check_test_run_result(test_case, process_result)
 
   if !$capture || result != true
@@ -527,6 +532,13 @@ def read_error_message(content)
     if line == "1 error found." || line == "error during parsing."
       return position, message
 
+    elsif (m = line.match(/^error: (.+)$/)) != nil
+      position = nil
+      message = m[1].to_s
+
+    elsif message && position.nil? && (m = line.match(/^--> (.+):(\d+:\d+)$/)) != nil
+      position = m[2].to_s
+
     elsif (m = line.match(/^error in (.+) at (\d+:\d+): (.+)$/)) != nil
       position = m[2].to_s
       message = m[3].to_s
@@ -664,6 +676,12 @@ def parse_test_file(file)
       when "stderr"
         test_case.expectation.stderr = arguments[1]
 
+      when "stdin"
+        test_case.stdin = arguments[1]
+
+      when "test"
+        test_case.run_tests = true
+
       when "config"
         config = arguments[1].intern
         raise "unknown config #{arguments[1]}" unless $all_configs.include?(config)
@@ -676,7 +694,7 @@ def parse_test_file(file)
         test_case.vm_args = arguments[1..-1].join(" ")
 
       when "boots"
-        test_case.vm_args += '--package boots dora-boots/boots.dora --gc-verify'
+        test_case.vm_args += ' --package boots dora-boots/boots.dora --gc-verify'
 
       when "timeout"
         test_case.timeout = arguments[1].to_i